@@ -0,0 +1,30 @@
+/*
+ * Feeds arbitrary input to the lexer and the parser, which must never panic. Diagnostics are rendered as well, since
+ * their spans can point anywhere into the source.
+ *
+ * Run with `cargo fuzz run parse` from the repository root. `corpus/parse` holds inputs that used to panic or
+ * overflow the stack.
+ *
+ * Newton (C) 2023
+ */
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use newton_rs::{try_parse, Source};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(code) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let source = Source::new("fuzz", code);
+    let Err(diagnostics) = try_parse(&source) else {
+        return;
+    };
+
+    for diagnostic in diagnostics {
+        diagnostic.render(&source);
+        diagnostic.to_json(&source);
+    }
+});
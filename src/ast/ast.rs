@@ -1,20 +1,24 @@
 use crate::lexer::token::*;
 use crate::parser::error::*;
 use crate::parser::span::*;
+use crate::semantic::consteval::*;
 use crate::types::types::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ExpressionKind<'a> {
     Error(ParseError<'a>),
     NullLiteral,
     DecLiteral(&'a str),
     FloatLiteral(&'a str),
-    StringLiteral(&'a str),
+    StringLiteral(std::borrow::Cow<'a, str>),
     Char(&'a str),
+    BoolLiteral(bool),
+    Constant(ConstValue),
     Reference(Spanned<TokenType<'a>>, Box<Spanned<Expression<'a>>>),
     Dereference(Spanned<TokenType<'a>>, Box<Spanned<Expression<'a>>>),
     Negate(Spanned<TokenType<'a>>, Box<Spanned<Expression<'a>>>),
     BoolNegate(Spanned<TokenType<'a>>, Box<Spanned<Expression<'a>>>),
+    BitNot(Spanned<TokenType<'a>>, Box<Spanned<Expression<'a>>>),
     Binary(
         Box<Spanned<Expression<'a>>>,
         Spanned<TokenType<'a>>,
@@ -33,6 +37,19 @@ pub enum ExpressionKind<'a> {
     Identifier(&'a str),
     New(Box<Spanned<Expression<'a>>>),
     SizeOf(Type<'a>),
+    // The size of the type of an expression, which is never evaluated.
+    SizeOfValue(Box<Spanned<Expression<'a>>>),
+    ArrayLiteral(Vec<Spanned<Expression<'a>>>),
+    // `(a, b)`, always with at least two elements since `(a)` is a grouping.
+    Tuple(Vec<Spanned<Expression<'a>>>),
+    // An expression in parentheses, spanning them. It is typed and evaluated like the expression itself.
+    Grouping(Box<Spanned<Expression<'a>>>),
+    // `{ let a = 1; a + 1 }`, whose value is the expression it ends with, if that has no semicolon after it. A block
+    // without one is `void`.
+    Block {
+        body: Block<'a>,
+        value: Option<Box<Spanned<Expression<'a>>>>,
+    },
 
     Assignment {
         left: Box<Spanned<Expression<'a>>>,
@@ -40,6 +57,14 @@ pub enum ExpressionKind<'a> {
         value: Box<Spanned<Expression<'a>>>,
     },
 
+    // `i++`, `--i` and the like, where the operator tells incrementing and decrementing apart. The operand is changed
+    // in place, so it has to be assignable, but the increment itself is not.
+    Increment {
+        operator: Spanned<TokenType<'a>>,
+        operand: Box<Spanned<Expression<'a>>>,
+        is_postfix: bool,
+    },
+
     Call {
         module: &'a str,
         callee: Box<Spanned<Expression<'a>>>,
@@ -51,6 +76,12 @@ pub enum ExpressionKind<'a> {
         identifier: Spanned<&'a str>,
     },
 
+    // `array[index]`, which chains for nested arrays like `grid[y][x]`.
+    Index {
+        array: Box<Spanned<Expression<'a>>>,
+        index: Box<Spanned<Expression<'a>>>,
+    },
+
     StructInitialization {
         identifier: Spanned<UserIdentifier<'a>>,
         fields: InitializerList<'a>,
@@ -113,11 +144,16 @@ impl<'a> Expression<'a> {
             | ExpressionKind::FloatLiteral(_)
             | ExpressionKind::StringLiteral(_)
             | ExpressionKind::Char(_)
+            | ExpressionKind::BoolLiteral(_)
+            | ExpressionKind::Constant(_)
             | ExpressionKind::SizeOf(_)
             | ExpressionKind::Identifier(_) => vec![],
             ExpressionKind::New(expr)
             | ExpressionKind::Negate(_, expr)
             | ExpressionKind::BoolNegate(_, expr)
+            | ExpressionKind::BitNot(_, expr)
+            | ExpressionKind::SizeOfValue(expr)
+            | ExpressionKind::Grouping(expr)
             | ExpressionKind::Reference(_, expr)
             | ExpressionKind::Dereference(_, expr) => vec![&expr],
             ExpressionKind::Binary(left, _, right) => vec![&left, &right],
@@ -125,6 +161,9 @@ impl<'a> Expression<'a> {
             ExpressionKind::Cast(e, _, _) => vec![&e],
 
             ExpressionKind::Assignment { left, value, .. } => vec![&left, &value],
+            // The statements of the block are not expressions, only the value it ends with is.
+            ExpressionKind::Block { value, .. } => value.iter().map(|value| value.as_ref()).collect(),
+            ExpressionKind::Increment { operand, .. } => vec![&operand],
 
             ExpressionKind::Call {
                 arguments, callee, ..
@@ -145,6 +184,11 @@ impl<'a> Expression<'a> {
             }
 
             ExpressionKind::Access { left, .. } => vec![&left],
+            ExpressionKind::Index { array, index } => vec![&array, &index],
+
+            ExpressionKind::ArrayLiteral(elements) | ExpressionKind::Tuple(elements) => {
+                elements.iter().collect()
+            }
 
             ExpressionKind::StructInitialization { fields, .. } => {
                 fields.0.iter().map(|(_, e)| e).collect()
@@ -152,25 +196,109 @@ impl<'a> Expression<'a> {
         }
     }
 
-    pub fn is_r_value(&mut self) -> bool {
+    pub fn is_r_value(&self) -> bool {
         match self.kind {
-            ExpressionKind::Identifier(_) | ExpressionKind::Access { .. } => false,
+            ExpressionKind::Identifier(_)
+            | ExpressionKind::Access { .. }
+            | ExpressionKind::Index { .. }
+            | ExpressionKind::Dereference(..) => false,
+            ExpressionKind::Grouping(ref inner) => inner.node.is_r_value(),
             _ => true,
         }
     }
 
     #[inline]
-    pub fn is_l_value(&mut self) -> bool {
+    pub fn is_l_value(&self) -> bool {
         !self.is_r_value()
     }
 }
 
+// Chains like `a + b + c + ...` or `f()()()...` nest as deeply as they are long. Dropping each level from within the
+// level above it could overflow the stack, so the sub-expressions are taken out and dropped one after the other.
+impl<'a> Drop for Expression<'a> {
+    fn drop(&mut self) {
+        let mut pending = vec![];
+        self.take_sub_expressions(&mut pending);
+
+        while let Some(mut expression) = pending.pop() {
+            expression.take_sub_expressions(&mut pending);
+        }
+    }
+}
+
+impl<'a> Expression<'a> {
+    // Move every direct sub-expression into `taken`, leaving cheap placeholders behind.
+    fn take_sub_expressions(&mut self, taken: &mut Vec<Expression<'a>>) {
+        let mut take = |expression: &mut Spanned<Expression<'a>>| {
+            let placeholder = Expression::new(ExpressionKind::NullLiteral);
+            taken.push(std::mem::replace(&mut expression.node, placeholder));
+        };
+
+        match &mut self.kind {
+            ExpressionKind::New(expression)
+            | ExpressionKind::Negate(_, expression)
+            | ExpressionKind::BoolNegate(_, expression)
+            | ExpressionKind::BitNot(_, expression)
+            | ExpressionKind::SizeOfValue(expression)
+            | ExpressionKind::Grouping(expression)
+            | ExpressionKind::Reference(_, expression)
+            | ExpressionKind::Dereference(_, expression)
+            | ExpressionKind::Cast(expression, _, _)
+            | ExpressionKind::Increment {
+                operand: expression,
+                ..
+            }
+            | ExpressionKind::Access {
+                left: expression, ..
+            } => take(expression),
+
+            ExpressionKind::Binary(left, _, right)
+            | ExpressionKind::BoolBinary(left, _, right)
+            | ExpressionKind::Assignment {
+                left, value: right, ..
+            }
+            | ExpressionKind::Index {
+                array: left,
+                index: right,
+            } => {
+                take(left);
+                take(right);
+            }
+
+            ExpressionKind::Call {
+                callee, arguments, ..
+            } => {
+                take(callee);
+                arguments.0.iter_mut().for_each(take);
+            }
+
+            ExpressionKind::ArrayLiteral(elements) | ExpressionKind::Tuple(elements) => {
+                elements.iter_mut().for_each(take)
+            }
+            ExpressionKind::StructInitialization { fields, .. } => {
+                fields.0.iter_mut().for_each(|(_, value)| take(value))
+            }
+            ExpressionKind::Block {
+                value: Some(value), ..
+            } => take(value),
+
+            _ => {}
+        }
+    }
+}
+
 impl<'a> PartialEq for Expression<'a> {
     fn eq(&self, other: &Self) -> bool {
         self.kind.eq(&other.kind)
     }
 }
 
+impl<'a> std::hash::Hash for Expression<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+    }
+}
+
 impl<'a> std::fmt::Display for Expression<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match &self.kind {
@@ -178,13 +306,38 @@ impl<'a> std::fmt::Display for Expression<'a> {
             ExpressionKind::NullLiteral => write!(f, "null"),
             ExpressionKind::DecLiteral(lit)
             | ExpressionKind::FloatLiteral(lit)
-            | ExpressionKind::StringLiteral(lit)
             | ExpressionKind::Char(lit) => write!(f, "{lit}"),
+            ExpressionKind::StringLiteral(lit) => write!(f, "{lit}"),
+            ExpressionKind::BoolLiteral(value) => write!(f, "{value}"),
+            ExpressionKind::Constant(value) => write!(f, "{value}"),
             ExpressionKind::SizeOf(ty) => write!(f, "sizeof({ty})"),
+            ExpressionKind::SizeOfValue(expr) => write!(f, "sizeof({})", expr.node),
+            ExpressionKind::ArrayLiteral(elements) => write!(
+                f,
+                "[{}]",
+                elements
+                    .iter()
+                    .map(|element| element.node.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            ExpressionKind::Tuple(elements) => write!(
+                f,
+                "({})",
+                elements
+                    .iter()
+                    .map(|element| element.node.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            ExpressionKind::Grouping(expr) => write!(f, "({})", expr.node),
+            ExpressionKind::Block { value: Some(value), .. } => write!(f, "{{ ... {} }}", value.node),
+            ExpressionKind::Block { .. } => write!(f, "{{ ... }}"),
             ExpressionKind::New(expr) => write!(f, "new {}", expr.node),
             ExpressionKind::Negate(_, expr) | ExpressionKind::BoolNegate(_, expr) => {
                 write!(f, "-{}", expr.node)
             }
+            ExpressionKind::BitNot(_, expr) => write!(f, "~{}", expr.node),
             ExpressionKind::Reference(_, expr) => write!(f, "&{}", expr.node),
             ExpressionKind::Dereference(_, expr) => write!(f, "*{}", expr.node),
             ExpressionKind::Binary(l, op, r) | ExpressionKind::BoolBinary(l, op, r) => {
@@ -195,12 +348,21 @@ impl<'a> std::fmt::Display for Expression<'a> {
             ExpressionKind::Assignment { left, value, .. } => {
                 write!(f, "{} = {}", left.node, value.node)
             }
+            ExpressionKind::Increment {
+                operator,
+                operand,
+                is_postfix: true,
+            } => write!(f, "{}{}", operand.node, operator.node),
+            ExpressionKind::Increment {
+                operator, operand, ..
+            } => write!(f, "{}{}", operator.node, operand.node),
             ExpressionKind::Call {
                 callee, arguments, ..
             } => write!(f, "{}({})", callee.node, arguments),
             ExpressionKind::Access { left, identifier } => {
                 write!(f, "{}.{}", left.node, identifier.node)
             }
+            ExpressionKind::Index { array, index } => write!(f, "{}[{}]", array.node, index.node),
             ExpressionKind::StructInitialization { identifier, fields } => write!(
                 f,
                 "{} {{ {} }}",
@@ -216,13 +378,29 @@ impl<'a> std::fmt::Display for Expression<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Program<'a>(pub Vec<TopLevel<'a>>);
 
-#[derive(Debug, PartialEq, Eq, Default)]
-pub struct Block<'a>(pub Vec<Statement<'a>>);
+impl<'a> Program<'a> {
+    // A hash of what the program means rather than how it is written down. Spans are left out, so reformatting the
+    // code keeps the hash, while any other change to it does not. The hash is the same across runs, which build caches
+    // rely on to skip what did not change.
+    pub fn structural_hash(&self) -> u64 {
+        structural_hash(self)
+    }
+}
 
-#[derive(Debug, PartialEq, Eq)]
+// The statements of a block, along with the span from its opening to its closing brace.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Block<'a>(pub Vec<Statement<'a>>, pub Span);
+
+impl<'a> std::hash::Hash for Block<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Parameter<'a>(pub Spanned<&'a str>, pub Spanned<Type<'a>>);
 
 impl<'a> Parameter<'a> {
@@ -231,13 +409,13 @@ impl<'a> Parameter<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Hash)]
 pub struct ParameterList<'a> {
     pub varargs: bool,
     pub parameters: Vec<Parameter<'a>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ArgumentList<'a>(pub Vec<Spanned<Expression<'a>>>);
 
 impl<'a> std::fmt::Display for ArgumentList<'a> {
@@ -251,10 +429,10 @@ impl<'a> std::fmt::Display for ArgumentList<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct InitializerList<'a>(pub Vec<(Spanned<&'a str>, Spanned<Expression<'a>>)>);
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TopLevel<'a> {
     FunctionDeclaration {
         name: Spanned<&'a str>,
@@ -262,14 +440,36 @@ pub enum TopLevel<'a> {
         body: Block<'a>,
         return_type: Spanned<Type<'a>>,
         is_external: bool,
+        is_public: bool,
+        attributes: Vec<Attribute<'a>>,
     },
 
+    // `import "module";` brings in the whole module, `from "module" import a, b;` only the listed symbols.
     Import {
         name: Spanned<&'a str>,
+        symbols: Vec<Spanned<&'a str>>,
     },
 
     TypeDeclaration {
         ty: TypeDeclaration<'a>,
+        is_public: bool,
+        attributes: Vec<Attribute<'a>>,
+    },
+
+    // `extern let errno: i32;` declares a global defined outside of Newton, so it has a type, but never a value.
+    ExternVariable {
+        name: Spanned<&'a str>,
+        ty: Spanned<Type<'a>>,
+        is_public: bool,
+        attributes: Vec<Attribute<'a>>,
+    },
+
+    // `let SIZE = 4 * 1024;` at top level is a constant. Its value is computed at compile time, so it can only refer to
+    // literals and other constants of the module, which may also be declared further down.
+    Constant {
+        declaration: VariableDeclaration<'a>,
+        is_public: bool,
+        attributes: Vec<Attribute<'a>>,
     },
 
     Error {
@@ -277,10 +477,47 @@ pub enum TopLevel<'a> {
     },
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl<'a> TopLevel<'a> {
+    // Imports and errors never have attributes.
+    pub fn attributes(&self) -> &[Attribute<'a>] {
+        match self {
+            TopLevel::FunctionDeclaration { attributes, .. }
+            | TopLevel::TypeDeclaration { attributes, .. }
+            | TopLevel::ExternVariable { attributes, .. }
+            | TopLevel::Constant { attributes, .. } => attributes,
+
+            TopLevel::Import { .. } | TopLevel::Error { .. } => &[],
+        }
+    }
+
+    // Like `Program::structural_hash`, for a single declaration, so one function can be skipped without the others.
+    pub fn structural_hash(&self) -> u64 {
+        structural_hash(self)
+    }
+}
+
+// The hasher is created with fixed keys, unlike the ones of a `HashMap`, so the same value always gets the same hash.
+fn structural_hash<T: std::hash::Hash>(value: &T) -> u64 {
+    use std::hash::Hasher;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// An attribute attached to a declaration, written as `@[name]`, or as `@[name(argument, ...)]` for attributes that take
+// arguments, like `@[cfg(c)]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Attribute<'a> {
+    pub name: Spanned<&'a str>,
+    pub arguments: Vec<Spanned<&'a str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TypeDeclaration<'a> {
     StructDefinition {
         name: Spanned<&'a str>,
+        generic_parameters: Vec<Spanned<&'a str>>,
         fields: Vec<(Spanned<&'a str>, Spanned<Type<'a>>)>,
         methods: Vec<TopLevel<'a>>,
     },
@@ -301,17 +538,76 @@ pub enum TypeDeclaration<'a> {
     },
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Declarations are written the way they are in the source, with the methods of a struct left out.
+impl<'a> std::fmt::Display for TypeDeclaration<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn generics(parameters: &[Spanned<&str>]) -> String {
+            if parameters.is_empty() {
+                return String::new();
+            }
+
+            let names: Vec<&str> = parameters.iter().map(|parameter| parameter.node).collect();
+            format!("<{}>", names.join(", "))
+        }
+
+        match self {
+            TypeDeclaration::StructDefinition {
+                name,
+                generic_parameters,
+                fields,
+                methods,
+            } => {
+                write!(f, "type {} struct{} {{", name.node, generics(generic_parameters))?;
+                for (field, ty) in fields {
+                    write!(f, " @{}: {};", field.node, ty.node)?;
+                }
+                if !methods.is_empty() {
+                    write!(f, " ...")?;
+                }
+                write!(f, " }}")
+            }
+            TypeDeclaration::TraitDefinition { name } => write!(f, "type {} trait", name.node),
+            TypeDeclaration::EnumDefinition { name, fields } => {
+                write!(f, "type {} enum", name.node)?;
+                // Every variant has the backing type, which is `void` unless the enum names one.
+                match fields.first() {
+                    Some((_, ty)) if ty.node != Type::Simple(Simple::Void) => write!(f, ": {}", ty.node)?,
+                    _ => {}
+                }
+                let variants: Vec<&str> = fields.iter().map(|(variant, _)| variant.node).collect();
+                if variants.is_empty() {
+                    return write!(f, " {{ }}");
+                }
+                write!(f, " {{ {} }}", variants.join(", "))
+            }
+            TypeDeclaration::TypeAlias {
+                name,
+                generic_parameters,
+                ty,
+            } => write!(f, "type {}{} = {};", name.node, generics(generic_parameters), ty.node),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Statement<'a> {
     VariableDeclaration(Box<VariableDeclaration<'a>>),
     IfStatement(Box<IfStatement<'a>>),
     WhileStatement(Box<WhileStatement<'a>>),
-    ReturnStatement(Option<Spanned<Expression<'a>>>),
+    // The `return` keyword, which is where a missing value is reported, and the value if there is one.
+    ReturnStatement(Spanned<TokenType<'a>>, Option<Spanned<Expression<'a>>>),
     DeleteStatement(Box<Spanned<Expression<'a>>>),
     ExpressionStatement(Spanned<Expression<'a>>),
+    BlockStatement(Box<BlockStatement<'a>>),
+    MatchStatement(Box<MatchStatement<'a>>),
+    BreakStatement(LoopControl<'a>),
+    ContinueStatement(LoopControl<'a>),
+    // A function declared inside another one, which is always a `TopLevel::FunctionDeclaration`. It cannot use the
+    // variables of the function it is declared in.
+    FunctionDeclaration(Box<TopLevel<'a>>),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VariableDeclaration<'a> {
     pub name: Spanned<&'a str>,
     pub value: Spanned<Expression<'a>>,
@@ -319,21 +615,65 @@ pub struct VariableDeclaration<'a> {
     pub ty: std::cell::RefCell<Option<Spanned<Type<'a>>>>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// The type is the declared one, or the one the resolver inferred once it ran.
+impl<'a> std::hash::Hash for VariableDeclaration<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.value.hash(state);
+        self.eq.hash(state);
+        self.ty.borrow().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct IfStatement<'a> {
     pub condition: Spanned<Expression<'a>>,
     pub then_block: Block<'a>,
     pub else_branch: Option<Box<Else<'a>>>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Else<'a> {
     IfStatement(Box<Statement<'a>>),
     Block(Block<'a>),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WhileStatement<'a> {
+    pub label: Option<Spanned<&'a str>>,
     pub condition: Spanned<Expression<'a>>,
     pub body: Block<'a>,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlockStatement<'a> {
+    pub label: Option<Spanned<&'a str>>,
+    pub body: Block<'a>,
+}
+
+// `match value { case A: { ... } default: { ... } }`. The arms are checked in order, the first matching one runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchStatement<'a> {
+    pub keyword: Spanned<TokenType<'a>>,
+    pub scrutinee: Spanned<Expression<'a>>,
+    pub arms: Vec<(Spanned<Expression<'a>>, Block<'a>)>,
+    pub default: Option<Block<'a>>,
+    // Set by the resolver when the arms cover every variant of the matched enum.
+    pub exhaustive: std::cell::Cell<bool>,
+}
+
+impl<'a> std::hash::Hash for MatchStatement<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.keyword.hash(state);
+        self.scrutinee.hash(state);
+        self.arms.hash(state);
+        self.default.hash(state);
+        self.exhaustive.get().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoopControl<'a> {
+    pub keyword: Spanned<TokenType<'a>>,
+    pub label: Option<Spanned<&'a str>>,
+}
@@ -0,0 +1,479 @@
+/*
+ * A stable, textual dump of syntax trees, for golden files and for comparing the trees of two parses by eye.
+ *
+ * Every node is a parenthesized list that starts with the kind of the node, followed by its names, operators and types
+ * on the same line, and its children on the lines below, indented by two spaces. Unlike `{:?}`, the output does not
+ * change along with the field names of the syntax tree, and it leaves out spans unless they are asked for, so moving
+ * code around in a golden file keeps its dump as it is.
+ *
+ * Newton (C) 2023
+ */
+
+use super::ast::*;
+use crate::parser::span::*;
+
+// The dump of `program`, without any spans.
+pub fn debug_dump(program: &Program) -> String {
+    dump(program, false)
+}
+
+// The dump of `program`, with the span of every node as `@start..end` right after its kind. Spans are inclusive, as
+// they are everywhere else in the compiler.
+pub fn debug_dump_with_spans(program: &Program) -> String {
+    dump(program, true)
+}
+
+fn dump(program: &Program, spans: bool) -> String {
+    let mut dumper = Dumper {
+        output: String::new(),
+        depth: 0,
+        spans,
+    };
+
+    dumper.open("program", None);
+    for top_level in &program.0 {
+        dumper.top_level(top_level);
+    }
+    dumper.close();
+
+    dumper.output.push('\n');
+    dumper.output
+}
+
+struct Dumper {
+    output: String,
+    // Nodes that are open right now, which is how deeply the next one is indented.
+    depth: usize,
+    spans: bool,
+}
+
+impl Dumper {
+    fn open(&mut self, kind: &str, span: Option<Span>) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output.push_str(&"  ".repeat(self.depth));
+        self.output.push('(');
+        self.output.push_str(kind);
+
+        if let (true, Some(span)) = (self.spans, span) {
+            self.output
+                .push_str(&format!(" @{}..{}", span.start, span.end));
+        }
+
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.output.push(')');
+        self.depth -= 1;
+    }
+
+    fn atom<T: std::fmt::Display>(&mut self, atom: T) {
+        self.output.push_str(&format!(" {}", atom));
+    }
+
+    // A node without children, like `(param x i32)`.
+    fn leaf<T: std::fmt::Display>(&mut self, kind: &str, span: Option<Span>, atoms: &[T]) {
+        self.open(kind, span);
+        for atom in atoms {
+            self.atom(atom);
+        }
+        self.close();
+    }
+
+    fn top_level(&mut self, top_level: &TopLevel) {
+        match top_level {
+            TopLevel::FunctionDeclaration {
+                name,
+                arguments,
+                body,
+                return_type,
+                is_external,
+                is_public,
+                attributes,
+            } => {
+                self.open("fn", Some(name.span));
+                self.atom(name.node);
+
+                if *is_public {
+                    self.atom("pub");
+                }
+                if *is_external {
+                    self.atom("extern");
+                }
+
+                self.attributes(attributes);
+
+                // Varargs are the last parameter, named and typed `...`.
+                for (index, Parameter(name, ty)) in arguments.parameters.iter().enumerate() {
+                    if arguments.varargs && index + 1 == arguments.parameters.len() {
+                        self.leaf::<&str>("varargs", Some(name.span), &[]);
+                        continue;
+                    }
+
+                    self.open("param", Some(name.span));
+                    self.atom(name.node);
+                    self.atom(&ty.node);
+                    self.close();
+                }
+
+                self.leaf("returns", Some(return_type.span), &[&return_type.node]);
+                self.block(body);
+                self.close();
+            }
+
+            TopLevel::Import { name, symbols } => {
+                self.open("import", Some(name.span));
+                self.atom(format!("{:?}", name.node));
+
+                for symbol in symbols {
+                    self.atom(symbol.node);
+                }
+                self.close();
+            }
+
+            TopLevel::TypeDeclaration {
+                ty,
+                is_public,
+                attributes,
+            } => self.type_declaration(ty, *is_public, attributes),
+
+            TopLevel::ExternVariable {
+                name,
+                ty,
+                is_public,
+                attributes,
+            } => {
+                self.open("extern-let", Some(name.span));
+                self.atom(name.node);
+                self.atom(&ty.node);
+
+                if *is_public {
+                    self.atom("pub");
+                }
+                self.attributes(attributes);
+                self.close();
+            }
+
+            TopLevel::Constant {
+                declaration,
+                is_public,
+                attributes,
+            } => {
+                self.open("const", Some(declaration.name.span));
+                self.atom(declaration.name.node);
+
+                if *is_public {
+                    self.atom("pub");
+                }
+                if let Some(ty) = declaration.ty.borrow().as_ref() {
+                    self.atom(&ty.node);
+                }
+
+                self.attributes(attributes);
+
+                self.expression(&declaration.value);
+                self.close();
+            }
+
+            TopLevel::Error { error } => self.leaf(
+                "error",
+                Some(error.span),
+                &[format!("{:?}", error.node.to_string())],
+            ),
+        }
+    }
+
+    // The arguments of an attribute follow its name, like they do in the code.
+    fn attributes(&mut self, attributes: &[Attribute]) {
+        for attribute in attributes {
+            let mut atoms = vec![attribute.name.node];
+            atoms.extend(attribute.arguments.iter().map(|argument| argument.node));
+
+            self.leaf("attribute", Some(attribute.name.span), &atoms);
+        }
+    }
+
+    fn type_declaration(&mut self, ty: &TypeDeclaration, is_public: bool, attributes: &[Attribute]) {
+        let (kind, name) = match ty {
+            TypeDeclaration::StructDefinition { name, .. } => ("struct", name),
+            TypeDeclaration::TraitDefinition { name } => ("trait", name),
+            TypeDeclaration::EnumDefinition { name, .. } => ("enum", name),
+            TypeDeclaration::TypeAlias { name, .. } => ("alias", name),
+        };
+
+        self.open(kind, Some(name.span));
+        self.atom(name.node);
+
+        if is_public {
+            self.atom("pub");
+        }
+        self.attributes(attributes);
+
+        match ty {
+            TypeDeclaration::StructDefinition {
+                generic_parameters,
+                fields,
+                methods,
+                ..
+            } => {
+                self.generic_parameters(generic_parameters);
+                self.fields(fields);
+
+                for method in methods {
+                    self.top_level(method);
+                }
+            }
+
+            TypeDeclaration::EnumDefinition { fields, .. } => self.fields(fields),
+
+            TypeDeclaration::TypeAlias {
+                generic_parameters,
+                ty,
+                ..
+            } => {
+                self.generic_parameters(generic_parameters);
+                self.leaf("type", Some(ty.span), &[&ty.node]);
+            }
+
+            TypeDeclaration::TraitDefinition { .. } => {}
+        }
+
+        self.close();
+    }
+
+    fn generic_parameters(&mut self, parameters: &[Spanned<&str>]) {
+        for parameter in parameters {
+            self.leaf("generic", Some(parameter.span), &[parameter.node]);
+        }
+    }
+
+    fn fields(&mut self, fields: &[(Spanned<&str>, Spanned<crate::types::types::Type>)]) {
+        for (name, ty) in fields {
+            self.open("field", Some(name.span));
+            self.atom(name.node);
+            self.atom(&ty.node);
+            self.close();
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.open("block", Some(block.1));
+        for statement in &block.0 {
+            self.statement(statement);
+        }
+        self.close();
+    }
+
+    fn label(&mut self, label: &Option<Spanned<&str>>) {
+        if let Some(label) = label {
+            self.atom(format!("'{}", label.node));
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                self.open("let", Some(declaration.name.span));
+                self.atom(declaration.name.node);
+
+                if let Some(ty) = declaration.ty.borrow().as_ref() {
+                    self.atom(&ty.node);
+                }
+
+                self.expression(&declaration.value);
+                self.close();
+            }
+
+            Statement::IfStatement(statement) => self.if_statement(statement),
+
+            Statement::WhileStatement(statement) => {
+                self.open("while", None);
+                self.label(&statement.label);
+                self.expression(&statement.condition);
+                self.block(&statement.body);
+                self.close();
+            }
+
+            Statement::ReturnStatement(_, value) => {
+                self.open("return", None);
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+                self.close();
+            }
+
+            Statement::DeleteStatement(value) => {
+                self.open("delete", None);
+                self.expression(value);
+                self.close();
+            }
+
+            Statement::ExpressionStatement(expression) => self.expression(expression),
+
+            Statement::BlockStatement(statement) => {
+                if statement.label.is_none() {
+                    return self.block(&statement.body);
+                }
+
+                self.open("labeled", None);
+                self.label(&statement.label);
+                self.block(&statement.body);
+                self.close();
+            }
+
+            Statement::MatchStatement(statement) => {
+                self.open("match", Some(statement.keyword.span));
+                self.expression(&statement.scrutinee);
+
+                for (pattern, block) in &statement.arms {
+                    self.open("case", Some(pattern.span));
+                    self.expression(pattern);
+                    self.block(block);
+                    self.close();
+                }
+
+                if let Some(block) = &statement.default {
+                    self.open("default", None);
+                    self.block(block);
+                    self.close();
+                }
+                self.close();
+            }
+
+            Statement::BreakStatement(control) | Statement::ContinueStatement(control) => {
+                self.open(
+                    &control.keyword.node.to_string(),
+                    Some(control.keyword.span),
+                );
+                self.label(&control.label);
+                self.close();
+            }
+
+            Statement::FunctionDeclaration(function) => self.top_level(function),
+        }
+    }
+
+    fn if_statement(&mut self, statement: &IfStatement) {
+        self.open("if", None);
+        self.expression(&statement.condition);
+        self.block(&statement.then_block);
+
+        match statement.else_branch.as_deref() {
+            Some(Else::IfStatement(statement)) => {
+                self.open("else", None);
+                self.statement(statement);
+                self.close();
+            }
+            Some(Else::Block(block)) => {
+                self.open("else", None);
+                self.block(block);
+                self.close();
+            }
+            None => {}
+        }
+
+        self.close();
+    }
+
+    fn expression(&mut self, expression: &Spanned<Expression>) {
+        let span = Some(expression.span);
+
+        match expression.node.kind() {
+            ExpressionKind::Error(error) => {
+                return self.leaf("error", span, &[format!("{:?}", error.to_string())])
+            }
+
+            ExpressionKind::NullLiteral => return self.leaf::<&str>("null", span, &[]),
+            ExpressionKind::DecLiteral(literal) => return self.leaf("dec", span, &[literal]),
+            ExpressionKind::FloatLiteral(literal) => return self.leaf("float", span, &[literal]),
+            ExpressionKind::StringLiteral(literal) => {
+                return self.leaf("string", span, &[format!("\"{}\"", literal.escape_debug())])
+            }
+            ExpressionKind::Char(literal) => {
+                return self.leaf("char", span, &[format!("'{}'", literal)])
+            }
+            ExpressionKind::BoolLiteral(value) => return self.leaf("bool", span, &[value]),
+            ExpressionKind::Constant(value) => return self.leaf("const", span, &[value]),
+            ExpressionKind::Identifier(name) => return self.leaf("id", span, &[name]),
+            ExpressionKind::SizeOf(ty) => return self.leaf("sizeof", span, &[ty]),
+
+            ExpressionKind::Reference(..) => self.open("ref", span),
+            ExpressionKind::Dereference(..) => self.open("deref", span),
+            ExpressionKind::Negate(..) => self.open("neg", span),
+            ExpressionKind::BoolNegate(..) => self.open("not", span),
+            ExpressionKind::BitNot(..) => self.open("bitnot", span),
+            ExpressionKind::New(_) => self.open("new", span),
+            ExpressionKind::SizeOfValue(_) => self.open("sizeof", span),
+            ExpressionKind::ArrayLiteral(_) => self.open("array", span),
+            ExpressionKind::Tuple(_) => self.open("tuple", span),
+            ExpressionKind::Grouping(_) => self.open("group", span),
+
+            // The statements come first, the value after them like it does in the code.
+            ExpressionKind::Block { body, .. } => {
+                self.open("value-block", span);
+                self.block(body);
+            }
+
+            ExpressionKind::Binary(_, operator, _) | ExpressionKind::BoolBinary(_, operator, _) => {
+                self.open("binary", span);
+                self.atom(&operator.node);
+            }
+
+            ExpressionKind::Cast(_, _, ty) => {
+                self.open("cast", span);
+                self.atom(&ty.node);
+            }
+
+            ExpressionKind::Assignment { .. } => self.open("assign", span),
+
+            ExpressionKind::Increment {
+                operator,
+                is_postfix,
+                ..
+            } => {
+                self.open(if *is_postfix { "postfix" } else { "prefix" }, span);
+                self.atom(&operator.node);
+            }
+
+            ExpressionKind::Call { module, callee, .. } => {
+                self.open("call", span);
+                self.atom(module);
+
+                if let ExpressionKind::Identifier(name) = callee.node.kind() {
+                    self.atom(name);
+                }
+            }
+
+            ExpressionKind::Access { identifier, .. } => {
+                self.open("access", span);
+                self.atom(identifier.node);
+            }
+
+            ExpressionKind::Index { .. } => self.open("index", span),
+
+            // The field names are not expressions, so they are written out here along with their values.
+            ExpressionKind::StructInitialization { identifier, fields } => {
+                self.open("init", span);
+                self.atom(&identifier.node);
+
+                for (name, value) in &fields.0 {
+                    self.open("field", Some(name.span));
+                    self.atom(name.node);
+                    self.expression(value);
+                    self.close();
+                }
+
+                return self.close();
+            }
+        }
+
+        for sub_expression in expression.node.sub_expressions() {
+            self.expression(sub_expression);
+        }
+
+        self.close();
+    }
+}
@@ -7,6 +7,9 @@
  * Newton (C) 2023
  */
 
+use crate::ast::ast::*;
+use crate::error::diagnostic::*;
+
 /*
  * `Backend` trait. Every new backend must implement this trait and its associated methods.
  */
@@ -30,8 +33,54 @@ pub trait Backend {
     // The emit method. Supposedly, it should feed generated source code to a `source` field on the backend's struct.
     fn emit(&mut self, code: &str) -> ();
 
-    // Arbitrary header. Could be info about the backend, could be anything else the author wants.
-    fn generate_header(&mut self) -> ();
+    // Arbitrary header. Could be info about the backend, could be anything else the author wants. `module` is the name
+    // of the module being lowered.
+    fn generate_header(&mut self, module: &str) -> ();
+
+    // Arbitrary footer, right after the program. This is where anything the header opened gets closed again, like the
+    // include guard of the C backend. Nothing by default.
+    fn generate_footer(&mut self, _module: &str) {}
+
+    // Lower everything of a resolved program but its functions, right after the header. Every backend has constructs it
+    // cannot lower (yet), which it reports to `diagnostics` instead of panicking, leaving them out of the output. The
+    // output is only usable if no errors were reported. Nothing by default.
+    fn generate(&mut self, _program: &Program, _diagnostics: &mut DiagnosticSink) {}
+
+    // Lower a single function declaration, after the rest of the program. Reports what it cannot lower like `generate`.
+    fn generate_function(&mut self, function: &TopLevel, diagnostics: &mut DiagnosticSink);
+
+    // Called right before and right after every function is lowered, with the function's declaration. A backend can use
+    // these to set up and tear down whatever it keeps per function, or to lay out the output. Nothing by default.
+    fn begin_function(&mut self, _function: &TopLevel) {}
+    fn end_function(&mut self, _function: &TopLevel) {}
+
+    // Finalize the backend and hand the complete output over to the driver. After this, the backend's source stream is empty
+    // again, so the same backend can be reused for another compilation.
+    fn finish(&mut self) -> String;
+}
+
+// Lower `program`, the module named `module`, with `backend`, calling its hooks in order: the header, everything but
+// the functions, every function between `begin_function` and `end_function`, and the footer. Returns the complete output,
+// which is only usable if no errors were reported to `diagnostics`.
+pub fn generate_program(
+    backend: &mut dyn Backend,
+    module: &str,
+    program: &Program,
+    diagnostics: &mut DiagnosticSink,
+) -> String {
+    backend.generate_header(module);
+    backend.generate(program, diagnostics);
+
+    for top_level in &program.0 {
+        if let TopLevel::FunctionDeclaration { .. } = top_level {
+            backend.begin_function(top_level);
+            backend.generate_function(top_level, diagnostics);
+            backend.end_function(top_level);
+        }
+    }
+
+    backend.generate_footer(module);
+    backend.finish()
 }
 
 // `Display` is already implemented for `BackendInfo`, providing a default pretty-printed message for the backend.
@@ -48,6 +97,28 @@ impl std::fmt::Display for dyn Backend {
     }
 }
 
+/*
+ * Errors that can come up while registering a backend. Registering is the only fallible operation for now.
+ */
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BackendAPIError {
+    // The alias is already taken by another backend (or appears twice in the same registration).
+    DuplicateAlias(String),
+}
+
+impl std::fmt::Display for BackendAPIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::DuplicateAlias(alias) => {
+                write!(f, "a backend is already registered under the name `{}`", alias)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BackendAPIError {}
+
 /*
  * This struct is where all the magic happens. You simply `::register()` the new backend, and in *theory* it should all work just fine.
  * Subject to change; if you are the maintainer of a backend, make sure to always check this file with every Newton update to see if
@@ -69,21 +140,40 @@ impl BackendAPI {
         }
     }
 
-    // Register the new backend, and push it to a HashMap.
+    // Register the new backend, and push it to a HashMap. Like any alias, the name cannot be taken already.
     pub fn register(
         &mut self,
-        name: &'static str,
+        name: &str,
+        backend: std::rc::Rc<std::cell::RefCell<dyn Backend>>,
+    ) -> Result<(), BackendAPIError> {
+        self.register_aliases(&[name], backend)
+    }
+
+    // Register the new backend under several names at once, e.g. "c", "gcc" and "clang" for the C backend.
+    // Every alias points to the very same backend. If any of the aliases is already taken, nothing gets registered.
+    pub fn register_aliases(
+        &mut self,
+        names: &[&str],
         backend: std::rc::Rc<std::cell::RefCell<dyn Backend>>,
-    ) -> () {
-        self.backends
-            .lock()
-            .unwrap()
-            .insert(name.to_owned(), backend);
+    ) -> Result<(), BackendAPIError> {
+        let mut backends = self.backends.lock().unwrap();
+
+        for (index, name) in names.iter().enumerate() {
+            if backends.contains_key(*name) || names[..index].contains(name) {
+                return Err(BackendAPIError::DuplicateAlias((*name).to_owned()));
+            }
+        }
+
+        for name in names {
+            backends.insert((*name).to_owned(), backend.clone());
+        }
+
+        Ok(())
     }
 
-    // Retrieve a specific backend by name.
+    // Retrieve a specific backend by name or by any of its aliases, or `None` if no backend is registered under it.
     // You need to get a reference to the returned value to be able to use the methods that belong to the backend.
-    pub fn get(&mut self, name: &'static str) -> std::rc::Rc<std::cell::RefCell<dyn Backend>> {
-        self.backends.lock().unwrap().get(name).unwrap().clone()
+    pub fn get(&mut self, name: &str) -> Option<std::rc::Rc<std::cell::RefCell<dyn Backend>>> {
+        self.backends.lock().unwrap().get(name).cloned()
     }
 }
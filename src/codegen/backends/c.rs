@@ -1,4 +1,10 @@
 use super::super::api::*;
+use crate::ast::ast::*;
+use crate::error::diagnostic::*;
+use crate::parser::parser::{parse_alignment, ALIGN_ATTRIBUTE, PACKED_ATTRIBUTE};
+use crate::parser::span::*;
+use crate::semantic::consteval::*;
+use crate::types::types::*;
 
 /*
  * Newton's C backend. This is one of the backends originally included in the project.
@@ -17,6 +23,16 @@ pub struct C {
     pub target: String,
 
     pub source: String,
+
+    // Names of the tuple structs defined so far, so each one is only defined once.
+    tuples: std::collections::HashSet<String>,
+
+    // Definitions of the functions lowered so far. They follow every prototype, so functions can call each other no
+    // matter the order they are declared in.
+    definitions: String,
+
+    // Whether a definition concatenates strings, which needs the runtime's helper.
+    concatenates: bool,
 }
 
 impl C {
@@ -30,7 +46,718 @@ impl C {
             target: "C".to_owned(),
 
             source: String::new(),
+            tuples: std::collections::HashSet::new(),
+            definitions: String::new(),
+            concatenates: false,
+        }
+    }
+
+    // Define the struct of every tuple `ty` uses that has not been defined yet, right before `ty` is used. Tuples
+    // inside tuples are defined first, since C needs the definition of a struct before it is used as a field.
+    fn define_tuples(&mut self, ty: &Type) {
+        let mut tuples = vec![];
+        tuples_in(ty, &mut tuples);
+
+        for tuple in tuples {
+            if self.tuples.insert(tuple_struct_name(tuple)) {
+                self.emit(&format!("\n{}\n", Self::tuple_definition(tuple)));
+            }
+        }
+    }
+
+    // Declare `name` as a `ty` using C's declarator syntax, where array dimensions follow the name: `[3][4]i32` becomes
+    // `int32_t name[3][4]`.
+    pub fn declaration(ty: &Type, name: &str) -> String {
+        match ty {
+            Type::Simple(simple) => format!("{} {}", lower_simple(simple), name),
+
+            Type::Complex(Complex::Pointer(pointer)) => {
+                declare_indirection(pointer.base_type(), pointer.size(), name)
+            }
+            Type::Complex(Complex::Ref(reference)) => {
+                declare_indirection(reference.base_type(), reference.size(), name)
+            }
+
+            Type::Complex(Complex::Array(array)) => {
+                let dimension = if array.is_unsized() {
+                    String::new()
+                } else {
+                    match array.length() {
+                        Some(length) => length.to_string(),
+                        None => panic!("array type '{}' must have a constant size", ty),
+                    }
+                };
+
+                Self::declaration(array.base_type(), &format!("{}[{}]", name, dimension))
+            }
+
+            // Function values are function pointers.
+            Type::Complex(Complex::Function(function)) => {
+                let parameters = if function.parameters().is_empty() {
+                    "void".to_owned()
+                } else {
+                    function
+                        .parameters()
+                        .iter()
+                        .map(|parameter| Self::declaration(parameter, "").trim_end().to_owned())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                };
+
+                Self::declaration(
+                    function.return_type(),
+                    &format!("(*{})({})", name, parameters),
+                )
+            }
+
+            Type::Complex(Complex::Tuple(tuple)) => {
+                format!("struct {} {}", tuple_struct_name(tuple), name)
+            }
+
+            Type::Complex(Complex::Generic(_)) => {
+                panic!(
+                    "generic type '{}' has to be monomorphized for the C backend",
+                    ty
+                )
+            }
+
+            Type::Nullable(_) => panic!("type '{}' is not supported by the C backend yet", ty),
+        }
+    }
+
+    // Tuples are structs with one field per element, `_0`, `_1` and so on. The struct is named after the element
+    // types, so the same tuple type always lowers to the same struct, which has to be defined before its first use.
+    pub fn tuple_definition(tuple: &Tuple) -> String {
+        let fields = tuple
+            .elements()
+            .iter()
+            .enumerate()
+            .map(|(index, element)| {
+                format!(
+                    "    {};",
+                    Self::declaration(element, &format!("_{}", index))
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        format!("struct {} {{\n{}\n}};", tuple_struct_name(tuple), fields)
+    }
+
+    // Structs keep their fields in declaration order, so C lays them out just like Newton does, as long as it is told
+    // about `@[packed]` and `@[align(N)]` as well.
+    pub fn struct_definition(
+        name: &str,
+        fields: &[(Spanned<&str>, Spanned<Type>)],
+        attributes: &[Attribute],
+    ) -> String {
+        let fields = fields
+            .iter()
+            .map(|(field, ty)| format!("    {};", Self::declaration(&ty.node, field.node)))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let layout = attributes
+            .iter()
+            .filter_map(|attribute| match (attribute.name.node, attribute.arguments.as_slice()) {
+                (PACKED_ATTRIBUTE, _) => Some("packed".to_owned()),
+                (ALIGN_ATTRIBUTE, [align]) => {
+                    parse_alignment(align.node).map(|align| format!("aligned({})", align))
+                }
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+
+        let layout = if layout.is_empty() {
+            String::new()
+        } else {
+            format!(" __attribute__(({}))", layout.join(", "))
+        };
+
+        format!("struct {} {{\n{}\n}}{};", name, fields, layout)
+    }
+
+    // Extern variables are only declared, whatever defines them is linked in along with the generated code.
+    pub fn extern_variable(name: &str, ty: &Type) -> String {
+        format!("extern {};", Self::declaration(ty, name))
+    }
+
+    // Constants are computed by the compiler, so C only ever sees their values.
+    pub fn constant(name: &str, ty: &Type, value: ConstValue) -> String {
+        format!("static const {} = {};", Self::declaration(ty, name), value)
+    }
+
+    // The prototype of a function. Functions with a body are defined along with the other definitions.
+    pub fn prototype(name: &str, arguments: &ParameterList, return_type: &Type) -> String {
+        format!("{};", Self::signature(name, arguments, return_type, false))
+    }
+
+    // Discarded parameters are left unnamed in prototypes, but a definition needs a name for every parameter.
+    fn signature(name: &str, arguments: &ParameterList, return_type: &Type, definition: bool) -> String {
+        let mut parameters = arguments
+            .parameters
+            .iter()
+            .filter(|Parameter(_, ty)| ty.node != Type::Simple(Simple::VarArgs))
+            .enumerate()
+            .map(|(index, Parameter(name, ty))| match name.node {
+                "_" if definition => Self::declaration(&ty.node, &format!("newton_discard{}", index)),
+                "_" => Self::declaration(&ty.node, "").trim_end().to_owned(),
+                name => Self::declaration(&ty.node, name),
+            })
+            .collect::<Vec<String>>();
+
+        if arguments.varargs {
+            parameters.push("...".to_owned());
         }
+
+        let parameters = if parameters.is_empty() {
+            "void".to_owned()
+        } else {
+            parameters.join(", ")
+        };
+
+        Self::declaration(return_type, &format!("{}({})", name, parameters))
+    }
+
+    // Lower the statements of `block`, indented by `depth` levels. Every statement is lowered, so all of those C cannot
+    // express yet are reported at once.
+    fn lower_block(&mut self, block: &Block, depth: usize, diagnostics: &mut DiagnosticSink) -> Option<String> {
+        let statements = block
+            .0
+            .iter()
+            .map(|statement| self.lower_statement(statement, depth, diagnostics))
+            .collect::<Vec<Option<String>>>();
+
+        statements
+            .into_iter()
+            .collect::<Option<Vec<String>>>()
+            .map(|statements| statements.concat())
+    }
+
+    fn lower_statement(
+        &mut self,
+        statement: &Statement,
+        depth: usize,
+        diagnostics: &mut DiagnosticSink,
+    ) -> Option<String> {
+        let indent = "    ".repeat(depth);
+
+        let lowered = match statement {
+            // `let _ = f();` runs `f` for its side effects only.
+            Statement::VariableDeclaration(declaration) => {
+                let value = self.lower_value(&declaration.value, diagnostics);
+                let name = declaration.name.node;
+                if name == "_" {
+                    return Some(format!("{}{};\n", indent, value?));
+                }
+
+                let ty = declaration.ty.borrow();
+                let Some(ty) = ty.as_ref() else {
+                    let message = format!("the type of '{}' must be resolved first", name);
+                    diagnostics.error(declaration.name.span, message);
+                    return None;
+                };
+
+                if !check_type(ty, diagnostics) {
+                    return None;
+                }
+                self.define_tuples(&ty.node);
+
+                format!("{} = {};", Self::declaration(&ty.node, name), value?)
+            }
+
+            Statement::ReturnStatement(_, Some(value)) => {
+                format!("return {};", self.lower_value(value, diagnostics)?)
+            }
+            Statement::ReturnStatement(_, None) => "return;".to_owned(),
+            Statement::ExpressionStatement(expression) => {
+                format!("{};", self.lower_value(expression, diagnostics)?)
+            }
+
+            Statement::IfStatement(statement) => self.lower_if(statement, depth, diagnostics)?,
+
+            Statement::WhileStatement(statement) => {
+                unlabeled(statement.label.as_ref(), diagnostics)?;
+
+                let condition = self.lower_value(&statement.condition, diagnostics);
+                let body = self.lower_block(&statement.body, depth + 1, diagnostics);
+                format!("while ({}) {{\n{}{}}}", condition?, body?, indent)
+            }
+
+            Statement::BlockStatement(statement) => {
+                unlabeled(statement.label.as_ref(), diagnostics)?;
+
+                let body = self.lower_block(&statement.body, depth + 1, diagnostics)?;
+                format!("{{\n{}{}}}", body, indent)
+            }
+
+            Statement::BreakStatement(control) => {
+                unlabeled(control.label.as_ref(), diagnostics)?;
+                "break;".to_owned()
+            }
+            Statement::ContinueStatement(control) => {
+                unlabeled(control.label.as_ref(), diagnostics)?;
+                "continue;".to_owned()
+            }
+
+            Statement::MatchStatement(statement) => {
+                return unsupported_statement("match", statement.keyword.span, diagnostics)
+            }
+            Statement::DeleteStatement(expression) => {
+                return unsupported_statement("delete", expression.span, diagnostics)
+            }
+            Statement::FunctionDeclaration(function) => match function.as_ref() {
+                TopLevel::FunctionDeclaration { name, .. } => {
+                    return unsupported_statement("fn", name.span, diagnostics)
+                }
+                _ => return None,
+            },
+        };
+
+        Some(format!("{}{}\n", indent, lowered))
+    }
+
+    // `else if` chains stay on one level, like they are written.
+    fn lower_if(&mut self, statement: &IfStatement, depth: usize, diagnostics: &mut DiagnosticSink) -> Option<String> {
+        let indent = "    ".repeat(depth);
+        let condition = self.lower_value(&statement.condition, diagnostics);
+        let then_block = self.lower_block(&statement.then_block, depth + 1, diagnostics);
+
+        let else_branch = match statement.else_branch.as_deref() {
+            None => Some(String::new()),
+            Some(Else::IfStatement(statement)) => match statement.as_ref() {
+                Statement::IfStatement(statement) => self
+                    .lower_if(statement, depth, diagnostics)
+                    .map(|lowered| format!(" else {}", lowered)),
+                _ => None,
+            },
+            Some(Else::Block(block)) => self
+                .lower_block(block, depth + 1, diagnostics)
+                .map(|lowered| format!(" else {{\n{}{}}}", lowered, indent)),
+        };
+
+        Some(format!("if ({}) {{\n{}{}}}{}", condition?, then_block?, indent, else_branch?))
+    }
+
+    // Constant parts of the expression are folded first, and anything C cannot express is reported.
+    fn lower_value(&mut self, expression: &Spanned<Expression>, diagnostics: &mut DiagnosticSink) -> Option<String> {
+        match Self::lower_expression(&fold(expression).node, &mut self.concatenates) {
+            Ok(lowered) => Some(lowered),
+            Err(reason) => {
+                diagnostics.error(expression.span, reason);
+                None
+            }
+        }
+    }
+
+    // Lower an expression to C. Every compound expression is parenthesized, so Newton's precedence is kept regardless
+    // of how C would parse it. Expressions C cannot express yet, and those that were never resolved, give the reason
+    // why instead.
+    pub fn expression(expression: &Expression) -> Result<String, String> {
+        Self::lower_expression(expression, &mut false)
+    }
+
+    // Like `expression`, setting `concatenates` if the runtime's string concatenation is used.
+    fn lower_expression(expression: &Expression, concatenates: &mut bool) -> Result<String, String> {
+        let lowered = match expression.kind() {
+            ExpressionKind::NullLiteral => "NULL".to_owned(),
+            ExpressionKind::DecLiteral(literal) | ExpressionKind::FloatLiteral(literal) => {
+                literal.to_string()
+            }
+            ExpressionKind::StringLiteral(literal) => lower_string(literal),
+            ExpressionKind::Char(literal) => lower_char(literal),
+            ExpressionKind::BoolLiteral(value) => value.to_string(),
+            ExpressionKind::Constant(value) => value.to_string(),
+            ExpressionKind::Identifier(name) => name.to_string(),
+
+            // Every compound expression is parenthesized already.
+            ExpressionKind::Grouping(inner) => Self::lower_expression(&inner.node, concatenates)?,
+            ExpressionKind::Negate(_, inner) => {
+                format!("(-{})", Self::lower_expression(&inner.node, concatenates)?)
+            }
+            ExpressionKind::BoolNegate(_, inner) => {
+                format!("(!{})", Self::lower_expression(&inner.node, concatenates)?)
+            }
+            ExpressionKind::BitNot(_, inner) => {
+                format!("(~{})", Self::lower_expression(&inner.node, concatenates)?)
+            }
+            ExpressionKind::Reference(_, inner) => {
+                format!("(&{})", Self::lower_expression(&inner.node, concatenates)?)
+            }
+            ExpressionKind::Dereference(_, inner) => {
+                format!("(*{})", Self::lower_expression(&inner.node, concatenates)?)
+            }
+
+            // C strings cannot be added, the runtime allocates a new one holding both.
+            ExpressionKind::Binary(left, _, right)
+                if expression.clone_ty() == Some(Type::Simple(Simple::String)) =>
+            {
+                *concatenates = true;
+                format!(
+                    "newton_string_concat({}, {})",
+                    Self::lower_expression(&left.node, concatenates)?,
+                    Self::lower_expression(&right.node, concatenates)?
+                )
+            }
+
+            ExpressionKind::Binary(left, operator, right)
+            | ExpressionKind::BoolBinary(left, operator, right) => format!(
+                "({} {} {})",
+                Self::lower_expression(&left.node, concatenates)?,
+                operator.node,
+                Self::lower_expression(&right.node, concatenates)?
+            ),
+
+            ExpressionKind::Assignment { left, value, .. } => {
+                format!(
+                    "({} = {})",
+                    Self::lower_expression(&left.node, concatenates)?,
+                    Self::lower_expression(&value.node, concatenates)?
+                )
+            }
+
+            ExpressionKind::Increment {
+                operator,
+                operand,
+                is_postfix: true,
+            } => format!("({}{})", Self::lower_expression(&operand.node, concatenates)?, operator.node),
+            ExpressionKind::Increment {
+                operator, operand, ..
+            } => format!("({}{})", operator.node, Self::lower_expression(&operand.node, concatenates)?),
+
+            ExpressionKind::Call {
+                callee, arguments, ..
+            } => format!(
+                "{}({})",
+                Self::lower_expression(&callee.node, concatenates)?,
+                arguments
+                    .0
+                    .iter()
+                    .map(|argument| Self::lower_expression(&argument.node, concatenates))
+                    .collect::<Result<Vec<String>, String>>()?
+                    .join(", ")
+            ),
+
+            // Fields are accessed through a single pointer or reference to the struct as well, which C needs `->` for.
+            ExpressionKind::Access { left, identifier } => {
+                let operator = match left.node.clone_ty() {
+                    Some(Type::Complex(Complex::Pointer(_) | Complex::Ref(_))) => "->",
+                    Some(_) => ".",
+                    None => return Err(format!("the type of '{}' must be resolved first", left.node)),
+                };
+
+                format!("{}{}{}", Self::lower_expression(&left.node, concatenates)?, operator, identifier.node)
+            }
+
+            ExpressionKind::Index { array, index } => format!(
+                "{}[{}]",
+                Self::lower_expression(&array.node, concatenates)?,
+                Self::lower_expression(&index.node, concatenates)?
+            ),
+
+            // Fields are initialized by name, so they can be given in any order.
+            ExpressionKind::StructInitialization { identifier, fields } => format!(
+                "((struct {}){{{}}})",
+                identifier.node.clone().name(),
+                fields
+                    .0
+                    .iter()
+                    .map(|(field, value)| {
+                        Ok(format!(".{} = {}", field.node, Self::lower_expression(&value.node, concatenates)?))
+                    })
+                    .collect::<Result<Vec<String>, String>>()?
+                    .join(", ")
+            ),
+
+            // A compound literal of the tuple's struct, whose name only the resolved type knows.
+            ExpressionKind::Tuple(elements) => match expression.clone_ty() {
+                Some(Type::Complex(Complex::Tuple(tuple))) => format!(
+                    "((struct {}){{{}}})",
+                    tuple_struct_name(&tuple),
+                    elements
+                        .iter()
+                        .map(|element| Self::lower_expression(&element.node, concatenates))
+                        .collect::<Result<Vec<String>, String>>()?
+                        .join(", ")
+                ),
+
+                _ => return Err(format!("the type of tuple '{}' must be resolved first", expression)),
+            },
+
+            _ => {
+                return Err(format!(
+                    "expression '{}' is not supported by the C backend yet",
+                    expression
+                ))
+            }
+        };
+
+        Ok(lowered)
+    }
+}
+
+// The macro guarding the generated code of `module` against being included twice, like `NEWTON_MAIN_H`. Anything but
+// letters and digits becomes an underscore, so every module name makes a valid macro name.
+pub fn include_guard(module: &str) -> String {
+    let name = module
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>();
+
+    format!("NEWTON_{}_H", name)
+}
+
+// Headers generated code relies on, included along with the header.
+const INCLUDES: &str = "
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+";
+
+// The runtime's string concatenation, which is only emitted if some definition concatenates strings.
+const STRING_CONCAT: &str = "
+static const char* newton_string_concat(const char* left, const char* right) {
+    size_t left_length = strlen(left);
+    size_t right_length = strlen(right);
+    char* result = malloc(left_length + right_length + 1);
+
+    memcpy(result, left, left_length);
+    memcpy(result + left_length, right, right_length + 1);
+
+    return result;
+}
+";
+
+fn unsupported_statement(keyword: &str, span: Span, diagnostics: &mut DiagnosticSink) -> Option<String> {
+    diagnostics.error(span, format!("'{}' statements cannot be lowered to C yet", keyword));
+    None
+}
+
+// Labels have no C counterpart, `break` and `continue` can only leave the innermost loop.
+fn unlabeled(label: Option<&Spanned<&str>>, diagnostics: &mut DiagnosticSink) -> Option<()> {
+    match label {
+        Some(label) => {
+            diagnostics.error(label.span, "labels cannot be lowered to C yet".to_owned());
+            None
+        }
+        None => Some(()),
+    }
+}
+
+// Why `ty` has no C counterpart, if it has none. Generic types only exist before monomorphization, which the C backend
+// does not do yet.
+fn unsupported(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Simple(Simple::TypeParameter(name)) => Some(format!(
+            "type parameter '{}' has to be monomorphized first",
+            name
+        )),
+        Type::Simple(Simple::VarArgs) => Some("varargs have no C type".to_owned()),
+        Type::Simple(_) => None,
+
+        Type::Complex(Complex::Pointer(pointer)) => unsupported(pointer.base_type()),
+        Type::Complex(Complex::Ref(reference)) => unsupported(reference.base_type()),
+        Type::Complex(Complex::Array(array)) if !array.is_unsized() && array.length().is_none() => {
+            Some("its size is not constant".to_owned())
+        }
+        Type::Complex(Complex::Array(array)) => unsupported(array.base_type()),
+        Type::Complex(Complex::Function(function)) => function
+            .parameters()
+            .iter()
+            .chain(std::iter::once(function.return_type()))
+            .find_map(unsupported),
+        Type::Complex(Complex::Tuple(tuple)) => tuple.elements().iter().find_map(unsupported),
+
+        Type::Complex(Complex::Generic(_)) => {
+            Some("generic types have to be monomorphized first".to_owned())
+        }
+        Type::Nullable(_) => Some("nullable types are not supported yet".to_owned()),
+    }
+}
+
+// Report `ty` if it cannot be lowered, so whatever uses it is left out.
+fn check_type(ty: &Spanned<Type>, diagnostics: &mut DiagnosticSink) -> bool {
+    let Some(reason) = unsupported(&ty.node) else {
+        return true;
+    };
+
+    diagnostics.error(
+        ty.span,
+        format!("type '{}' cannot be lowered to C, {}", ty.node, reason),
+    );
+    false
+}
+
+// References are plain pointers in C. Array and function declarators bind tighter than `*`, so pointers to those
+// need parentheses: `*[3]i32` becomes `int32_t (*name)[3]`.
+fn declare_indirection(base_type: &Type, depth: u8, name: &str) -> String {
+    let declarator = format!("{}{}", "*".repeat(depth.into()), name);
+
+    match base_type {
+        Type::Complex(Complex::Array(_)) | Type::Complex(Complex::Function(_)) => {
+            C::declaration(base_type, &format!("({})", declarator))
+        }
+        _ => C::declaration(base_type, &declarator),
+    }
+}
+
+// Every tuple type in `ty`, inner ones before the tuples containing them.
+fn tuples_in<'t, 'a>(ty: &'t Type<'a>, tuples: &mut Vec<&'t Tuple<'a>>) {
+    match ty {
+        Type::Simple(_) => {}
+
+        Type::Complex(Complex::Pointer(pointer)) => tuples_in(pointer.base_type(), tuples),
+        Type::Complex(Complex::Ref(reference)) => tuples_in(reference.base_type(), tuples),
+        Type::Complex(Complex::Array(array)) => tuples_in(array.base_type(), tuples),
+        Type::Complex(Complex::Function(function)) => {
+            for parameter in function.parameters() {
+                tuples_in(parameter, tuples);
+            }
+            tuples_in(function.return_type(), tuples);
+        }
+        Type::Complex(Complex::Tuple(tuple)) => {
+            for element in tuple.elements() {
+                tuples_in(element, tuples);
+            }
+            tuples.push(tuple);
+        }
+        Type::Complex(Complex::Generic(generic)) => {
+            for argument in generic.arguments() {
+                tuples_in(argument, tuples);
+            }
+        }
+        Type::Nullable(nullable) => tuples_in(nullable.inner_type(), tuples),
+    }
+}
+
+fn tuple_struct_name(tuple: &Tuple) -> String {
+    format!(
+        "newton_{}",
+        mangle(&Type::Complex(Complex::Tuple(tuple.clone())))
+    )
+}
+
+// A C identifier that is unique to `ty`. Every compound part states how many parts follow it, so different types
+// never end up with the same name.
+fn mangle(ty: &Type) -> String {
+    match ty {
+        Type::Simple(Simple::UserDefinedType(identifier)) => {
+            let mut identifier = identifier.clone();
+            format!("{}_{}", identifier.file(), identifier.name())
+        }
+        Type::Simple(simple) => simple.to_string(),
+
+        Type::Complex(Complex::Pointer(pointer)) => {
+            format!("ptr{}_{}", pointer.size(), mangle(pointer.base_type()))
+        }
+        Type::Complex(Complex::Ref(reference)) => {
+            format!("ref{}_{}", reference.size(), mangle(reference.base_type()))
+        }
+        Type::Complex(Complex::Array(array)) => match array.length() {
+            Some(length) => format!("arr{}_{}", length, mangle(array.base_type())),
+            None => format!("arr_{}", mangle(array.base_type())),
+        },
+        Type::Complex(Complex::Function(function)) => format!(
+            "fn{}_{}_{}",
+            function.parameters().len(),
+            function
+                .parameters()
+                .iter()
+                .map(mangle)
+                .collect::<Vec<String>>()
+                .join("_"),
+            mangle(function.return_type())
+        ),
+        Type::Complex(Complex::Tuple(tuple)) => format!(
+            "tuple{}_{}",
+            tuple.elements().len(),
+            tuple
+                .elements()
+                .iter()
+                .map(mangle)
+                .collect::<Vec<String>>()
+                .join("_")
+        ),
+
+        Type::Complex(Complex::Generic(generic)) => format!(
+            "gen{}_{}_{}",
+            generic.arguments().len(),
+            mangle(generic.base_type()),
+            generic
+                .arguments()
+                .iter()
+                .map(mangle)
+                .collect::<Vec<String>>()
+                .join("_")
+        ),
+
+        Type::Nullable(nullable) => format!("opt_{}", mangle(nullable.inner_type())),
+    }
+}
+
+// The lexer has already replaced escape sequences with the characters they stand for, so they need escaping again.
+fn lower_char(literal: &str) -> String {
+    match literal {
+        "\n" => "'\\n'".to_owned(),
+        "\r" => "'\\r'".to_owned(),
+        "\t" => "'\\t'".to_owned(),
+        "\0" => "'\\0'".to_owned(),
+        "\\" => "'\\\\'".to_owned(),
+        "'" => "'\\''".to_owned(),
+
+        c => format!("'{}'", c),
+    }
+}
+
+// Strings are decoded by the lexer, so everything C would read as anything but the character itself is escaped again.
+// Other control characters use octal escapes, as a hexadecimal one would take any hexadecimal digit after it along.
+fn lower_string(literal: &str) -> String {
+    let mut lowered = String::with_capacity(literal.len() + 2);
+    lowered.push('"');
+
+    for c in literal.chars() {
+        match c {
+            '\n' => lowered.push_str("\\n"),
+            '\r' => lowered.push_str("\\r"),
+            '\t' => lowered.push_str("\\t"),
+            '\\' => lowered.push_str("\\\\"),
+            '"' => lowered.push_str("\\\""),
+            c if c.is_ascii_control() => lowered.push_str(&format!("\\{:03o}", c as u32)),
+
+            c => lowered.push(c),
+        }
+    }
+
+    lowered.push('"');
+    lowered
+}
+
+fn lower_simple(ty: &Simple) -> String {
+    match ty {
+        Simple::Integer(integer) => {
+            let mut integer = *integer;
+            let prefix = if integer.signed() { "" } else { "u" };
+
+            format!("{}int{}_t", prefix, integer.size())
+        }
+        Simple::Float(float) => match { *float }.size() {
+            32 => "float".to_owned(),
+            _ => "double".to_owned(),
+        },
+        Simple::Bool => "bool".to_owned(),
+        Simple::Character => "char".to_owned(),
+        Simple::Void => "void".to_owned(),
+        Simple::String => "const char*".to_owned(),
+        Simple::UserDefinedType(identifier) => format!("struct {}", identifier.clone().name()),
+
+        Simple::TypeParameter(name) => {
+            panic!(
+                "type parameter '{}' has to be monomorphized for the C backend",
+                name
+            )
+        }
+        Simple::VarArgs => panic!("varargs have no C type"),
     }
 }
 
@@ -59,7 +786,140 @@ impl Backend for C {
         self.source.push_str(&code.to_owned());
     }
 
-    fn generate_header(&mut self) -> () {
-        self.emit("// This code has been generated by Newton's official C backend.");
+    // The output is guarded, so it can be included as a header as well.
+    fn generate_header(&mut self, module: &str) -> () {
+        let guard = include_guard(module);
+
+        self.emit("// This code has been generated by Newton's official C backend.\n");
+        self.emit(&format!("#ifndef {}\n#define {}\n", guard, guard));
+        self.emit(INCLUDES);
+    }
+
+    // Definitions follow every prototype, after the runtime they use.
+    fn generate_footer(&mut self, module: &str) {
+        if self.concatenates {
+            self.emit(STRING_CONCAT);
+        }
+
+        let definitions = std::mem::take(&mut self.definitions);
+        self.emit(&definitions);
+        self.emit(&format!("\n#endif // {}\n", include_guard(module)));
+    }
+
+    // Functions are set apart by a blank line.
+    fn begin_function(&mut self, _function: &TopLevel) {
+        self.emit("\n");
+    }
+
+    // Extern variables, constants and structs are lowered here, and functions in `generate_function`.
+    fn generate(&mut self, program: &Program, diagnostics: &mut DiagnosticSink) {
+        let constants = evaluate_constants(program);
+
+        for top_level in &program.0 {
+            match top_level {
+                TopLevel::ExternVariable { name, ty, .. } => {
+                    if check_type(ty, diagnostics) {
+                        self.define_tuples(&ty.node);
+                        self.emit(&format!("{}\n", Self::extern_variable(name.node, &ty.node)));
+                    }
+                }
+
+                // Constants with an error are reported by the resolver already.
+                TopLevel::Constant { declaration, .. } => {
+                    let name = declaration.name.node;
+                    let ty = declaration.ty.borrow();
+
+                    if let (Some(Ok(value)), Some(ty)) = (constants.get(name), ty.as_ref()) {
+                        if check_type(ty, diagnostics) {
+                            self.define_tuples(&ty.node);
+                            self.emit(&format!("{}\n", Self::constant(name, &ty.node, *value)));
+                        }
+                    }
+                }
+
+                // Generic structs only exist as their instances, which C cannot lower yet.
+                TopLevel::TypeDeclaration {
+                    ty:
+                        TypeDeclaration::StructDefinition {
+                            name,
+                            generic_parameters,
+                            fields,
+                            ..
+                        },
+                    attributes,
+                    ..
+                } if generic_parameters.is_empty() => {
+                    let unsupported = fields
+                        .iter()
+                        .filter(|(_, ty)| !check_type(ty, diagnostics))
+                        .count();
+
+                    if unsupported == 0 {
+                        for (_, ty) in fields {
+                            self.define_tuples(&ty.node);
+                        }
+
+                        let definition = Self::struct_definition(name.node, fields, attributes);
+                        self.emit(&format!("\n{}\n", definition));
+                    }
+                }
+
+                TopLevel::Import { .. }
+                | TopLevel::FunctionDeclaration { .. }
+                | TopLevel::TypeDeclaration { .. }
+                | TopLevel::Error { .. } => {}
+            }
+        }
+    }
+
+    fn generate_function(&mut self, function: &TopLevel, diagnostics: &mut DiagnosticSink) {
+        let TopLevel::FunctionDeclaration {
+            name,
+            arguments,
+            body,
+            return_type,
+            is_external,
+            ..
+        } = function
+        else {
+            panic!("expected a function declaration");
+        };
+
+        let parameters = arguments
+            .parameters
+            .iter()
+            .filter(|Parameter(_, ty)| ty.node != Type::Simple(Simple::VarArgs));
+
+        // Every type is checked, so all of them are reported at once.
+        let unsupported = parameters
+            .map(|Parameter(_, ty)| ty)
+            .chain(std::iter::once(return_type))
+            .filter(|ty| !check_type(ty, diagnostics))
+            .count();
+
+        if unsupported == 0 {
+            for Parameter(_, ty) in &arguments.parameters {
+                self.define_tuples(&ty.node);
+            }
+            self.define_tuples(&return_type.node);
+
+            let prototype = Self::prototype(name.node, arguments, &return_type.node);
+            self.emit(&format!("{}\n", prototype));
+
+            // A function whose body cannot be lowered yet is only declared.
+            if !is_external {
+                if let Some(body) = self.lower_block(body, 1, diagnostics) {
+                    let signature = Self::signature(name.node, arguments, &return_type.node, true);
+                    self.definitions.push_str(&format!("\n{} {{\n{}}}\n", signature, body));
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self) -> String {
+        self.tuples.clear();
+        self.definitions.clear();
+        self.concatenates = false;
+        std::mem::take(&mut self.source)
     }
 }
@@ -0,0 +1,453 @@
+use super::super::api::*;
+use crate::ast::ast::*;
+use crate::error::diagnostic::*;
+use crate::lexer::lexer::*;
+use crate::lexer::token::*;
+use crate::parser::span::*;
+use crate::semantic::consteval::*;
+use crate::types::types::*;
+
+/*
+ * Newton's LLVM IR backend. It emits textual LLVM IR that can be fed straight to `llc` or `clang`.
+ *
+ * This is merely a skeleton for now: function definitions, variables, integer arithmetic and returns are supported.
+ * Everything else is reported to the diagnostics as unsupported. Programs have to be resolved first, since the types of
+ * variables and expressions are needed to lower them, and the constant parts of every expression are folded before it is
+ * lowered.
+ *
+ * Newton (C) 2023
+ */
+
+#[derive(Debug)]
+pub struct LLVM {
+    // Fields needed to provide `Backend` all the information about the new backend.
+    pub name: String,
+    pub description: String,
+    pub author: String,
+    pub target: String,
+
+    pub source: String,
+
+    // Counter used to name the temporaries of the function currently being generated.
+    temporaries: usize,
+}
+
+impl LLVM {
+    pub fn new() -> Self {
+        Self {
+            name: "Newton LLVM IR backend".to_owned(),
+            description:
+                "The official LLVM IR backend for Newton. Created by the Newton project creators"
+                    .to_owned(),
+            author: "Newton Team".to_owned(),
+            target: "LLVM IR".to_owned(),
+
+            source: String::new(),
+
+            temporaries: 0,
+        }
+    }
+
+    // Generate the instructions for a statement of a function returning `return_type`, returning whether it returned.
+    // Variables are never assigned to (assignments cannot be lowered yet), so they are just names for the operand
+    // holding their value.
+    fn generate_statement<'p>(
+        &mut self,
+        statement: &Statement<'p>,
+        return_type: &Type,
+        lowered_return_type: &str,
+        locals: &mut std::collections::HashMap<&'p str, String>,
+        diagnostics: &mut DiagnosticSink,
+    ) -> bool {
+        match statement {
+            Statement::ReturnStatement(_, Some(expression)) => {
+                let value = self.generate_expression(&fold(expression), return_type, locals, diagnostics);
+
+                if let Some(value) = &value {
+                    self.emit(&format!("  ret {} {}\n", lowered_return_type, value));
+                }
+                value.is_some()
+            }
+
+            Statement::ReturnStatement(_, None) => {
+                self.emit("  ret void\n");
+
+                true
+            }
+
+            Statement::VariableDeclaration(declaration) => {
+                let ty = declaration.ty.borrow().as_ref().map(|ty| ty.node.clone());
+                let value = resolved_type(ty, declaration.name.span, diagnostics)
+                    .and_then(|ty| self.generate_expression(&fold(&declaration.value), &ty, locals, diagnostics));
+
+                // A variable that cannot be lowered has been reported already, so later uses of it read `undef` instead
+                // of reporting it a second time.
+                locals.insert(declaration.name.node, value.unwrap_or_else(|| String::from("undef")));
+                false
+            }
+
+            Statement::ExpressionStatement(expression) => {
+                if let Some(ty) = resolved_type(expression.node.clone_ty(), expression.span, diagnostics) {
+                    self.generate_expression(&fold(expression), &ty, locals, diagnostics);
+                }
+                false
+            }
+
+            Statement::IfStatement(statement) => {
+                unsupported_statement("if", statement.condition.span, diagnostics)
+            }
+            Statement::WhileStatement(statement) => {
+                unsupported_statement("while", statement.condition.span, diagnostics)
+            }
+            Statement::BlockStatement(statement) => {
+                unsupported_statement("block", statement.body.1, diagnostics)
+            }
+            Statement::MatchStatement(statement) => {
+                unsupported_statement("match", statement.keyword.span, diagnostics)
+            }
+            Statement::BreakStatement(control) => {
+                unsupported_statement("break", control.keyword.span, diagnostics)
+            }
+            Statement::ContinueStatement(control) => {
+                unsupported_statement("continue", control.keyword.span, diagnostics)
+            }
+            Statement::DeleteStatement(expression) => {
+                unsupported_statement("delete", expression.span, diagnostics)
+            }
+            Statement::FunctionDeclaration(function) => match function.as_ref() {
+                TopLevel::FunctionDeclaration { name, .. } => {
+                    unsupported_statement("fn", name.span, diagnostics)
+                }
+                _ => false,
+            },
+        }
+    }
+
+    // Generate the instructions for an expression of type `ty`, returning the operand that holds its value, or nothing if
+    // some part of it cannot be lowered.
+    fn generate_expression(
+        &mut self,
+        expression: &Spanned<Expression>,
+        ty: &Type,
+        locals: &std::collections::HashMap<&str, String>,
+        diagnostics: &mut DiagnosticSink,
+    ) -> Option<String> {
+        let operand = match expression.node.kind() {
+            ExpressionKind::DecLiteral(literal) => literal.to_string(),
+            ExpressionKind::FloatLiteral(literal) => lower_float(literal, ty),
+            ExpressionKind::BoolLiteral(value) => value.to_string(),
+            ExpressionKind::Constant(value) => value.to_string(),
+
+            ExpressionKind::Identifier(name) => match locals.get(name) {
+                Some(operand) => operand.clone(),
+                None => {
+                    diagnostics.error(
+                        expression.span,
+                        format!(
+                            "'{}' cannot be lowered to LLVM IR, only parameters and variables are supported yet",
+                            name
+                        ),
+                    );
+                    return None;
+                }
+            },
+
+            ExpressionKind::Grouping(inner) => {
+                self.generate_expression(inner, ty, locals, diagnostics)?
+            }
+
+            // Floats have an instruction of their own, as `0.0 - x` is not `-x` for `x` being `0.0`.
+            ExpressionKind::Negate(_, inner) => {
+                let value = self.generate_expression(inner, ty, locals, diagnostics)?;
+                let temporary = self.temporary();
+
+                let negation = match ty {
+                    Type::Simple(Simple::Float(_)) => format!("fneg {} {}", lower_type(ty)?, value),
+                    _ => format!("sub {} 0, {}", lower_type(ty)?, value),
+                };
+
+                self.emit(&format!("  {} = {}\n", temporary, negation));
+                temporary
+            }
+
+            ExpressionKind::BitNot(_, inner) => {
+                let value = self.generate_expression(inner, ty, locals, diagnostics)?;
+                let temporary = self.temporary();
+
+                self.emit(&format!(
+                    "  {} = xor {} {}, -1\n",
+                    temporary,
+                    lower_type(ty)?,
+                    value
+                ));
+                temporary
+            }
+
+            ExpressionKind::Binary(left, operator, right) => {
+                let Type::Simple(Simple::Integer(integer)) = ty else {
+                    diagnostics.error(
+                        expression.span,
+                        format!(
+                            "arithmetic on '{}' cannot be lowered to LLVM IR, only integer arithmetic is supported yet",
+                            ty
+                        ),
+                    );
+                    return None;
+                };
+
+                let mut integer = *integer;
+                let signed = integer.signed();
+                let instruction = match operator.node {
+                    TokenType::Plus => "add",
+                    TokenType::Minus => "sub",
+                    TokenType::Star => "mul",
+                    TokenType::Slash if signed => "sdiv",
+                    TokenType::Slash => "udiv",
+                    TokenType::Percent if signed => "srem",
+                    TokenType::Percent => "urem",
+
+                    _ => {
+                        diagnostics.error(
+                            operator.span,
+                            format!(
+                                "operator '{}' cannot be lowered to LLVM IR yet",
+                                operator.node
+                            ),
+                        );
+                        return None;
+                    }
+                };
+
+                let left = self.generate_expression(left, ty, locals, diagnostics)?;
+                let right = self.generate_expression(right, ty, locals, diagnostics)?;
+                let temporary = self.temporary();
+
+                self.emit(&format!(
+                    "  {} = {} {} {}, {}\n",
+                    temporary,
+                    instruction,
+                    lower_type(ty)?,
+                    left,
+                    right
+                ));
+
+                temporary
+            }
+
+            _ => {
+                diagnostics.error(
+                    expression.span,
+                    format!(
+                        "expression '{}' cannot be lowered to LLVM IR yet",
+                        expression.node
+                    ),
+                );
+                return None;
+            }
+        };
+
+        Some(operand)
+    }
+
+    fn temporary(&mut self) -> String {
+        let temporary = format!("%t{}", self.temporaries);
+        self.temporaries += 1;
+
+        temporary
+    }
+}
+
+// The type the resolver gave to something, which every lowered value needs. Programs have to be resolved first.
+fn resolved_type<'a>(ty: Option<Type<'a>>, span: Span, diagnostics: &mut DiagnosticSink) -> Option<Type<'a>> {
+    if ty.is_none() {
+        diagnostics.error(
+            span,
+            "the type of this has to be resolved before lowering it to LLVM IR".to_owned(),
+        );
+    }
+    ty
+}
+
+fn unsupported_statement(keyword: &str, span: Span, diagnostics: &mut DiagnosticSink) -> bool {
+    diagnostics.error(span, format!("'{}' statements cannot be lowered to LLVM IR yet", keyword));
+    false
+}
+
+// LLVM IR writes constants of both float types as the bits of a `double`, which `float` constants have to be exactly
+// representable as a `float` in. The lexer only lets valid literals through.
+fn lower_float(literal: &str, ty: &Type) -> String {
+    let value = float_value(literal).unwrap_or_default();
+    let value = match ty {
+        Type::Simple(Simple::Float(float)) if { *float }.size() == 32 => f64::from(value as f32),
+        _ => value,
+    };
+
+    format!("0x{:016X}", value.to_bits())
+}
+
+// Map a Newton type to its LLVM IR counterpart. Integers keep their width, so `i16` and `u16` both become `i16`.
+fn lower_type(ty: &Type) -> Option<String> {
+    let lowered = match ty {
+        Type::Simple(Simple::Integer(integer)) => format!("i{}", { *integer }.size()),
+        Type::Simple(Simple::Float(float)) => match { *float }.size() {
+            32 => "float".to_owned(),
+            _ => "double".to_owned(),
+        },
+        Type::Simple(Simple::Bool) => "i1".to_owned(),
+        Type::Simple(Simple::Character) => "i8".to_owned(),
+        Type::Simple(Simple::Void) => "void".to_owned(),
+        Type::Simple(Simple::String) | Type::Complex(_) | Type::Nullable(_) => "ptr".to_owned(),
+
+        _ => return None,
+    };
+
+    Some(lowered)
+}
+
+// `lower_type`, reporting the type if it has no counterpart.
+fn lower_checked(ty: &Spanned<Type>, diagnostics: &mut DiagnosticSink) -> Option<String> {
+    let lowered = lower_type(&ty.node);
+
+    if lowered.is_none() {
+        diagnostics.error(
+            ty.span,
+            format!("type '{}' cannot be lowered to LLVM IR yet", ty.node),
+        );
+    }
+    lowered
+}
+
+impl Default for LLVM {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for LLVM {
+    fn backend_name(&self) -> &String {
+        &self.name
+    }
+
+    fn backend_description(&self) -> &String {
+        &self.description
+    }
+
+    fn backend_author(&self) -> &String {
+        &self.author
+    }
+
+    fn backend_target(&self) -> &String {
+        &self.target
+    }
+
+    fn source(&self) -> &String {
+        &self.source
+    }
+
+    fn emit(&mut self, code: &str) {
+        self.source.push_str(code);
+    }
+
+    fn generate_header(&mut self, _module: &str) {
+        self.emit("; This code has been generated by Newton's official LLVM IR backend.\n\n");
+    }
+
+    // Generate a function definition (or a declaration, for external functions). Only functions are lowered so far,
+    // and functions with a type that cannot be lowered are left out.
+    fn generate_function(&mut self, function: &TopLevel, diagnostics: &mut DiagnosticSink) {
+        let TopLevel::FunctionDeclaration {
+            name,
+            arguments,
+            body,
+            return_type,
+            is_external,
+            ..
+        } = function
+        else {
+            panic!("expected a function declaration");
+        };
+
+        let parameters = arguments
+            .parameters
+            .iter()
+            .filter(|Parameter(_, ty)| ty.node != Type::Simple(Simple::VarArgs))
+            .collect::<Vec<&Parameter>>();
+
+        // Every type is lowered before giving up, so all of them are reported at once.
+        let types = parameters
+            .iter()
+            .map(|Parameter(_, ty)| lower_checked(ty, diagnostics))
+            .collect::<Vec<Option<String>>>();
+        let lowered_return_type = lower_checked(return_type, diagnostics);
+
+        let (Some(mut types), Some(lowered_return_type)) = (
+            types.into_iter().collect::<Option<Vec<String>>>(),
+            lowered_return_type,
+        ) else {
+            return;
+        };
+        let return_type = &return_type.node;
+
+        if *is_external {
+            if arguments.varargs {
+                types.push("...".to_owned());
+            }
+
+            self.emit(&format!(
+                "declare {} @{}({})\n\n",
+                lowered_return_type,
+                name.node,
+                types.join(", ")
+            ));
+
+            return;
+        }
+
+        let mut locals = parameters
+            .iter()
+            .map(|Parameter(name, _)| (name.node, format!("%{}", name.node)))
+            .collect::<std::collections::HashMap<&str, String>>();
+
+        // Every discarded parameter is still passed, but it needs a name of its own that no Newton identifier can have.
+        let signature = parameters
+            .iter()
+            .zip(&types)
+            .enumerate()
+            .map(|(index, (Parameter(name, _), ty))| match name.node {
+                "_" => format!("{} %.discard{}", ty, index),
+                name => format!("{} %{}", ty, name),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        self.temporaries = 0;
+        self.emit(&format!(
+            "define {} @{}({}) {{\nentry:\n",
+            lowered_return_type, name.node, signature
+        ));
+
+        // Anything after a `ret` is dead code, which is left out.
+        let mut returned = false;
+        for statement in &body.0 {
+            let lowered = &lowered_return_type;
+            if self.generate_statement(statement, return_type, lowered, &mut locals, diagnostics) {
+                returned = true;
+                break;
+            }
+        }
+
+        if !returned {
+            if let Type::Simple(Simple::Void) = return_type {
+                self.emit("  ret void\n");
+            } else {
+                self.emit("  unreachable\n");
+            }
+        }
+
+        self.emit("}\n\n");
+    }
+
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.source)
+    }
+}
@@ -1 +1,2 @@
 pub mod c;
+pub mod llvm;
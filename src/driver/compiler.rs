@@ -0,0 +1,368 @@
+/*
+ * The compiler driver, which owns every source of a program and runs the passes over all of them.
+ *
+ * Tokens and syntax trees borrow from the `Source` they were created from. Instead of every caller keeping its sources
+ * alive on its own, a `Compiler` holds them, and everything it produces borrows from the compiler itself.
+ *
+ * Sources can also be loaded from files, along with every module they import. Files are cached by their canonical
+ * path, so a module imported from several places is still only read and parsed once, and is the same module no matter
+ * which path it is imported by. `refresh` reloads the files that changed since, which is what incremental runs use.
+ *
+ * Newton (C) 2023
+ */
+
+use super::import::*;
+use crate::ast::ast::*;
+use crate::error::diagnostic::*;
+use crate::lexer::lexer::*;
+use crate::lexer::token::*;
+use crate::parser::parser::*;
+use crate::parser::span::*;
+use crate::semantic::cfg::*;
+use crate::semantic::error::*;
+use crate::semantic::typecheck::*;
+use crate::{find_errors, print_error, with_stack, Source};
+
+#[derive(Debug, Default)]
+pub struct Compiler {
+    sources: Vec<Source>,
+    options: ResolverOptions,
+    parser_options: ParserOptions,
+    imports: ImportResolver,
+
+    // Every source loaded from a file, by canonical path.
+    files: std::collections::HashMap<std::path::PathBuf, CachedFile>,
+
+    // The module each import of a loaded source refers to, by the index of the source and the name used in the import.
+    // A file is named after the first import that reached it, so any other import of it names it differently.
+    module_names: std::collections::HashMap<usize, std::collections::HashMap<String, String>>,
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    // Index of the source in `Compiler::sources`.
+    index: usize,
+    modified: Option<std::time::SystemTime>,
+    hash: u64,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::path::PathBuf, std::io::Error),
+    NotFound(ModuleNotFoundError),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(path, error) => write!(f, "cannot read '{}': {}", path.display(), error),
+            Self::NotFound(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_with_options(options: ResolverOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    // Options every source is parsed with, like the features it may use.
+    pub fn set_parser_options(&mut self, options: ParserOptions) {
+        self.parser_options = options;
+    }
+
+    // Directories searched for imported modules, in the order they were added.
+    pub fn add_search_path<P: AsRef<std::path::Path>>(&mut self, path: P) {
+        self.imports.add_search_path(path);
+    }
+
+    // Sources are modules named after their source, which is how other sources import them.
+    pub fn add_source(&mut self, source: Source) {
+        self.sources.push(source);
+    }
+
+    #[inline]
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    // Load the file at `path` as a module named after the file, and then every module it imports, transitively.
+    // Files that are already loaded are not read again.
+    pub fn add_file<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), LoadError> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+
+        self.load(vec![(None, name, path.to_path_buf())])
+    }
+
+    // Reload every file that changed since it was loaded, along with the modules it newly imports. Files whose
+    // modification time changed but whose contents did not are kept as they are. Returns the number of reloaded files.
+    //
+    // A file only counts as up to date once all of its imports are loaded, so after an error, the next refresh tries
+    // the same files again.
+    pub fn refresh(&mut self) -> Result<usize, LoadError> {
+        let mut reloaded = vec![];
+        let mut pending = vec![];
+
+        for (path, file) in &self.files {
+            let modified = modification_time(path);
+            if modified.is_some() && modified == file.modified {
+                continue;
+            }
+
+            let code = read(path)?;
+            let hash = hash_of(&code);
+
+            let source = &mut self.sources[file.index];
+            if hash != file.hash {
+                source.code = code;
+                self.module_names.remove(&file.index);
+
+                let imports = imported_modules(source, path, &self.imports)?;
+                pending.extend(imports.into_iter().map(|(name, path)| (Some(file.index), name, path)));
+            }
+
+            reloaded.push((path.clone(), modified, hash));
+        }
+
+        self.load(pending)?;
+
+        let mut changed = 0;
+        for (path, modified, hash) in reloaded {
+            let file = self.files.get_mut(&path).unwrap();
+            if file.hash != hash {
+                changed += 1;
+            }
+
+            file.modified = modified;
+            file.hash = hash;
+        }
+
+        Ok(changed)
+    }
+
+    // Load the files of `pending`, given with the index of the source importing them, if any, and the name they are
+    // imported by. A file is loaded as a module named after the first import that reaches it, or after its path if a
+    // module of that name exists already.
+    fn load(&mut self, mut pending: Vec<(Option<usize>, String, std::path::PathBuf)>) -> Result<(), LoadError> {
+        while let Some((importer, name, path)) = pending.pop() {
+            let path = std::fs::canonicalize(&path).map_err(|error| LoadError::Io(path, error))?;
+
+            let index = match self.files.get(&path) {
+                Some(file) => file.index,
+                None => {
+                    let index = self.sources.len();
+                    let module = if self.sources.iter().any(|source| source.name == name) {
+                        path.display().to_string()
+                    } else {
+                        name.clone()
+                    };
+
+                    let code = read(&path)?;
+                    let source = Source::new(&module, &code);
+                    let imports = imported_modules(&source, &path, &self.imports)?;
+                    pending.extend(imports.into_iter().map(|(name, path)| (Some(index), name, path)));
+
+                    self.files.insert(
+                        path.clone(),
+                        CachedFile {
+                            index,
+                            modified: modification_time(&path),
+                            hash: hash_of(&code),
+                        },
+                    );
+                    self.sources.push(source);
+
+                    index
+                }
+            };
+
+            if let Some(importer) = importer {
+                let module = self.sources[index].name.clone();
+                self.module_names.entry(importer).or_default().insert(name, module);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Parse and resolve every source. Each one is resolved on its own, with all the others available for import.
+    // Declarations that are not active for the options' `cfg` are left out of the programs. This runs on a stack of
+    // `STACK_SIZE`, which deeply nested code needs.
+    pub fn compile(&self) -> Compilation<'_> {
+        with_stack(|| self.compile_unchecked())
+    }
+
+    fn compile_unchecked(&self) -> Compilation<'_> {
+        let programs = self
+            .sources
+            .iter()
+            .map(|source| {
+                let mut parser =
+                    Parser::new_with_options(Lexer::new(source), self.parser_options.clone());
+                let mut program = parser.parse();
+                remove_inactive(&mut program, &self.options.cfg);
+
+                (source, program)
+            })
+            .collect::<Vec<_>>();
+
+        let mut errors = vec![];
+        let mut warnings = vec![];
+
+        for (index, (source, program)) in programs.iter().enumerate() {
+            let mut resolver = Resolver::new_with_options(source, self.options.clone());
+            for (alias, module) in self.module_names.get(&index).into_iter().flatten() {
+                resolver.alias_module(alias, module);
+            }
+
+            for (module, module_program) in &programs {
+                if module != source {
+                    resolver.declare_module(module, module_program);
+                }
+            }
+
+            resolver.resolve(program);
+
+            let (resolver_errors, resolver_warnings) = resolver.finish();
+            errors.extend(resolver_errors);
+            warnings.extend(resolver_warnings);
+        }
+
+        Compilation {
+            programs,
+            errors,
+            warnings,
+        }
+    }
+}
+
+// The modules `source` imports, named as they are in the import, along with their files. Imports only need the lexer
+// to be found, so the source is still parsed just once, along with all the others.
+fn imported_modules(
+    source: &Source,
+    path: &std::path::Path,
+    imports: &ImportResolver,
+) -> Result<Vec<(String, std::path::PathBuf)>, LoadError> {
+    let mut modules = vec![];
+    let mut lexer = Lexer::new(source).filter_map(Result::ok).peekable();
+
+    while let Some(token) = lexer.next() {
+        if !matches!(token.node, TokenType::Import | TokenType::From) {
+            continue;
+        }
+
+        if let Some(Spanned {
+            node: TokenType::StringLiteral(name),
+            ..
+        }) = lexer.peek()
+        {
+            let file = imports
+                .find(name, Some(path))
+                .map_err(LoadError::NotFound)?;
+            modules.push((name.to_string(), file));
+        }
+    }
+
+    Ok(modules)
+}
+
+fn read(path: &std::path::Path) -> Result<String, LoadError> {
+    std::fs::read_to_string(path).map_err(|error| LoadError::Io(path.to_path_buf(), error))
+}
+
+fn modification_time(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn hash_of(code: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Everything a `Compiler` produced for its sources. Parse errors stay in the programs, as they do everywhere else.
+#[derive(Debug)]
+pub struct Compilation<'a> {
+    pub programs: Vec<(&'a Source, Program<'a>)>,
+    pub errors: Vec<ResolverError<'a>>,
+    pub warnings: Vec<ResolverWarning<'a>>,
+}
+
+impl<'a> Compilation<'a> {
+    pub fn program(&self, name: &str) -> Option<&Program<'a>> {
+        self.programs
+            .iter()
+            .find(|(source, _)| source.name == name)
+            .map(|(_, program)| program)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+            || self
+                .programs
+                .iter()
+                .any(|(_, program)| !find_errors(program).is_empty())
+    }
+
+    // Print everything found in the sources, one source after the other. The errors and warnings of a source are printed
+    // in the order they appear in it, whether the parser or the resolver found them. Returns the number of errors,
+    // warnings left out.
+    pub fn report<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<usize> {
+        let mut count = 0;
+
+        for (source, program) in &self.programs {
+            let mut reports = find_errors(program)
+                .iter()
+                .map(|diagnostic| {
+                    let key = by_position(diagnostic.span, diagnostic.severity);
+                    (key, diagnostic.render(source))
+                })
+                .collect::<Vec<_>>();
+
+            reports.extend(
+                self.errors
+                    .iter()
+                    .filter(|error| std::ptr::eq(error.source, *source))
+                    .map(|error| (by_position(error.error_span, Severity::Error), error.to_string())),
+            );
+
+            reports.extend(
+                self.warnings
+                    .iter()
+                    .filter(|warning| std::ptr::eq(warning.source, *source))
+                    .map(|warning| {
+                        let key = by_position(warning.warning_span, Severity::Warning);
+                        (key, warning.to_string())
+                    }),
+            );
+
+            reports.sort_by_key(|(key, _)| *key);
+
+            for ((_, severity), report) in reports {
+                if severity == Severity::Error {
+                    count += 1;
+                }
+
+                print_error(&report, writer)?;
+            }
+        }
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * Source formatting, which only lays out blank lines between top level declarations so far.
+ *
+ * The code is taken as written, along with its comments, and only the lines that are blank between the declarations
+ * change: a single blank line that separates two declarations is kept, since the author put it there on purpose, and
+ * runs of several blank lines become one. Blank lines at the start and the end of the source are dropped. Blank lines
+ * inside braces, strings and comments are left alone.
+ *
+ * Newton (C) 2023
+ */
+
+use crate::lexer::lexer::*;
+use crate::lexer::token::*;
+use crate::parser::span::*;
+use crate::Source;
+
+pub fn format_blank_lines(source: &Source) -> String {
+    let code = source.code.as_str();
+
+    // Every token and comment, along with how it changes the nesting of braces. Tokens the lexer fails on are skipped,
+    // which at worst leaves more blank lines alone than necessary.
+    let mut lexer = Lexer::new(source);
+    let mut items = lexer
+        .by_ref()
+        .filter_map(Result::ok)
+        .filter(|token| token.node != TokenType::Eof)
+        .map(|token| {
+            let depth = match token.node {
+                TokenType::LeftBrace => 1,
+                TokenType::RightBrace => -1,
+                _ => 0,
+            };
+
+            (token.span, depth)
+        })
+        .collect::<Vec<(Span, i32)>>();
+
+    items.extend(lexer.comments().iter().map(|span| (*span, 0)));
+    items.sort_by_key(|(span, _)| span.start);
+
+    let mut items = items.into_iter().peekable();
+    let mut output = String::with_capacity(code.len());
+
+    let mut depth = 0;
+    // The last byte any token or comment seen so far covers, so a line within a multiline string is not blank.
+    let mut covered = None;
+    let mut pending_blank_line = false;
+    let mut line_start = 0;
+
+    for line in code.split_inclusive('\n') {
+        while let Some((span, change)) = items.next_if(|(span, _)| span.start < line_start) {
+            depth += change;
+            covered = covered.max(Some(span.end));
+        }
+
+        let is_inside_item = matches!(covered, Some(end) if end >= line_start);
+        line_start += line.len();
+
+        if depth == 0 && !is_inside_item && line.trim().is_empty() {
+            pending_blank_line = true;
+            continue;
+        }
+
+        if pending_blank_line && !output.is_empty() {
+            output.push('\n');
+        }
+        pending_blank_line = false;
+
+        output.push_str(line);
+    }
+
+    output
+}
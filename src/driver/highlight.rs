@@ -0,0 +1,129 @@
+/*
+ * Classification of the tokens of a source, for editors that highlight Newton code.
+ *
+ * The lexer alone cannot tell a struct name from a variable, both are identifiers to it. The declarations the resolver
+ * collects from the source settle that, so identifiers naming a type declared in the source are classified as types.
+ *
+ * Newton (C) 2023
+ */
+
+use crate::lexer::lexer::*;
+use crate::lexer::token::*;
+use crate::parser::parser::*;
+use crate::parser::span::*;
+use crate::semantic::typecheck::*;
+use crate::{with_stack, Source};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    Keyword,
+    Type,
+    Identifier,
+    Literal,
+    // Operators along with all other punctuation, like parentheses and semicolons.
+    Operator,
+    Comment,
+}
+
+// Every token and comment of `source` in source order, along with its class. Tokens the lexer fails on are left out.
+pub fn highlight(source: &Source) -> Vec<(Span, TokenClass)> {
+    let program = with_stack(|| Parser::new(Lexer::new(source)).parse());
+    let mut resolver = Resolver::new(source);
+    resolver.declare_module(source, &program);
+
+    let is_type = |name: &str| {
+        resolver
+            .module_map()
+            .get_user_type(&source.name, name)
+            .is_some()
+    };
+
+    let mut lexer = Lexer::new(source);
+    let mut tokens = lexer
+        .by_ref()
+        .filter_map(Result::ok)
+        .filter(|token| token.node != TokenType::Eof)
+        .map(|token| (quoted_span(source, &token), classify(&token.node, is_type)))
+        .collect::<Vec<_>>();
+
+    tokens.extend(
+        lexer
+            .comments()
+            .iter()
+            .map(|span| (*span, TokenClass::Comment)),
+    );
+    tokens.sort_by_key(|(span, _)| span.start);
+
+    tokens
+}
+
+// Literal characters only span their contents, but their quotes belong to them just as well. Strings span their quotes
+// already.
+fn quoted_span(source: &Source, token: &Spanned<TokenType>) -> Span {
+    let span = token.span;
+
+    match token.node {
+        TokenType::Char(_) => match source.code[span.start..].find('\'') {
+            Some(quote) => Span::new(span.start - 1, span.start + quote),
+            None => span,
+        },
+
+        _ => span,
+    }
+}
+
+fn classify<F>(token: &TokenType, is_type: F) -> TokenClass
+where
+    F: Fn(&str) -> bool,
+{
+    match token {
+        TokenType::Identifier(name) if is_type(name) => TokenClass::Type,
+        TokenType::Identifier(_) => TokenClass::Identifier,
+        TokenType::TypeIdentifier(_) => TokenClass::Type,
+
+        TokenType::NullLiteral
+        | TokenType::DecLiteral(_)
+        | TokenType::FloatLiteral(_)
+        | TokenType::StringLiteral(_)
+        | TokenType::Char(_)
+        | TokenType::True
+        | TokenType::False => TokenClass::Literal,
+
+        TokenType::Let
+        | TokenType::Fn
+        | TokenType::If
+        | TokenType::Else
+        | TokenType::Import
+        | TokenType::From
+        | TokenType::Pub
+        | TokenType::Return
+        | TokenType::Extern
+        | TokenType::While
+        | TokenType::Type
+        | TokenType::Struct
+        | TokenType::Trait
+        | TokenType::Implements
+        | TokenType::Enum
+        | TokenType::New
+        | TokenType::Delete
+        | TokenType::Sizeof
+        | TokenType::As
+        | TokenType::Static
+        | TokenType::Inline
+        | TokenType::Abstract
+        | TokenType::Mut
+        | TokenType::And
+        | TokenType::Or
+        | TokenType::For
+        | TokenType::Break
+        | TokenType::Continue
+        | TokenType::Match
+        | TokenType::Case
+        | TokenType::Default
+        | TokenType::Finally
+        | TokenType::Volatile
+        | TokenType::Register => TokenClass::Keyword,
+
+        _ => TokenClass::Operator,
+    }
+}
@@ -0,0 +1,111 @@
+/*
+ * Finding the files imported modules live in.
+ *
+ * `import "./utils";` and `import "../utils";` are relative to the directory of the importing file. Every other name
+ * is looked up in the search paths, in the order they were added, and the first file that exists wins. Absolute
+ * names are used as they are. Names without an extension get the extension of Newton sources.
+ *
+ * Newton (C) 2023
+ */
+
+use std::path::{Path, PathBuf};
+
+pub const SOURCE_EXTENSION: &str = "nt";
+
+#[derive(Debug, Default, Clone)]
+pub struct ImportResolver {
+    search_paths: Vec<PathBuf>,
+}
+
+// No file exists for a module, along with every path that was tried, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleNotFoundError {
+    pub name: String,
+    pub searched: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for ModuleNotFoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.searched.is_empty() {
+            return write!(
+                f,
+                "cannot find module '{}', there are no search paths",
+                self.name
+            );
+        }
+
+        let searched = self
+            .searched
+            .iter()
+            .map(|path| format!("    {}", path.display()))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        write!(
+            f,
+            "cannot find module '{}', searched in:\n{}",
+            self.name, searched
+        )
+    }
+}
+
+impl std::error::Error for ModuleNotFoundError {}
+
+impl ImportResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_search_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.search_paths.push(path.as_ref().to_path_buf());
+    }
+
+    #[inline]
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    // The file of the module `name`, found through the search paths only.
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        self.find(name, None).ok()
+    }
+
+    // The file of the module `name` imported from the file `importer`, which relative names are resolved against.
+    pub fn find(
+        &self,
+        name: &str,
+        importer: Option<&Path>,
+    ) -> Result<PathBuf, ModuleNotFoundError> {
+        let mut file = PathBuf::from(name);
+        if file.extension().is_none() {
+            file.set_extension(SOURCE_EXTENSION);
+        }
+
+        let candidates = if file.is_absolute() {
+            vec![file]
+        } else if is_relative(name) {
+            let directory = importer
+                .and_then(Path::parent)
+                .unwrap_or_else(|| Path::new("."));
+
+            vec![directory.join(file)]
+        } else {
+            self.search_paths
+                .iter()
+                .map(|path| path.join(&file))
+                .collect()
+        };
+
+        match candidates.iter().find(|candidate| candidate.is_file()) {
+            Some(found) => Ok(found.clone()),
+            None => Err(ModuleNotFoundError {
+                name: name.to_owned(),
+                searched: candidates,
+            }),
+        }
+    }
+}
+
+fn is_relative(name: &str) -> bool {
+    name.starts_with("./") || name.starts_with("../")
+}
@@ -0,0 +1,4 @@
+pub mod compiler;
+pub mod format;
+pub mod highlight;
+pub mod import;
@@ -0,0 +1,331 @@
+/*
+ * A backend-independent representation of the errors and warnings Newton reports.
+ *
+ * Every pass produces its own error type; turning them into a `Diagnostic` lets tools consume all of them the same
+ * way, either rendered for a terminal or as JSON (see `docs/internal-compiler-design-for-newton.md` for the schema).
+ *
+ * Newton (C) 2023
+ */
+
+use crate::parser::span::*;
+use crate::{error_to_string, find_line_index, format_error, format_warning, Source};
+
+// Bumped whenever a field of the JSON output changes its meaning or is removed.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Note => write!(f, "note"),
+        }
+    }
+}
+
+// An additional location that helps explaining a diagnostic, like the earlier definition of a redefined symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// A machine-applicable fix: replacing the source covered by `span` with `replacement` resolves the diagnostic. Spans
+// cannot be empty, so insertions are marked separately and put `replacement` right before `span.start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+    pub insertion: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, span: Span, message: String) -> Self {
+        Self {
+            severity,
+            code: None,
+            message,
+            span,
+            labels: vec![],
+            suggestions: vec![],
+        }
+    }
+
+    pub fn error(span: Span, message: String) -> Self {
+        Self::new(Severity::Error, span, message)
+    }
+
+    pub fn warning(span: Span, message: String) -> Self {
+        Self::new(Severity::Warning, span, message)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: String) -> Self {
+        self.labels.push(Label { span, message });
+        self
+    }
+
+    pub fn with_suggestion(mut self, message: String, span: Span, replacement: String) -> Self {
+        self.suggestions.push(Suggestion {
+            message,
+            span,
+            replacement,
+            insertion: false,
+        });
+        self
+    }
+
+    pub fn with_insertion(mut self, message: String, position: usize, text: String) -> Self {
+        self.suggestions.push(Suggestion {
+            message,
+            span: Span::new(position, position),
+            replacement: text,
+            insertion: true,
+        });
+        self
+    }
+
+    // Render the diagnostic the same way the rest of the compiler prints errors.
+    pub fn render(&self, source: &Source) -> String {
+        let mut rendered = match self.severity {
+            Severity::Error => format_error(source, self.span, self.span, &self.message),
+            _ => format_warning(source, self.span, self.span, &self.message),
+        };
+
+        for label in &self.labels {
+            let (line_number, _) = find_line_index(source, label.span.start);
+
+            rendered.push_str(&format!(
+                "\nnote: {}\n{}",
+                label.message,
+                error_to_string(source, label.span, label.span, line_number, true)
+            ));
+        }
+
+        for suggestion in &self.suggestions {
+            rendered.push_str(&format!(
+                "\nhelp: {}: '{}'",
+                suggestion.message, suggestion.replacement
+            ));
+        }
+
+        rendered
+    }
+
+    // Serialize the diagnostic as a single line of JSON.
+    pub fn to_json(&self, source: &Source) -> String {
+        let mut spans = vec![json_span(source, self.span, true, None)];
+        spans.extend(
+            self.labels
+                .iter()
+                .map(|label| json_span(source, label.span, false, Some(&label.message))),
+        );
+
+        let suggestions = self
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                let span = if suggestion.insertion {
+                    let position = suggestion.span.start;
+                    json_range(source, position, position, false, None)
+                } else {
+                    json_span(source, suggestion.span, false, None)
+                };
+
+                format!(
+                    "{{\"message\":{},\"span\":{},\"replacement\":{}}}",
+                    json_string(&suggestion.message),
+                    span,
+                    json_string(&suggestion.replacement)
+                )
+            })
+            .collect::<Vec<String>>();
+
+        format!(
+            "{{\"version\":{},\"severity\":\"{}\",\"code\":{},\"message\":{},\"spans\":[{}],\"suggestions\":[{}],\"rendered\":{}}}",
+            JSON_SCHEMA_VERSION,
+            self.severity,
+            self.code.map_or("null".to_owned(), json_string),
+            json_string(&self.message),
+            spans.join(","),
+            suggestions.join(","),
+            json_string(&strip_colors(&self.render(source)))
+        )
+    }
+}
+
+// Collects the diagnostics of a pass that keeps going after the first problem, like a backend that leaves out what it
+// cannot lower. Several passes can report to the same sink, which hands them out in source order.
+#[derive(Debug, Default, Clone)]
+pub struct DiagnosticSink {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn error(&mut self, span: Span, message: String) {
+        self.push(Diagnostic::error(span, message));
+    }
+
+    pub fn warning(&mut self, span: Span, message: String) {
+        self.push(Diagnostic::warning(span, message));
+    }
+
+    pub fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, diagnostics: I) {
+        self.diagnostics.extend(diagnostics);
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    #[inline]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    // Every diagnostic in the order it appears in the source, so they read from top to bottom, whichever pass reported
+    // them. See `by_position` for the order.
+    pub fn sorted(&self) -> Vec<&Diagnostic> {
+        let mut sorted = self.diagnostics.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|diagnostic| by_position(diagnostic.span, diagnostic.severity));
+
+        sorted
+    }
+
+    pub fn into_sorted(mut self) -> Vec<Diagnostic> {
+        self.diagnostics
+            .sort_by_key(|diagnostic| by_position(diagnostic.span, diagnostic.severity));
+
+        self.diagnostics
+    }
+}
+
+// The key diagnostics are sorted by: where they start, then errors before warnings and notes. The sort is stable, so
+// diagnostics at the same place stay in the order they were reported in.
+pub fn by_position(span: Span, severity: Severity) -> (usize, Severity) {
+    (span.start, severity)
+}
+
+// Spans are inclusive within the compiler, but byte and column ranges in the JSON output are half-open like everywhere
+// else, so that an insertion has the same start and end. Since spans end at the first byte of their last character,
+// the end is moved past the rest of that character.
+fn json_span(source: &Source, span: Span, is_primary: bool, label: Option<&str>) -> String {
+    json_range(source, span.start, span.end + 1, is_primary, label)
+}
+
+fn json_range(
+    source: &Source,
+    start: usize,
+    end: usize,
+    is_primary: bool,
+    label: Option<&str>,
+) -> String {
+    let length = source.code.len();
+    let start = start.min(length);
+    let mut end = end.clamp(start, length);
+
+    while !source.code.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let (line_start, column_start) = find_line_index(source, start);
+    let (line_end, column_end) = find_line_index(source, end);
+
+    format!(
+        "{{\"file\":{},\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{},\"is_primary\":{},\"label\":{}}}",
+        json_string(&source.name),
+        start,
+        end,
+        line_start,
+        column_start,
+        line_end,
+        column_end,
+        is_primary,
+        label.map_or("null".to_owned(), json_string)
+    )
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+// Remove the ANSI escape sequences used to color the terminal output.
+fn strip_colors(value: &str) -> String {
+    let mut stripped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c == 'm' {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
@@ -1 +1,2 @@
+pub mod diagnostic;
 pub mod error;
\ No newline at end of file
@@ -3,6 +3,7 @@ use crate::parser::error::*;
 use crate::parser::span::*;
 use crate::types::types::*;
 use crate::Source;
+use std::borrow::Cow;
 
 macro_rules! consume_once {
     ($self: ident, $start: ident, $token: expr) => {{
@@ -12,22 +13,6 @@ macro_rules! consume_once {
 }
 
 macro_rules! consume_multiple {
-    ($self: ident, $start: ident, $first: expr, $second: expr) => {{
-        let InputPosition { value: tok, .. } = $self.current.unwrap();
-        $self.advance();
-
-        if let Some(InputPosition { value: new, .. }) = $self.current {
-            if new == tok {
-                $self.advance();
-                Ok($self.spanned($start, $second))
-            } else {
-                Ok($self.spanned($start, $first))
-            }
-        } else {
-            Ok($self.spanned($start, $first))
-        }
-    }};
-
     ($self: ident, $start: ident, $next: expr, $first: expr, $second: expr) => {{
         $self.advance();
 
@@ -70,6 +55,10 @@ pub struct Lexer<'a> {
     chars: std::iter::Peekable<std::str::CharIndices<'a>>,
     current: Option<InputPosition>,
     prev: Option<char>,
+    reached_eof: bool,
+
+    // Comments are not tokens, but tools like highlighters still want to know where they are.
+    comments: Vec<Span>,
 }
 
 impl<'a> Lexer<'a> {
@@ -83,9 +72,17 @@ impl<'a> Lexer<'a> {
             current: InputPosition::new_opt(chars.next()),
             chars,
             prev: None,
+            reached_eof: false,
+            comments: vec![],
         }
     }
 
+    // Every comment scanned so far, in source order.
+    #[inline]
+    pub fn comments(&self) -> &[Span] {
+        &self.comments
+    }
+
     fn pos(&self) -> usize {
         if let Some(InputPosition { pos, .. }) = self.current {
             return pos;
@@ -111,12 +108,8 @@ impl<'a> Lexer<'a> {
     fn advance(&mut self) -> Option<InputPosition> {
         let current = self.current?;
 
-        if self.pos() > 0 {
-            self.prev = Some({
-                let InputPosition { value: prev, .. } = current;
-                prev
-            });
-        }
+        let InputPosition { value: prev, .. } = current;
+        self.prev = Some(prev);
 
         self.current = InputPosition::new_opt(self.chars.next());
         Some(current)
@@ -156,9 +149,7 @@ impl<'a> Lexer<'a> {
         } else {
             Err(self.spanned(
                 start,
-                ParseError::LexingError(LexingError::with_cause(
-                    "non-ascii identifiers are not allowed",
-                )),
+                ParseError::LexingError(LexingError::new(LexingErrorKind::NonAsciiIdentifier)),
             ))
         }
     }
@@ -188,6 +179,7 @@ impl<'a> Lexer<'a> {
                 "if" => TokenType::If,
                 "else" => TokenType::Else,
                 "import" => TokenType::Import,
+                "pub" => TokenType::Pub,
                 "from" => TokenType::From,
                 "return" => TokenType::Return,
                 "extern" => TokenType::Extern,
@@ -210,6 +202,7 @@ impl<'a> Lexer<'a> {
                 "for" => TokenType::For,
                 "break" => TokenType::Break,
                 "continue" => TokenType::Continue,
+                "null" => TokenType::NullLiteral,
                 "true" => TokenType::True,
                 "false" => TokenType::False,
                 "match" => TokenType::Match,
@@ -226,6 +219,12 @@ impl<'a> Lexer<'a> {
 
     fn scan_number(&mut self) -> Scanned<'a> {
         let start = self.pos();
+        if let (Some(InputPosition { value: '0', .. }), Some((_, 'x' | 'X'))) =
+            (self.current, self.chars.peek())
+        {
+            return self.scan_hex_float(start);
+        }
+
         let slice = self.read_while(|c| c.is_digit(10));
 
         if let Some(InputPosition { value: '.', .. }) = self.current {
@@ -243,60 +242,235 @@ impl<'a> Lexer<'a> {
         Ok(self.spanned(start, TokenType::DecLiteral(slice)))
     }
 
-    fn scan_string(&mut self) -> Scanned<'a> {
+    // `0x1.8p3` is 1.5 times 2 to the power of 3. The exponent is required, even without a fraction, so `0xFF` is
+    // reported as a float missing its exponent rather than taken as an integer. `float_value` decodes these literals.
+    fn scan_hex_float(&mut self, start: usize) -> Scanned<'a> {
+        self.advance();
         self.advance();
 
+        let digits = self.read_while(|c| c.is_ascii_hexdigit());
+        let fraction = match self.current {
+            Some(InputPosition { value: '.', .. }) => {
+                self.advance();
+                self.read_while(|c| c.is_ascii_hexdigit())
+            }
+            _ => "",
+        };
+
+        let error = |lexer: &Self, kind| {
+            Err(lexer.spanned(start, ParseError::LexingError(LexingError::new(kind))))
+        };
+
+        if digits.is_empty() && fraction.is_empty() {
+            return error(self, LexingErrorKind::EmptyHexLiteral);
+        }
+
+        if !matches!(self.current, Some(InputPosition { value: 'p' | 'P', .. })) {
+            return error(self, LexingErrorKind::MissingHexExponent);
+        }
+        self.advance();
+
+        if let Some(InputPosition { value: '+' | '-', .. }) = self.current {
+            self.advance();
+        }
+        if self.read_while(|c| c.is_ascii_digit()).is_empty() {
+            return error(self, LexingErrorKind::MissingHexExponent);
+        }
+
+        let slice = self.slice(start, self.pos());
+        Ok(self.spanned(start, TokenType::FloatLiteral(slice)))
+    }
+
+    // Strings are decoded right away, so escapes like `\n` are replaced by the characters they stand for. Strings without
+    // any escapes borrow their contents from the source. The span covers the whole literal, quotes included.
+    fn scan_string(&mut self) -> Scanned<'a> {
         let start = self.pos();
-        let slice = self.read_while(|c| c != '"');
+        self.advance();
+
+        let mut contents = Cow::Borrowed(self.read_while(|c| c != '"' && c != '\\'));
+
+        loop {
+            let Some(InputPosition { value, pos }) = self.advance() else {
+                let pos = self.pos();
+
+                return Err(Spanned::new(
+                    pos,
+                    pos,
+                    ParseError::LexingError(LexingError::new(LexingErrorKind::UnterminatedString)),
+                ));
+            };
+
+            if value == '"' {
+                break;
+            }
+
+            let escaped = match self.scan_escape(pos) {
+                Ok(escaped) => escaped,
+                Err(error) => {
+                    self.skip_string();
+                    return Err(error);
+                }
+            };
+
+            let contents = contents.to_mut();
+            contents.push(escaped);
+            contents.push_str(self.read_while(|c| c != '"' && c != '\\'));
+        }
+
+        Ok(self.spanned(start, TokenType::StringLiteral(contents)))
+    }
+
+    // Skip the rest of a string after an error in it, so lexing goes on after its closing quote.
+    fn skip_string(&mut self) {
+        while let Some(InputPosition { value, .. }) = self.advance() {
+            match value {
+                '"' => break,
+                '\\' => {
+                    self.advance();
+                }
+                _ => {}
+            }
+        }
+    }
 
-        if self.advance().is_none() {
+    // Decode the escape sequence of a string after its backslash, which is at `start`: `\n`, `\t`, `\r`, `\0`, `\\`,
+    // `\"`, or `\x` with two hexadecimal digits for an ASCII character.
+    fn scan_escape(&mut self, start: usize) -> Result<char, Spanned<ParseError<'a>>> {
+        let invalid = |lexer: &Self| {
+            Spanned::new(
+                start,
+                lexer.pos().saturating_sub(1).max(start),
+                ParseError::LexingError(LexingError::new(LexingErrorKind::InvalidStringEscape)),
+            )
+        };
+
+        let Some(InputPosition { value, .. }) = self.advance() else {
             let pos = self.pos();
 
-            Err(Spanned::new(
+            return Err(Spanned::new(
                 pos,
                 pos,
-                ParseError::LexingError(LexingError::with_cause("unterminated string literal")),
-            ))?;
+                ParseError::LexingError(LexingError::new(LexingErrorKind::UnterminatedString)),
+            ));
+        };
+
+        match value {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+
+            'x' => {
+                let mut code = 0;
+
+                for _ in 0..2 {
+                    let digit = self
+                        .current
+                        .and_then(|InputPosition { value, .. }| value.to_digit(16));
+
+                    let Some(digit) = digit else {
+                        return Err(invalid(self));
+                    };
+
+                    self.advance();
+                    code = code * 16 + digit;
+                }
+
+                match char::from_u32(code) {
+                    Some(c) if c.is_ascii() => Ok(c),
+                    _ => Err(invalid(self)),
+                }
+            }
+
+            _ => Err(invalid(self)),
+        }
+    }
+
+    // Skip a `/* ... */` comment that starts at `start`. Block comments nest, so `/* a /* b */ c */` is a single comment,
+    // which makes it possible to comment out code that has block comments in it.
+    // Comments are skipped one after the other in a loop, so any number of them can come before a token.
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), Spanned<ParseError<'a>>> {
+        loop {
+            self.skip_whitespace();
+
+            let start = self.pos();
+            let current = self.current.map(|InputPosition { value, .. }| value);
+
+            match (current, self.chars.peek()) {
+                // A lone `\r` ends the comment as well, like it ends the line in diagnostics.
+                (Some('/'), Some((_, '/'))) => {
+                    self.read_while(|c| c != '\n' && c != '\r');
+                    self.comments.push(self.spanned(start, ()).span);
+                }
+                (Some('/'), Some((_, '*'))) => self.skip_block_comment(start)?,
+
+                _ => return Ok(()),
+            }
         }
+    }
+
+    fn skip_block_comment(&mut self, start: usize) -> Result<(), Spanned<ParseError<'a>>> {
+        self.advance();
+        self.advance();
 
-        let mut spanned = self.spanned(start, TokenType::StringLiteral(slice));
+        let mut depth = 1;
+
+        while depth > 0 {
+            let Some(InputPosition { value, .. }) = self.advance() else {
+                return Err(Spanned::new(
+                    start,
+                    start + 1,
+                    ParseError::LexingError(LexingError::new(
+                        LexingErrorKind::UnterminatedBlockComment,
+                    )),
+                ));
+            };
 
-        if spanned.span.end - spanned.span.start > 0 {
-            spanned.span.end -= 1;
+            match (value, self.current.map(|InputPosition { value, .. }| value)) {
+                ('/', Some('*')) => {
+                    self.advance();
+                    depth += 1;
+                }
+                ('*', Some('/')) => {
+                    self.advance();
+                    depth -= 1;
+                }
+                _ => {}
+            }
         }
 
-        Ok(spanned)
+        self.comments.push(self.spanned(start, ()).span);
+
+        Ok(())
     }
 
     fn scan_token(&mut self) -> Option<Scanned<'a>> {
-        self.skip_whitespace();
+        if let Err(error) = self.skip_whitespace_and_comments() {
+            return Some(Err(error));
+        }
 
         let start = self.pos();
         let ch = self.current.map(|InputPosition { value, .. }| value)?;
 
         let scanned: Scanned = match ch {
             '=' => {
-                let token = match self.chars.peek()? {
-                    (_, '=') => TokenType::EqualsEquals,
-                    (_, '>') => TokenType::Arrow,
+                self.advance();
+
+                let token = match self.current {
+                    Some(InputPosition { value: '=', .. }) => TokenType::EqualsEquals,
+                    Some(InputPosition { value: '>', .. }) => TokenType::Arrow,
 
-                    (_, _) => TokenType::Equals,
+                    _ => return Some(Ok(self.spanned(start, TokenType::Equals))),
                 };
 
-                self.advance();
                 self.advance();
 
                 Ok(self.spanned(start, token))
             }
 
-            '/' => {
-                if let Some((_, '/')) = self.chars.peek() {
-                    self.read_while(|c| c != '\n');
-                    return self.scan_token();
-                }
-
-                consume_once!(self, start, TokenType::Slash)
-            }
+            '/' => consume_once!(self, start, TokenType::Slash),
 
             '.' => {
                 let dots = self.read_while(|c| c == '.');
@@ -311,7 +485,7 @@ impl<'a> Lexer<'a> {
                             return Some(Err(Spanned::new(
                                 start,
                                 self.pos() - 1,
-                                ParseError::LexingError(LexingError::with_cause("too many dots")),
+                                ParseError::LexingError(LexingError::new(LexingErrorKind::TooManyDots)),
                             )));
                         }
                     },
@@ -342,12 +516,16 @@ impl<'a> Lexer<'a> {
                         Ok(Spanned::new(start + 1, start + 1, TokenType::Char("\t")))
                     }
 
+                    2 if c.starts_with('\\') => Err(Spanned::new(
+                        start,
+                        self.pos(),
+                        ParseError::LexingError(LexingError::new(LexingErrorKind::InvalidEscape)),
+                    )),
+
                     _ => Err(Spanned::new(
                         start,
                         self.pos(),
-                        ParseError::LexingError(LexingError::with_cause(
-                            "`char` must have a length of one",
-                        )),
+                        ParseError::LexingError(LexingError::new(LexingErrorKind::InvalidCharLiteral)),
                     )),
                 };
 
@@ -357,8 +535,8 @@ impl<'a> Lexer<'a> {
             }
 
             '!' => consume_multiple!(self, start, '=', TokenType::Bang, TokenType::BangEquals),
-            '+' => consume_multiple!(self, start, TokenType::Plus, TokenType::PlusPlus),
-            '-' => consume_multiple!(self, start, TokenType::Minus, TokenType::MinusMinus),
+            '+' => consume_multiple!(self, start, '+', TokenType::Plus, TokenType::PlusPlus),
+            '-' => consume_multiple!(self, start, '-', TokenType::Minus, TokenType::MinusMinus),
             '<' => consume_multiple!(
                 self,
                 start,
@@ -376,10 +554,11 @@ impl<'a> Lexer<'a> {
             '&' => consume_multiple!(
                 self,
                 start,
+                '&',
                 TokenType::Ampersand,
                 TokenType::AmpersandAmpersand
             ),
-            '|' => consume_multiple!(self, start, TokenType::Pipe, TokenType::PipePipe),
+            '|' => consume_multiple!(self, start, '|', TokenType::Pipe, TokenType::PipePipe),
             '*' => consume_once!(self, start, TokenType::Star),
             '%' => consume_once!(self, start, TokenType::Percent),
             ':' => consume_once!(self, start, TokenType::Colon),
@@ -393,10 +572,11 @@ impl<'a> Lexer<'a> {
             '?' => consume_once!(self, start, TokenType::Question),
             '@' => consume_once!(self, start, TokenType::At),
             '^' => consume_once!(self, start, TokenType::Caret),
+            '~' => consume_once!(self, start, TokenType::Tilde),
             ',' => consume_once!(self, start, TokenType::Comma),
 
             '"' => self.scan_string(),
-            c if c.is_alphabetic() => self.scan_identifier(),
+            c if c.is_alphabetic() || c == '_' => self.scan_identifier(),
             c if c.is_digit(10) => self.scan_number(),
 
             _ => {
@@ -405,7 +585,7 @@ impl<'a> Lexer<'a> {
                 let span = Span::new(start, start);
                 Err(Spanned {
                     span,
-                    node: ParseError::LexingError(LexingError::default()),
+                    node: ParseError::LexingError(LexingError::new(LexingErrorKind::UnexpectedCharacter)),
                 })
             }
         };
@@ -418,7 +598,18 @@ impl<'a> Iterator for Lexer<'a> {
     type Item = Scanned<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.scan_token()
+        if let Some(scanned) = self.scan_token() {
+            return Some(scanned);
+        }
+
+        if self.reached_eof {
+            return None;
+        }
+
+        self.reached_eof = true;
+
+        let length = self.src.len();
+        Some(Ok(Spanned::new(length, length, TokenType::Eof)))
     }
 }
 
@@ -427,3 +618,24 @@ impl<'a> Scanner<'a> for Lexer<'a> {
         &self.source
     }
 }
+
+// The value of a float literal as the lexer scanned it, either decimal like `1.5` or hexadecimal like `0x1.8p3`. Every
+// hexadecimal digit is four bits of the mantissa, and the exponent is a power of two.
+pub fn float_value(literal: &str) -> Option<f64> {
+    let Some(hex) = literal.strip_prefix("0x").or_else(|| literal.strip_prefix("0X")) else {
+        return literal.parse().ok();
+    };
+
+    let (mantissa, exponent) = hex.split_once(['p', 'P'])?;
+    let (digits, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let mut value = 0.0;
+    for digit in digits.chars().chain(fraction.chars()) {
+        value = value * 16.0 + f64::from(digit.to_digit(16)?);
+    }
+
+    // The digits of the fraction were taken as a whole number, which is 16 to the power of their count too large.
+    let shift = i32::try_from(fraction.len()).ok()?.checked_mul(4)?;
+    let exponent = exponent.parse::<i32>().ok()?.checked_sub(shift)?;
+    Some(value * 2f64.powi(exponent))
+}
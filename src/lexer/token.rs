@@ -5,13 +5,14 @@
 
 use crate::types::types::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TokenType<'a> {
     NullLiteral,
     Identifier(&'a str),
     DecLiteral(&'a str),
     FloatLiteral(&'a str),
-    StringLiteral(&'a str),
+    // The decoded contents of a string, which only need to be owned if the string has escapes.
+    StringLiteral(std::borrow::Cow<'a, str>),
     Char(&'a str),
     TypeIdentifier(Simple<'a>),
 
@@ -21,6 +22,7 @@ pub enum TokenType<'a> {
     Else,
     Import,
     From,
+    Pub,
     Return,
     Extern,
     While,
@@ -47,6 +49,7 @@ pub enum TokenType<'a> {
     Match,
     Case,
     Default,
+    // Reserved for a `try` construct that does not exist yet, the parser rejects it everywhere.
     Finally,
     Volatile,
     Register,
@@ -75,6 +78,7 @@ pub enum TokenType<'a> {
     Question,
     At,
     Caret,
+    Tilde,
 
     Varargs,
     EqualsEquals,
@@ -86,6 +90,9 @@ pub enum TokenType<'a> {
     PlusPlus,
     MinusMinus,
     Arrow,
+
+    // Always the last token of a source, with an empty span right after its final character.
+    Eof,
 }
 
 impl<'a> TokenType<'a> {
@@ -97,21 +104,62 @@ impl<'a> TokenType<'a> {
             Self::Greater | Self::GreaterEquals | Self::Smaller | Self::SmallerEquals => {
                 Precedence::Comparison
             }
-            Self::Plus | Self::PlusPlus | Self::Minus | Self::MinusMinus => Precedence::Sum,
+            Self::Plus | Self::Minus => Precedence::Sum,
             Self::Star | Self::Slash | Self::Percent | Self::As => Precedence::Product,
-            Self::LeftParen | Self::LeftBrace | Self::Dot => Precedence::Call,
+            // `++` and `--` are only ever infix as postfix operators, which bind as tightly as calls and indexing do.
+            Self::LeftParen
+            | Self::LeftBracket
+            | Self::LeftBrace
+            | Self::Dot
+            | Self::PlusPlus
+            | Self::MinusMinus => Precedence::Call,
             _ => Precedence::None,
         }
     }
+
+    // Whether the token can only be the first one of a statement or top level declaration, or closes a block.
+    pub fn starts_statement(&self) -> bool {
+        matches!(
+            self,
+            Self::Let
+                | Self::If
+                | Self::Return
+                | Self::While
+                | Self::Match
+                | Self::Delete
+                | Self::Break
+                | Self::Continue
+                | Self::Fn
+                | Self::Type
+                | Self::Import
+                | Self::From
+                | Self::Pub
+                | Self::Extern
+                | Self::RightBrace
+        )
+    }
+
+    // Precedence of the operand of a prefix operator, or `None` if the token is not one.
+    pub fn prefix_precedence(&self) -> Option<Precedence> {
+        match self {
+            Self::Minus
+            | Self::Ampersand
+            | Self::Star
+            | Self::Bang
+            | Self::Tilde
+            | Self::PlusPlus
+            | Self::MinusMinus => Some(Precedence::Unary),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> std::fmt::Display for TokenType<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::NullLiteral => write!(f, "null"),
-            Self::Identifier(ref val) | Self::StringLiteral(ref val) | Self::Char(ref val) => {
-                write!(f, "{val}")
-            }
+            Self::Identifier(ref val) | Self::Char(ref val) => write!(f, "{val}"),
+            Self::StringLiteral(ref val) => write!(f, "{val}"),
             Self::TypeIdentifier(val) => write!(f, "{val}"),
             Self::DecLiteral(val) | Self::FloatLiteral(val) => write!(f, "{val}"),
 
@@ -121,6 +169,7 @@ impl<'a> std::fmt::Display for TokenType<'a> {
             Self::Else => write!(f, "else"),
             Self::Import => write!(f, "import"),
             Self::From => write!(f, "from"),
+            Self::Pub => write!(f, "pub"),
             Self::Return => write!(f, "return"),
             Self::Extern => write!(f, "extern"),
             Self::While => write!(f, "while"),
@@ -175,6 +224,7 @@ impl<'a> std::fmt::Display for TokenType<'a> {
             Self::Question => write!(f, "?"),
             Self::At => write!(f, "@"),
             Self::Caret => write!(f, "^"),
+            Self::Tilde => write!(f, "~"),
 
             Self::Varargs => write!(f, "..."),
             Self::EqualsEquals => write!(f, "=="),
@@ -186,6 +236,8 @@ impl<'a> std::fmt::Display for TokenType<'a> {
             Self::PlusPlus => write!(f, "++"),
             Self::MinusMinus => write!(f, "--"),
             Self::Arrow => write!(f, "=>"),
+
+            Self::Eof => write!(f, "end of file"),
         }
     }
 }
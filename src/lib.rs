@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod codegen;
+pub mod driver;
 pub mod error;
 pub mod ir;
 pub mod lexer;
@@ -8,12 +9,15 @@ pub mod semantic;
 pub mod types;
 
 use ast::ast::*;
+use error::diagnostic::*;
 use parser::span::*;
 use types::types::*;
 
 use ansi_term::Colour::*;
 use unicode_width::UnicodeWidthStr;
 
+// The code is kept exactly as it was read, so spans are byte offsets into the original. Lines may end with `\n`, `\r\n` or
+// a lone `\r`, which the line and column numbers of diagnostics all take into account.
 #[derive(Debug)]
 pub struct Source {
     pub name: String,
@@ -28,9 +32,53 @@ impl Source {
         }
     }
 
+    // Spans end at the first byte of their last character, so the slice is extended to the rest of that character.
     pub fn slice(&self, span: Span) -> &str {
-        &self.code[span.start..=span.end]
+        let end = span.end + self.code[span.end..].chars().next().map_or(0, char::len_utf8);
+        &self.code[span.start..end]
     }
+
+    // The text of `span` along with the lines it covers and up to `context_lines` lines before and after them. Spans
+    // past the end of the code or inside of a character are moved back onto it, instead of panicking like slicing.
+    pub fn snippet(&self, span: Span, context_lines: usize) -> Snippet<'_> {
+        let start = self.char_boundary(span.start);
+        let last = self.char_boundary(span.end.max(span.start));
+        let end = last + self.code[last..].chars().next().map_or(0, char::len_utf8);
+
+        let first_line = line_breaks_before(&self.code, start).saturating_sub(context_lines);
+        let last_line = line_breaks_before(&self.code, last) + context_lines;
+
+        Snippet {
+            text: &self.code[start..end],
+            first_line: first_line + 1,
+            lines: self.lines().skip(first_line).take(last_line + 1 - first_line).collect(),
+        }
+    }
+
+    // The lines of the code without their line endings. Unlike `str::lines`, a lone `\r` ends a line as well.
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.code
+            .split('\n')
+            .flat_map(|line| line.strip_suffix('\r').unwrap_or(line).split('\r'))
+    }
+
+    // The start of the character `offset` is in, or the end of the code if it is past it.
+    fn char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.code.len());
+        while !self.code.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        offset
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet<'a> {
+    pub text: &'a str,
+    // Number of the first of the lines, counting from 1.
+    pub first_line: usize,
+    pub lines: Vec<&'a str>,
 }
 
 impl PartialEq for Source {
@@ -56,28 +104,59 @@ impl std::hash::Hash for Source {
 #[derive(Debug)]
 pub struct UserTypeDefinition<'a> {
     pub name: &'a str,
+    // The name in the declaration, which is where notes about the type point to.
+    pub span: Span,
+    // Every field along with its byte offset, which is only known once all the types of the program are collected.
     pub fields: std::collections::HashMap<&'a str, (u32, Spanned<Type<'a>>)>,
+    // Field names in declaration order.
+    pub field_order: Vec<&'a str>,
+    // The variants of an enum in declaration order. Enums have no fields and structs have no variants.
+    pub variants: Vec<&'a str>,
+    // The integer type of an enum's values, `i32` unless it is declared like `enum: u8`. The variants count up from 0.
+    pub backing_type: Option<Type<'a>>,
+    // The type parameters of a generic struct, in declaration order.
+    pub generic_parameters: Vec<&'a str>,
+    // `@[packed]` structs have no padding between their fields.
+    pub packed: bool,
+    // The alignment of an `@[align(N)]` struct, if it is larger than what the fields need.
+    pub align: Option<u64>,
+    // The methods that take `self`, with the parameters after it, so they can be called like `self.method()`.
+    pub methods: std::collections::HashMap<&'a str, FunctionDefinition<'a>>,
+    pub is_public: bool,
 }
 
 impl<'a> std::fmt::Display for UserTypeDefinition<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if !self.variants.is_empty() {
+            return write!(f, "type {} enum {{ {} }}", self.name, self.variants.join(", "));
+        }
+
         let fields = self
-            .fields
+            .field_order
             .iter()
-            .map(|(name, (_, Spanned { node, .. }))| format!("    {}: {}", name, node))
+            .map(|name| format!("    {}: {}", name, self.fields[name].1.node))
             .collect::<Vec<String>>()
             .join(",\n");
 
-        write!(f, "type {} struct {{\n{}\n}}", self.name, fields)
+        if self.generic_parameters.is_empty() {
+            write!(f, "type {} struct {{\n{}\n}}", self.name, fields)
+        } else {
+            let parameters = self.generic_parameters.join(", ");
+            write!(f, "type {} struct<{}> {{\n{}\n}}", self.name, parameters, fields)
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition<'a> {
     name: &'a str,
+    // The name in the declaration, which is where notes about the function point to.
+    span: Span,
     return_type: Spanned<Type<'a>>,
     parameters: Vec<Spanned<Type<'a>>>,
     varargs: bool,
+    must_use: bool,
+    is_public: bool,
 }
 
 impl<'a> FunctionDefinition<'a> {
@@ -92,108 +171,189 @@ impl<'a> FunctionDefinition<'a> {
 
         self.parameters.len()
     }
+
+    #[inline]
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 impl<'a> Default for FunctionDefinition<'a> {
     fn default() -> Self {
         Self {
             name: "",
+            span: Span::new(0, 0),
             return_type: Spanned::new(0, 0, Type::Simple(Simple::Void)),
             parameters: vec![],
             varargs: false,
+            must_use: false,
+            is_public: false,
         }
     }
 }
 
+// A global, which is either an extern variable defined outside of Newton, or a constant.
+#[derive(Debug, Clone)]
+pub struct GlobalDefinition<'a> {
+    ty: Spanned<Type<'a>>,
+    // Only constants have a value, and only if it could be computed.
+    value: Option<semantic::consteval::ConstValue>,
+    is_public: bool,
+}
+
+impl<'a> GlobalDefinition<'a> {
+    #[inline]
+    pub fn value(&self) -> Option<semantic::consteval::ConstValue> {
+        self.value
+    }
+}
+
 pub type UserTypeMap<'a> = std::collections::HashMap<&'a str, UserTypeDefinition<'a>>;
 pub type FunctionMap<'a> = std::collections::HashMap<&'a str, FunctionDefinition<'a>>;
+pub type GlobalMap<'a> = std::collections::HashMap<&'a str, GlobalDefinition<'a>>;
+
+// What is left to look at is kept on a stack instead of recursing, as expressions nest as deeply as chains like
+// `a + b + c + ...` are long. Children are pushed in reverse, so the errors still come out in the order of the code.
+fn find_errors(program: &Program) -> Vec<Diagnostic> {
+    enum Pending<'p, 'a> {
+        Statement(&'p Statement<'a>),
+        Expression(&'p Spanned<Expression<'a>>),
+    }
 
-fn find_errors(program: &Program) -> Vec<(Span, String)> {
-    fn find_errors_recursive(statement: &Statement, errors: &mut Vec<(Span, String)>) {
-        match statement {
-            Statement::VariableDeclaration(declaration) => {
-                if declaration.value.node.is_error() {
-                    errors.push((declaration.value.span, declaration.value.node.to_string()))
-                }
-            }
+    fn statements<'p, 'a>(block: &'p Block<'a>) -> impl Iterator<Item = Pending<'p, 'a>> {
+        block.0.iter().map(Pending::Statement)
+    }
 
-            Statement::ExpressionStatement(Spanned { node: expression, span }) => {
-                if expression.is_error() {
-                    errors.push((*span, expression.to_string()));
-                }
-            }
+    let mut errors = vec![];
+    for top_level in &program.0 {
+        let mut pending: Vec<Pending> = match top_level {
+            TopLevel::FunctionDeclaration { body, .. } => statements(body).collect(),
+            TopLevel::Constant { declaration, .. } => vec![Pending::Expression(&declaration.value)],
 
-            Statement::DeleteStatement(expression) => {
-                if expression.node.is_error() {
-                    errors.push((expression.span, expression.node.to_string()));
-                }
+            TopLevel::Error { error } => {
+                errors.push(error.node.to_diagnostic(error.span));
+                continue;
             }
 
-            Statement::ReturnStatement(expression) => {
-                if let Some(Spanned { node: expression, span }) = expression {
-                    if expression.is_error() {
-                        errors.push((*span, expression.to_string()));
+            TopLevel::TypeDeclaration { .. }
+            | TopLevel::Import { .. }
+            | TopLevel::ExternVariable { .. } => continue,
+        };
+        pending.reverse();
+
+        while let Some(next) = pending.pop() {
+            let mut children = vec![];
+
+            match next {
+                Pending::Expression(expression) => match expression.node.kind() {
+                    ExpressionKind::Error(error) => errors.push(error.to_diagnostic(expression.span)),
+
+                    // Blocks used as expressions keep the errors of their statements, like any other block does.
+                    kind => {
+                        if let ExpressionKind::Block { body, .. } = kind {
+                            children.extend(statements(body));
+                        }
+
+                        let sub_expressions = expression.node.sub_expressions();
+                        children.extend(sub_expressions.into_iter().map(Pending::Expression));
                     }
-                }
-            }
+                },
 
-            Statement::WhileStatement(statement) => {
-                let WhileStatement { condition: Spanned { node: condition, span, }, body, } = statement.as_ref();
+                Pending::Statement(statement) => match statement {
+                    Statement::VariableDeclaration(declaration) => {
+                        children.push(Pending::Expression(&declaration.value));
+                    }
 
-                if condition.is_error() {
-                    errors.push((*span, condition.to_string()));
-                }
+                    Statement::ExpressionStatement(expression) => children.push(Pending::Expression(expression)),
+                    Statement::DeleteStatement(expression) => children.push(Pending::Expression(expression)),
 
-                for statement in &body.0 {
-                    find_errors_recursive(statement, errors);
-                }
-            }
+                    Statement::ReturnStatement(_, expression) => {
+                        children.extend(expression.iter().map(Pending::Expression));
+                    }
+
+                    Statement::WhileStatement(statement) => {
+                        children.push(Pending::Expression(&statement.condition));
+                        children.extend(statements(&statement.body));
+                    }
+
+                    Statement::IfStatement(statement) => {
+                        children.push(Pending::Expression(&statement.condition));
+                        children.extend(statements(&statement.then_block));
 
-            Statement::IfStatement(statement) => {
-                let IfStatement { condition: Spanned { node: condition, span, }, then_block, else_branch } = statement.as_ref();
+                        match statement.else_branch.as_deref() {
+                            Some(Else::IfStatement(statement)) => children.push(Pending::Statement(statement)),
+                            Some(Else::Block(block)) => children.extend(statements(block)),
+                            None => {}
+                        }
+                    }
 
-                if condition.is_error() {
-                    errors.push((*span, condition.to_string()));
-                }
+                    Statement::BlockStatement(statement) => children.extend(statements(&statement.body)),
 
-                for statement in &then_block.0 {
-                    find_errors_recursive(statement, errors);
-                }
+                    Statement::MatchStatement(statement) => {
+                        children.push(Pending::Expression(&statement.scrutinee));
 
-                if let Some(else_branch) = else_branch {
-                    match else_branch.as_ref() {
-                        Else::IfStatement(statement) => find_errors_recursive(statement, errors),
-                        Else::Block(block) => {
-                            for statement in &block.0 {
-                                find_errors_recursive(statement, errors);
-                            }
+                        for (pattern, block) in &statement.arms {
+                            children.push(Pending::Expression(pattern));
+                            children.extend(statements(block));
                         }
+
+                        children.extend(statement.default.iter().flat_map(statements));
                     }
-                }
-            }
-        }
-    }
 
-    let mut errors = vec![];
-    for top_level in &program.0 {
-        match top_level {
-            TopLevel::FunctionDeclaration { body, .. } => {
-                for statement in &body.0 {
-                    find_errors_recursive(statement, &mut errors);
-                }
-            }
+                    Statement::FunctionDeclaration(function) => {
+                        if let TopLevel::FunctionDeclaration { body, .. } = function.as_ref() {
+                            children.extend(statements(body));
+                        }
+                    }
 
-            TopLevel::Error { error } => {
-                errors.push((error.span, error.node.to_string()));
+                    Statement::BreakStatement(_) | Statement::ContinueStatement(_) => {}
+                },
             }
 
-            TopLevel::TypeDeclaration { .. } | TopLevel::Import { .. } => {}
+            pending.extend(children.into_iter().rev());
         }
     }
 
     errors
 }
 
+// The stack the parser and the passes after it run on. Syntax trees are walked recursively, and nesting as deep as the
+// parser allows takes up far more than the 2 MiB of a spawned thread in debug builds.
+pub const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// Run `f` on a thread with a stack of `STACK_SIZE` and wait for it. A panic in `f` is passed on to the caller.
+pub fn with_stack<R, F>(f: F) -> R
+where
+    R: Send,
+    F: FnOnce() -> R + Send,
+{
+    std::thread::scope(|scope| {
+        let thread = std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("cannot spawn a thread for the compiler");
+
+        thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+// Parse `source` and hand out the program only if it has no errors. Nothing in here panics on any input, malformed or
+// not, which `fuzz/` checks; every problem ends up in the diagnostics instead.
+pub fn try_parse(source: &Source) -> Result<Program<'_>, Vec<Diagnostic>> {
+    with_stack(|| {
+        let program = parser::parser::Parser::new(lexer::lexer::Lexer::new(source)).parse();
+        let errors = find_errors(&program);
+
+        if errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(errors)
+        }
+    })
+}
+
 pub fn print_error<W: std::io::Write>(msg: &str, writer: &mut W) -> std::io::Result<()> {
     writer.write_all(msg.as_bytes())?;
     writer.write_all(b"\n")?;
@@ -202,16 +362,54 @@ pub fn print_error<W: std::io::Write>(msg: &str, writer: &mut W) -> std::io::Res
     Ok(())
 }
 
+// Print every error of `program`, returning how many there were, so a driver can tell whether to fail.
 pub fn report_errors<W: std::io::Write>(
     source: &Source,
     program: &Program,
     writer: &mut W,
-) -> std::io::Result<()> {
-    for (span, message) in find_errors(program) {
-        print_error(&format_error(source, span, span, &message), writer)?;
+) -> std::io::Result<usize> {
+    let mut diagnostics = DiagnosticSink::new();
+    diagnostics.extend(find_errors(program));
+
+    for diagnostic in diagnostics.sorted() {
+        print_error(&diagnostic.render(source), writer)?;
     }
 
-    Ok(())
+    Ok(diagnostics.len())
+}
+
+// Like `report_errors`, but writes every error as one line of JSON for tools like editors to consume.
+pub fn report_errors_json<W: std::io::Write>(
+    source: &Source,
+    program: &Program,
+    writer: &mut W,
+) -> std::io::Result<usize> {
+    let mut diagnostics = DiagnosticSink::new();
+    diagnostics.extend(find_errors(program));
+
+    for diagnostic in diagnostics.sorted() {
+        print_error(&diagnostic.to_json(source), writer)?;
+    }
+
+    Ok(diagnostics.len())
+}
+
+pub fn format_warning(
+    source: &Source,
+    expression_span: Span,
+    error_token: Span,
+    message: &str,
+) -> String {
+    let (line_number, index) = find_line_index(source, error_token.start);
+
+    format!(
+        "warning: {}\n--> {}:{}:{}\n{}",
+        message,
+        source.name,
+        line_number,
+        index,
+        error_to_string(source, expression_span, error_token, line_number, true)
+    )
 }
 
 pub fn format_error(
@@ -233,23 +431,56 @@ pub fn format_error(
 }
 
 pub fn find_line_index(source: &Source, start: usize) -> (usize, usize) {
-    let slice = &source.code[..start];
-    let line_number = slice.chars().filter(|c| *c == '\n').count() + 1;
-    let index = slice.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    let line_number = line_breaks_before(&source.code, start) + 1;
+    let index = source.code[line_start(&source.code, start)..start].chars().count() + 1;
 
     (line_number, index)
 }
 
-fn find_distance(source: &Source, start: usize) -> usize {
-    let slice = &source.code[..start];
-    let slice = slice
-        .chars()
+// Whether the character `c` at `index` ends a line. The `\r` of a `\r\n` does not, the `\n` after it does.
+fn is_line_break(code: &str, index: usize, c: char) -> bool {
+    c == '\n' || (c == '\r' && !code[index + 1..].starts_with('\n'))
+}
+
+fn line_breaks_before(code: &str, offset: usize) -> usize {
+    code[..offset]
+        .char_indices()
+        .filter(|(index, c)| is_line_break(code, *index, *c))
+        .count()
+}
+
+// Where the line `offset` is on starts.
+fn line_start(code: &str, offset: usize) -> usize {
+    code[..offset]
+        .char_indices()
         .rev()
-        .take_while(|c| *c != '\n')
-        .collect::<String>();
-    let tabs = slice.chars().filter(|c| *c == '\t').count() * 4;
+        .find(|(index, c)| is_line_break(code, *index, *c))
+        .map_or(0, |(index, _)| index + 1)
+}
+
+fn find_distance(source: &Source, start: usize) -> usize {
+    display_width(&source.code[line_start(&source.code, start)..start])
+}
+
+// Number of columns the token covers on its first line. Spans end at the first byte of their last character, so the
+// end is moved past the whole character. Tokens without any visible width, like the end of the file, still get one.
+fn find_width(source: &Source, span: Span) -> usize {
+    let code = &source.code;
+    let start = span.start.min(code.len());
+    let mut end = (span.end + 1).clamp(start, code.len());
+
+    while !code.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let slice = code[start..end].split(['\r', '\n']).next().unwrap_or("");
+
+    display_width(slice).max(1)
+}
 
-    UnicodeWidthStr::width(slice.as_str()) + tabs
+// Lines are printed with tabs expanded to four spaces, so they are measured that way too.
+fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text.replace('\t', "    ").as_str())
 }
 
 pub fn error_to_string(
@@ -259,13 +490,10 @@ pub fn error_to_string(
     line_number: usize,
     warning: bool,
 ) -> String {
-    let (starting_line, _) = find_line_index(source, expression_span.start);
-    let (ending_line, _) = find_line_index(source, expression_span.end);
-    let starting_line = starting_line - 1;
+    let snippet = source.snippet(expression_span, 0);
     let line_number_length = line_number.to_string().len();
     let filler = " ".repeat(line_number_length + 1);
-    let slice = &source.code[error_token.start..error_token.end];
-    let length = UnicodeWidthStr::width(slice) + 1;
+    let length = find_width(source, error_token);
     let distance = find_distance(source, error_token.start);
     let marker = format!("{}{}", " ".repeat(distance), "^".repeat(length));
 
@@ -275,15 +503,13 @@ pub fn error_to_string(
         Red.paint(marker)
     };
 
-    let lines: Vec<String> = source
-        .code
-        .lines()
+    let lines: Vec<String> = snippet
+        .lines
+        .iter()
         .enumerate()
-        .skip(starting_line)
-        .take(ending_line - starting_line)
-        .map(|(n, l)| (n, l.replace("\t", "    ")))
+        .map(|(n, l)| (snippet.first_line + n, l.replace("\t", "    ")))
         .map(|(n, l)| {
-            if n + 1 == line_number {
+            if n == line_number {
                 format!("{}|\n{} |{}\n{}|{}", filler, line_number, l, filler, marker)
             } else {
                 format!("{}|{}", filler, l)
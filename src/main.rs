@@ -1,45 +1,20 @@
 use newton_rs::Source;
-use newton_rs::lexer::lexer::*;
-use newton_rs::parser::parser::*;
+use newton_rs::ast::dump::*;
+use newton_rs::driver::compiler::*;
 
 fn main() {
-    let source: Source = Source::new(
-        "main",
-        "
-    type Pair struct<K, V> {
-        @key: K;
-        @value: V;
+    let mut compiler = Compiler::new();
+    // The golden dump of this code is checked by the tests, in `tests/golden/sample.dump`.
+    compiler.add_source(Source::new("main", include_str!("../tests/golden/sample.nt")));
 
-        fn init(self: &Pair, key: K, value: V) => Pair {
-            return new Pair {
-                key,
-                value
-            };
-        };
+    let compilation = compiler.compile();
+    let errors = compilation
+        .report(&mut std::io::stderr())
+        .expect("failed to report errors");
 
-        fn get_key(self: &Pair) => K {
-            return self.key;
-        };
+    print!("{}", debug_dump(compilation.program("main").unwrap()));
 
-        fn get_value(self: &Pair) => V {
-            return self.value;
-        };
-    }
-
-    type test struct {
-        @unsized_array: [?]i32;
-        @sized_array: [64]i32;
-    }
-
-    type Nullable<T> = ?T;
-    ",
-    );
-
-    let lexer = Lexer::new(&source);
-    let mut parser = Parser::new(lexer);
-    let program = parser.parse();
-
-    for toplevel in program.0 {
-        println!("{:?}", toplevel)
+    if errors > 0 {
+        std::process::exit(1);
     }
 }
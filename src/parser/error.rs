@@ -1,52 +1,255 @@
+use super::parser::Feature;
+use super::span::*;
+use crate::error::diagnostic::*;
 use crate::lexer::token::*;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ParseError<'a> {
-    LexingError(LexingError<'a>),
-    PrefixError(String),
-    InfixError(String),
+    LexingError(LexingError),
+    // The token an expression was expected at.
+    PrefixError(TokenType<'a>),
+    // The token an operator was expected at.
+    InfixError(TokenType<'a>),
     InternalError(&'a str),
+    ArraySize(i128),
+    MisplacedInType {
+        actual: TokenType<'a>,
+        hint: &'a str,
+    },
+
+    // A bare `=` in the condition of an `if` or `while`.
+    AssignmentInCondition,
+    // A struct initializer without parentheses in the condition of an `if` or `while`, with the name of the struct.
+    StructInCondition(&'a str),
+    // The left side of an assignment is neither a variable, nor a field, nor a dereferenced pointer.
+    InvalidAssignmentTarget,
+    // The operand of `++` or `--` is not something that can be assigned to.
+    InvalidIncrementTarget(TokenType<'a>),
+    // Expressions or blocks nested deeper than the parser's limit.
+    NestingTooDeep(usize),
+    // More levels of `*` or `&` in a type than pointers and references can have.
+    IndirectionTooDeep {
+        indirection: TokenType<'a>,
+        max: u8,
+    },
+    // `...` in the parameters of a function that is not `extern`.
+    VarargsOutsideExtern,
+    // Syntax that is reserved, but does not do anything yet, like `type Name trait`.
+    Unsupported(&'a str),
+    // A keyword that is taken for a future feature, but cannot be used anywhere yet, like `finally`.
+    ReservedKeyword(TokenType<'a>),
+    // Syntax of a feature that is turned off in the parser's options.
+    FeatureNotEnabled(Feature),
+    // `@[cfg]` with a number of names other than one.
+    CfgArguments(usize),
+    // A `match` without any `case`.
+    EmptyMatch,
+    // A module name with an escape sequence in it, like `import "a\\b";`.
+    EscapedModuleName,
+    // `@[align]` without exactly one alignment, or with one that is not a power of two.
+    InvalidAlignment,
+    // `return` followed by neither a value nor a semicolon, but the next statement or the end of the block.
+    UnterminatedReturn {
+        // Right after `return`, where the semicolon goes if there is no value to return.
+        position: usize,
+    },
 
     ConsumeError {
         actual: TokenType<'a>,
         expected: String,
+        // Where the expected token should have been, right after the previous one.
+        position: usize,
     },
 }
 
+// Errors hash like their message, which leaves out where the error is, just like spans are left out of hashes.
+impl<'a> std::hash::Hash for ParseError<'a> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.to_string().hash(state);
+    }
+}
+
 impl<'a> std::fmt::Display for ParseError<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::LexingError(err) => write!(f, "{}", err.as_string()),
-            Self::PrefixError(err) => write!(f, "{}", err),
-            Self::InfixError(err) => write!(f, "{}", err),
+            Self::PrefixError(TokenType::Eof) => write!(f, "expected an expression, but got end of file"),
+            Self::PrefixError(actual) => write!(f, "expected an expression, but got '{}' instead", actual),
+            Self::InfixError(actual) => write!(f, "expected an operator, but got '{}' instead", actual),
             Self::InternalError(err) => write!(f, "An internal error has occured!\n\t{}", err),
-            Self::ConsumeError { expected, actual } => {
+            Self::ArraySize(size) => write!(f, "array size must be positive, but is {}", size),
+            Self::MisplacedInType { actual, hint } => {
+                write!(f, "expected a type, but got '{}' instead; {}", actual, hint)
+            }
+            Self::NestingTooDeep(limit) => {
+                write!(f, "nesting too deep, at most {} levels are allowed", limit)
+            }
+            Self::IndirectionTooDeep { indirection, max } => {
+                write!(f, "at most {} levels of '{}' are allowed in a type", max, indirection)
+            }
+            Self::VarargsOutsideExtern => {
+                write!(f, "varargs are only supported in external functions")
+            }
+            Self::Unsupported(feature) => write!(f, "{} are not supported yet", feature),
+            Self::FeatureNotEnabled(feature) => write!(f, "{} are not enabled", feature),
+            Self::CfgArguments(count) => {
+                write!(f, "'cfg' checks for exactly one name, like '@[cfg(c)]', but got {}", count)
+            }
+            Self::EmptyMatch => write!(f, "'match' needs at least one 'case'"),
+            Self::EscapedModuleName => write!(f, "module names cannot contain escape sequences"),
+            Self::InvalidAlignment => {
+                write!(f, "'align' takes an alignment in bytes that is a power of two, like '@[align(8)]'")
+            }
+            Self::UnterminatedReturn { .. } => {
+                write!(f, "expected an expression or ';' after 'return'")
+            }
+            Self::ReservedKeyword(keyword) => {
+                write!(f, "'{}' is a reserved keyword, but it is not supported yet", keyword)
+            }
+            Self::AssignmentInCondition => {
+                write!(f, "expected a comparison, but got an assignment in this condition")
+            }
+            Self::StructInCondition(name) => {
+                write!(f, "struct initializers in conditions need parentheses, like '({} {{ ... }})', the braces are taken for the body otherwise", name)
+            }
+            Self::InvalidAssignmentTarget => {
+                write!(f, "cannot assign to this expression, only to variables, fields, array elements and dereferenced pointers")
+            }
+            Self::InvalidIncrementTarget(operator) => {
+                write!(f, "cannot apply '{}' to this expression, only to variables, fields, array elements and dereferenced pointers", operator)
+            }
+            // The end of the file is not something that can be quoted from the source.
+            Self::ConsumeError {
+                expected,
+                actual: TokenType::Eof,
+                ..
+            } => write!(f, "expected '{}', but got end of file", expected),
+            Self::ConsumeError {
+                expected, actual, ..
+            } => {
                 write!(f, "expected '{}', but got '{}' instead", expected, actual)
             }
         }
     }
 }
 
-impl<'a> From<LexingError<'a>> for ParseError<'a> {
-    fn from(value: LexingError<'a>) -> Self {
+impl<'a> ParseError<'a> {
+    pub fn to_diagnostic(&self, span: Span) -> Diagnostic {
+        let diagnostic = Diagnostic::error(span, self.to_string());
+
+        match self {
+            // Missing semicolons are by far the most common mistake, and where they go is unambiguous.
+            Self::ConsumeError {
+                expected, position, ..
+            } if expected == ";" => diagnostic.with_insertion(
+                "add the missing semicolon".to_owned(),
+                *position,
+                expected.clone(),
+            ),
+
+            Self::UnterminatedReturn { position } => diagnostic.with_insertion(
+                "add a semicolon if nothing is returned".to_owned(),
+                *position,
+                ";".to_owned(),
+            ),
+
+            Self::AssignmentInCondition => diagnostic.with_suggestion(
+                "compare the values instead".to_owned(),
+                span,
+                "==".to_owned(),
+            ),
+
+            Self::StructInCondition(_) => diagnostic
+                .with_insertion("wrap the struct in parentheses".to_owned(), span.start, "(".to_owned())
+                .with_insertion("wrap the struct in parentheses".to_owned(), span.end + 1, ")".to_owned()),
+
+            _ => diagnostic,
+        }
+    }
+}
+
+impl<'a> From<LexingError> for ParseError<'a> {
+    fn from(value: LexingError) -> Self {
         ParseError::LexingError(value)
     }
 }
 
-#[derive(Default, PartialEq, Eq, Clone, Hash)]
-pub struct LexingError<'a> {
-    cause: Option<&'a str>,
+// Why a token could not be lexed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LexingErrorKind {
+    // A character no token starts with.
+    UnexpectedCharacter,
+    UnexpectedEof,
+    UnterminatedString,
+    // A `/*` without its matching `*/`.
+    UnterminatedBlockComment,
+    TooManyDots,
+    NonAsciiIdentifier,
+    // A character literal of more or less than one character.
+    InvalidCharLiteral,
+    // A backslash in a character literal that is not followed by a known escape.
+    InvalidEscape,
+    // A backslash in a string literal that is not followed by a known escape.
+    InvalidStringEscape,
+    // `0x` without any hexadecimal digits after it.
+    EmptyHexLiteral,
+    // A hexadecimal float literal without the `p` exponent, like `0x1.8`.
+    MissingHexExponent,
+}
+
+impl LexingErrorKind {
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::UnexpectedCharacter => "unexpected character",
+            Self::UnexpectedEof => "unexpected end of file",
+            Self::UnterminatedString => "unterminated string literal",
+            Self::UnterminatedBlockComment => "unterminated block comment",
+            Self::TooManyDots => "too many dots",
+            Self::NonAsciiIdentifier => "non-ascii identifiers are not allowed",
+            Self::InvalidCharLiteral => "`char` must have a length of one",
+            Self::InvalidEscape => "unknown escape sequence in `char`",
+            Self::InvalidStringEscape => "unknown escape sequence in string",
+            Self::EmptyHexLiteral => "expected hexadecimal digits after `0x`",
+            Self::MissingHexExponent => {
+                "hexadecimal float literals need a binary exponent, like the `p4` of `0x1p4`"
+            }
+        }
+    }
 }
 
-impl<'a> LexingError<'a> {
-    pub fn with_cause(cause: &'a str) -> Self {
-        Self { cause: Some(cause) }
+impl std::fmt::Display for LexingErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct LexingError {
+    kind: LexingErrorKind,
+}
+
+impl LexingError {
+    pub fn new(kind: LexingErrorKind) -> Self {
+        Self { kind }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> LexingErrorKind {
+        self.kind
+    }
+
+    // Why the token could not be lexed, without the rest of the message. `None` for characters Newton has no use for.
+    pub fn cause(&self) -> Option<&'static str> {
+        match self.kind {
+            LexingErrorKind::UnexpectedCharacter => None,
+            kind => Some(kind.message()),
+        }
     }
 
     fn as_string(&self) -> String {
         let msg = "failed to lex token";
 
-        if let Some(ref reason) = self.cause {
+        if let Some(reason) = self.cause() {
             format!("{}; because {}", msg, reason)
         } else {
             msg.to_string()
@@ -54,16 +257,16 @@ impl<'a> LexingError<'a> {
     }
 }
 
-impl<'a> std::fmt::Debug for LexingError<'a> {
+impl std::fmt::Debug for LexingError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self)
     }
 }
 
-impl<'a> std::fmt::Display for LexingError<'a> {
+impl std::fmt::Display for LexingError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.as_string())
     }
 }
 
-impl<'a> std::error::Error for LexingError<'a> {}
+impl std::error::Error for LexingError {}
@@ -3,6 +3,7 @@ use super::span::*;
 use crate::ast::ast::*;
 use crate::lexer::lexer::*;
 use crate::lexer::token::*;
+use crate::semantic::consteval::*;
 use crate::types::types::*;
 use crate::Source;
 
@@ -12,12 +13,95 @@ type TopLevelResult<'a> = ParseResult<'a, TopLevel<'a>>;
 type StatementResult<'a> = ParseResult<'a, Statement<'a>>;
 type ExpressionResult<'a> = ParseResult<'a, Spanned<Expression<'a>>>;
 
+type Field<'a> = (Spanned<&'a str>, Spanned<Type<'a>>);
+
+// Explain what went wrong for tokens that are commonly written where a type is expected.
+fn misplaced_type_hint(token: &TokenType) -> Option<&'static str> {
+    Some(match token {
+        TokenType::Struct | TokenType::Enum | TokenType::Trait => {
+            "type declarations only appear at top level, refer to the declared type by its name instead"
+        }
+        TokenType::NullLiteral => "'null' is a value, use a nullable type like '?i32' to allow it",
+
+        _ => return None,
+    })
+}
+
 fn error_statement(error: Spanned<ParseError>) -> Statement {
-    Statement::ExpressionStatement(Spanned::new(
-        error.span.start,
-        error.span.end,
-        Expression::new(ExpressionKind::Error(error.node)),
-    ))
+    Statement::ExpressionStatement(error.map(|error| Expression::new(ExpressionKind::Error(error))))
+}
+
+// The attribute that only keeps the declaration it is attached to if the name it checks for is active.
+pub const CFG_ATTRIBUTE: &str = "cfg";
+
+// Struct attributes that change the layout: `@[packed]` leaves out all padding between fields, and `@[align(N)]` aligns
+// the struct to at least `N` bytes.
+pub const PACKED_ATTRIBUTE: &str = "packed";
+pub const ALIGN_ATTRIBUTE: &str = "align";
+
+// The alignment written in an `align` attribute, if it is a power of two.
+pub fn parse_alignment(argument: &str) -> Option<u64> {
+    argument
+        .parse::<u64>()
+        .ok()
+        .filter(|align| align.is_power_of_two())
+}
+
+// How deeply expressions, types and blocks may nest by default, which is far more than any sensible program needs. Every
+// level takes up lots of stack in the parser and in every pass after it, especially in debug builds, so this is only
+// safe on a stack of `crate::STACK_SIZE`, which `try_parse` and the `Compiler` run on.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+// Syntax that can be turned off, for code that has to stay within the stable part of the language.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Feature {
+    // `outer: while true { break outer; }`, and labeled blocks.
+    Labels,
+    // `(1, true)` and `(i32, bool)`.
+    Tuples,
+    // `fn(i32) => bool`
+    FunctionTypes,
+}
+
+impl std::fmt::Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Labels => write!(f, "labels"),
+            Self::Tuples => write!(f, "tuples"),
+            Self::FunctionTypes => write!(f, "function types"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    // How deeply expressions, types and blocks may nest. Deeper nesting is reported as an error instead of overflowing
+    // the stack.
+    pub max_depth: usize,
+
+    // Features that are turned off are still parsed, so the rest of the code is checked as usual, but every use of
+    // them is an error. Everything is enabled by default.
+    pub disabled_features: std::collections::HashSet<Feature>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            disabled_features: std::collections::HashSet::new(),
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn disable(mut self, feature: Feature) -> Self {
+        self.disabled_features.insert(feature);
+        self
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        !self.disabled_features.contains(&feature)
+    }
 }
 
 pub struct Parser<'a, T>
@@ -27,33 +111,77 @@ where
     pub(crate) source: &'a Source,
     pub(crate) error_count: usize,
 
+    // Position right after the last consumed token, which is where a missing token has to be inserted.
+    previous_end: usize,
+    // Errors the parser recovered from without giving up on the surrounding statement.
+    recovered: Vec<Spanned<ParseError<'a>>>,
+    // How deep the syntax tree being built is right now. This is how deep the parser has recursed, and how deep every
+    // pass after it will.
+    depth: usize,
+    options: ParserOptions,
+    // The identifier the last condition ended with, if a `{` followed it, along with where that brace starts.
+    braced_condition: Option<(Spanned<&'a str>, usize)>,
+    // The type parameters of the generic struct or type alias being parsed, which types can refer to by name.
+    type_parameters: Vec<&'a str>,
     scanner: std::iter::Peekable<T>,
 }
 
+impl<'a> Parser<'a, Lexer<'a>> {
+    // Parse a snippet like `1 + 2 * 3` on its own, without wrapping it in a function first.
+    pub fn parse_expression_str(source: &'a Source) -> ExpressionResult<'a> {
+        Parser::new(Lexer::new(source)).parse_single_expression()
+    }
+}
+
 impl<'a, T> Parser<'a, T>
 where
     T: Scanner<'a> + 'a,
 {
     pub fn new(scanner: T) -> Self {
+        Self::new_with_options(scanner, ParserOptions::default())
+    }
+
+    pub fn new_with_options(scanner: T, options: ParserOptions) -> Self {
         let source = scanner.source();
         let peekable = scanner.peekable();
 
         Self {
             source,
             error_count: 0,
+            previous_end: 0,
+            recovered: vec![],
+            depth: 0,
+            options,
+            braced_condition: None,
+            type_parameters: vec![],
             scanner: peekable,
         }
     }
 
+    // Deeper nesting is reported as an error instead of overflowing the stack.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.options.max_depth = max_depth;
+    }
+
     pub fn parse(&mut self) -> Program<'a> {
         let mut top_level_declarations = vec![];
 
-        while self.scanner.peek().is_some() {
+        while !self.at_end() {
             let declaration = self.top_level_declaration();
 
             if let Ok(declaration) = declaration {
                 top_level_declarations.push(declaration);
+                top_level_declarations.extend(
+                    self.take_recovered()
+                        .into_iter()
+                        .map(|error| TopLevel::Error { error }),
+                );
             } else if let Err(error) = declaration {
+                top_level_declarations.extend(
+                    self.take_recovered()
+                        .into_iter()
+                        .map(|error| TopLevel::Error { error }),
+                );
                 top_level_declarations.push(TopLevel::Error { error });
                 self.error_count += 1;
 
@@ -61,8 +189,10 @@ where
                     || self.peek_equals(&TokenType::Type)
                     || self.at_end())
                 {
+                    // Lexing errors in the skipped code have not been reported yet.
                     if let Err(error) = self.advance() {
-                        panic!("error in {}: {:?}", self.source.name, error);
+                        top_level_declarations.push(TopLevel::Error { error });
+                        self.error_count += 1;
                     }
                 }
             }
@@ -71,20 +201,65 @@ where
         Program(top_level_declarations)
     }
 
+    // Parse exactly one expression, which has to make up the whole input.
+    pub fn parse_single_expression(&mut self) -> ExpressionResult<'a> {
+        let expression = self.expression(false)?;
+
+        if !self.at_end() {
+            let peek = self.advance()?;
+            return Err(self.consume_error(&peek, "end of input".to_owned()).unwrap_err());
+        }
+
+        Ok(expression)
+    }
+
     fn parse_expression(
         &mut self,
         precedence: Precedence,
         no_struct: bool,
     ) -> ExpressionResult<'a> {
-        let token = self.advance()?;
-        let mut left = self.prefix(&token, no_struct)?;
+        self.nested(|parser| parser.parse_expression_unchecked(precedence, no_struct))
+    }
 
-        while self.next_higher_precedence(precedence, no_struct) {
-            let token = self.advance()?;
-            left = self.infix(&token, left, no_struct)?;
+    fn parse_expression_unchecked(
+        &mut self,
+        precedence: Precedence,
+        no_struct: bool,
+    ) -> ExpressionResult<'a> {
+        // A missing operand is reported without consuming the `;` or `}` after it, like in `1 + ;`, so the statement or
+        // block ends as written.
+        if let Some(Ok(peek)) = self.scanner.peek() {
+            if let TokenType::Semicolon | TokenType::RightBrace = peek.node {
+                let token = peek.clone();
+                return self.prefix_error(&token);
+            }
         }
 
-        Ok(left)
+        let token = self.advance()?;
+        let left = self.prefix(&token, no_struct)?;
+
+        self.continue_expression(left, precedence, no_struct)
+    }
+
+    // Parse the operators that follow `left` in an expression, binding tighter than `precedence`. Every operator wraps
+    // the expression before it, so chains like `a.b.c` or `1 + 2 + 3` nest as deeply as parentheses do, without the
+    // parser recursing.
+    fn continue_expression(
+        &mut self,
+        mut left: Spanned<Expression<'a>>,
+        precedence: Precedence,
+        no_struct: bool,
+    ) -> ExpressionResult<'a> {
+        self.chain(|parser| {
+            while parser.next_higher_precedence(precedence, no_struct) {
+                parser.descend()?;
+
+                let token = parser.advance()?;
+                left = parser.infix(&token, left, no_struct)?;
+            }
+
+            Ok(left)
+        })
     }
 
     pub fn expression(&mut self, no_struct: bool) -> ExpressionResult<'a> {
@@ -92,6 +267,13 @@ where
 
         while self.peek_equals(&TokenType::Equals) {
             let eq = self.consume(TokenType::Equals)?;
+            if left.node.is_r_value() {
+                self.recovered.push(Spanned::new_from_span(
+                    left.span,
+                    ParseError::InvalidAssignmentTarget,
+                ));
+            }
+
             let value = Box::new(self.expression(no_struct)?);
 
             left = Spanned::new(
@@ -108,32 +290,183 @@ where
         Ok(left)
     }
 
+    // The condition of an `if` or `while`. A bare `=` in there is almost certainly meant to be `==`, so it is reported
+    // and parsed as a comparison, keeping the rest of the statement intact. Parenthesized assignments are left to the
+    // resolver, which warns about them unless they are wrapped twice.
+    fn condition(&mut self) -> ExpressionResult<'a> {
+        let mut condition = self.parse_expression(Precedence::Assignment, true)?;
+
+        condition = self.chain(|parser| {
+            while parser.peek_equals(&TokenType::Equals) {
+                parser.descend()?;
+
+                let eq = parser.consume(TokenType::Equals)?;
+                parser.recovered.push(Spanned::new_from_span(
+                    eq.span,
+                    ParseError::AssignmentInCondition,
+                ));
+
+                let equals = Spanned::new_from_span(eq.span, TokenType::EqualsEquals);
+                condition = parser.infix(&equals, condition, true)?;
+                condition = parser.continue_expression(condition, Precedence::Assignment, true)?;
+            }
+
+            Ok(condition)
+        })?;
+
+        self.braced_condition = match (condition.node.kind(), self.scanner.peek()) {
+            (
+                ExpressionKind::Identifier(name),
+                Some(Ok(Spanned {
+                    node: TokenType::LeftBrace,
+                    span,
+                })),
+            ) => Some((Spanned::new_from_span(condition.span, *name), span.start)),
+            _ => None,
+        };
+
+        Ok(condition)
+    }
+
     fn statement(&mut self) -> StatementResult<'a> {
         if let Some(Ok(Spanned { node, .. })) = self.scanner.peek() {
             match node {
                 TokenType::Let => {
                     let declaration = self.let_declaration()?;
-                    self.consume(TokenType::Semicolon)?;
+                    self.consume_semicolon()?;
 
                     return Ok(declaration);
                 }
 
-                TokenType::If => return Ok(self.if_statement()?),
-                TokenType::Return => return Ok(self.return_statement()?),
-                TokenType::While => return Ok(self.while_statement()?),
-                TokenType::Delete => return Ok(self.delete_statement()?),
+                TokenType::If => return self.if_statement(),
+                TokenType::Return => return self.return_statement(),
+                TokenType::While => return self.while_statement(None),
+                TokenType::Match => return self.match_statement(),
+                TokenType::Delete => return self.delete_statement(),
+                TokenType::LeftBrace => return self.block_statement(None),
+                TokenType::Break | TokenType::Continue => return self.loop_control_statement(),
+                TokenType::Fn => return self.nested_function(),
 
                 _ => {}
             }
         }
 
         let expression = self.expression(false)?;
-        self.consume(TokenType::Semicolon)?;
+        self.expression_statement(expression)
+    }
+
+    // Nested functions take no attributes and are never public, since they are only visible in their block.
+    fn nested_function(&mut self) -> StatementResult<'a> {
+        let function = self.function_definition(vec![], false, false)?;
+        Ok(Statement::FunctionDeclaration(Box::new(function)))
+    }
+
+    // The rest of a statement that starts with `expression`.
+    fn expression_statement(&mut self, expression: Spanned<Expression<'a>>) -> StatementResult<'a> {
+        // A bare identifier followed by a colon is a label, e.g. `outer: while true { break outer; }`. This needs no
+        // additional lookahead, since `identifier:` is not a valid expression statement otherwise.
+        if let ExpressionKind::Identifier(label) = expression.node.kind() {
+            if self.match_token(TokenType::Colon)? {
+                self.require_feature(Feature::Labels, expression.span);
+                return self.labeled_statement(Spanned::new_from_span(expression.span, label));
+            }
+        }
+
+        self.consume_semicolon()?;
 
         Ok(Statement::ExpressionStatement(expression))
     }
 
+    fn labeled_statement(&mut self, label: Spanned<&'a str>) -> StatementResult<'a> {
+        if self.peek_equals(&TokenType::While) {
+            return self.while_statement(Some(label));
+        }
+
+        if self.peek_equals(&TokenType::LeftBrace) {
+            return self.block_statement(Some(label));
+        }
+
+        if let Some(error) = self.struct_in_condition(&label) {
+            return Err(error);
+        }
+
+        let token = self.advance()?;
+        Err(self
+            .consume_error(&token, "loop or block after label".to_owned())
+            .unwrap_err())
+    }
+
+    // `while Pair { key: k } { ... }` reads like a struct initializer in the condition, but its braces are taken for the
+    // body, where `key:` is a label. A label right at the start of that body that is not followed by a loop or a block
+    // means a struct was meant. The rest of the initializer is skipped, up to the brace closing it, which is left to the
+    // block it was taken for.
+    fn struct_in_condition(&mut self, label: &Spanned<&'a str>) -> Option<Spanned<ParseError<'a>>> {
+        let (name, brace) = self.braced_condition.take()?;
+        if !self.source.code[brace + 1..label.span.start].trim().is_empty() {
+            return None;
+        }
+
+        let mut open = 0;
+        let mut end = label.span.end;
+
+        while let Some(Ok(peek)) = self.scanner.peek() {
+            match peek.node {
+                TokenType::RightBrace if open == 0 => {
+                    end = peek.span.end;
+                    break;
+                }
+                TokenType::Eof => break,
+                TokenType::LeftBrace => open += 1,
+                TokenType::RightBrace => open -= 1,
+                _ => {}
+            }
+
+            let _ = self.advance();
+        }
+
+        Some(Spanned::new(name.span.start, end, ParseError::StructInCondition(name.node)))
+    }
+
+    fn block_statement(&mut self, label: Option<Spanned<&'a str>>) -> StatementResult<'a> {
+        let body = self.block()?;
+
+        Ok(Statement::BlockStatement(Box::new(BlockStatement {
+            label,
+            body,
+        })))
+    }
+
+    fn loop_control_statement(&mut self) -> StatementResult<'a> {
+        let keyword = self.advance()?;
+        let label = if let Some(Ok(Spanned {
+            node: TokenType::Identifier(_),
+            ..
+        })) = self.scanner.peek()
+        {
+            let label = self.consume_identifier()?;
+            self.require_feature(Feature::Labels, label.span);
+            Some(label)
+        } else {
+            None
+        };
+
+        self.consume_semicolon()?;
+
+        let control = LoopControl { keyword, label };
+        Ok(if let TokenType::Break = control.keyword.node {
+            Statement::BreakStatement(control)
+        } else {
+            Statement::ContinueStatement(control)
+        })
+    }
+
     fn let_declaration(&mut self) -> StatementResult<'a> {
+        let declaration = self.variable_declaration()?;
+        Ok(Statement::VariableDeclaration(Box::new(declaration)))
+    }
+
+    // `let name: type = value` without the semicolon, which locals and constants share.
+    fn variable_declaration(&mut self) -> ParseResult<'a, VariableDeclaration<'a>> {
         self.consume(TokenType::Let)?;
 
         let name = self.consume_identifier()?;
@@ -147,26 +480,25 @@ where
         let eq = self.consume(TokenType::Equals)?;
         let value = self.expression(false)?;
 
-        Ok(Statement::VariableDeclaration(Box::new(
-            VariableDeclaration {
-                name,
-                value,
-                eq,
-                ty,
-            },
-        )))
+        Ok(VariableDeclaration {
+            name,
+            value,
+            eq,
+            ty,
+        })
     }
 
     fn if_statement(&mut self) -> StatementResult<'a> {
         self.consume(TokenType::If)?;
 
-        let condition = self.expression(true)?;
-        let then_block = self.block()?;
+        let condition = self.condition()?;
+        let then_block = self.condition_body()?;
         let else_branch = if self.peek_equals(&TokenType::Else) {
             self.consume(TokenType::Else)?;
 
+            // Every `else if` is nested in the one before it.
             let else_branch = if self.peek_equals(&TokenType::If) {
-                let else_if = Box::new(self.if_statement()?);
+                let else_if = Box::new(self.nested(Self::if_statement)?);
                 Else::IfStatement(else_if)
             } else {
                 let block = self.block()?;
@@ -185,10 +517,45 @@ where
         })))
     }
 
+    // The body of an `if` or `while`. If it turns out to be a struct initializer from the condition, the block right
+    // after the initializer is the actual body, which keeps the error about the initializer.
+    fn condition_body(&mut self) -> ParseResult<'a, Block<'a>> {
+        let mut block = self.block()?;
+
+        let is_initializer = matches!(
+            block.0.as_slice(),
+            [Statement::ExpressionStatement(expression)]
+                if matches!(expression.node.kind(), ExpressionKind::Error(ParseError::StructInCondition(_)))
+        );
+
+        if is_initializer && self.peek_equals(&TokenType::LeftBrace) {
+            let body = self.block()?;
+
+            block.0.extend(body.0);
+            block.1 = Span::new(block.1.start, body.1.end);
+        }
+
+        Ok(block)
+    }
+
     fn return_statement(&mut self) -> StatementResult<'a> {
-        self.consume(TokenType::Return)?;
+        let keyword = self.consume(TokenType::Return)?;
+
+        // `return` right before another statement or the end of the block is missing either its value or its
+        // semicolon, which cannot be told apart, so the error says both.
+        if self.peek_starts_statement() {
+            self.recovered.push(Spanned::new_from_span(
+                keyword.span,
+                ParseError::UnterminatedReturn {
+                    position: self.previous_end,
+                },
+            ));
+
+            return Ok(Statement::ReturnStatement(keyword, None));
+        }
 
         let ret = Ok(Statement::ReturnStatement(
+            keyword,
             if self.peek_equals(&TokenType::Semicolon) {
                 None
             } else {
@@ -196,27 +563,70 @@ where
             },
         ));
 
-        self.consume(TokenType::Semicolon)?;
+        self.consume_semicolon()?;
 
         ret
     }
 
-    fn while_statement(&mut self) -> StatementResult<'a> {
+    fn while_statement(&mut self, label: Option<Spanned<&'a str>>) -> StatementResult<'a> {
         self.consume(TokenType::While)?;
 
-        let condition = self.expression(true)?;
-        let body = self.block()?;
+        let condition = self.condition()?;
+        let body = self.condition_body()?;
 
         Ok(Statement::WhileStatement(Box::new(WhileStatement {
+            label,
             condition,
             body,
         })))
     }
 
+    fn match_statement(&mut self) -> StatementResult<'a> {
+        let keyword = self.consume(TokenType::Match)?;
+        let scrutinee = self.expression(true)?;
+
+        self.consume(TokenType::LeftBrace)?;
+
+        let mut arms = vec![];
+        let mut default = None;
+
+        while !self.at_end() && !self.peek_equals(&TokenType::RightBrace) {
+            if self.match_token(TokenType::Default)? {
+                self.consume(TokenType::Colon)?;
+                default = Some(self.block()?);
+            } else {
+                self.consume(TokenType::Case)?;
+
+                let pattern = self.expression(true)?;
+                self.consume(TokenType::Colon)?;
+
+                arms.push((pattern, self.block()?));
+            }
+
+            self.match_token(TokenType::Semicolon)?;
+        }
+
+        self.consume(TokenType::RightBrace)?;
+
+        // Without a `case`, the `default` would run every time, so the `match` is left over from something.
+        if arms.is_empty() {
+            self.recovered
+                .push(Spanned::new_from_span(keyword.span, ParseError::EmptyMatch));
+        }
+
+        Ok(Statement::MatchStatement(Box::new(MatchStatement {
+            keyword,
+            scrutinee,
+            arms,
+            default,
+            exhaustive: std::cell::Cell::new(false),
+        })))
+    }
+
     fn delete_statement(&mut self) -> StatementResult<'a> {
         self.consume(TokenType::Delete)?;
         let expression = self.expression(false)?;
-        self.consume(TokenType::Semicolon)?;
+        self.consume_semicolon()?;
 
         Ok(Statement::DeleteStatement(Box::new(expression)))
     }
@@ -235,22 +645,28 @@ where
         })
     }
 
-    fn function_definition(&mut self) -> TopLevelResult<'a> {
-        let is_external = self.peek_equals(&TokenType::Extern);
-        if is_external {
-            self.consume(TokenType::Extern)?;
-        }
-
+    // The `extern` of an external function has to be consumed by the caller already, since it is shared with extern
+    // variables.
+    fn function_definition(
+        &mut self,
+        attributes: Vec<Attribute<'a>>,
+        is_public: bool,
+        is_external: bool,
+    ) -> TopLevelResult<'a> {
         self.consume(TokenType::Fn)?;
 
         let name = self.consume_identifier()?;
         let arguments = self.parameter_list(is_external)?;
 
-        self.consume(TokenType::Arrow)?;
+        // Functions without `=> Type` return nothing, as if they were declared with `=> void`.
+        let return_type = if self.match_token(TokenType::Arrow)? {
+            self.consume_type()?
+        } else {
+            Spanned::new_from_span(name.span, Type::Simple(Simple::Void))
+        };
 
-        let return_type = self.consume_type()?;
         let body = if is_external {
-            self.consume(TokenType::Semicolon)?;
+            self.consume_semicolon()?;
             Block::default()
         } else {
             self.block()?
@@ -262,36 +678,199 @@ where
             body,
             return_type,
             is_external,
+            is_public,
+            attributes,
         })
     }
 
+    // Parse the `[name, ...]` part of an attribute; the leading `@` has to be consumed by the caller already, since it is
+    // shared with struct fields.
+    fn attribute_list(&mut self) -> ParseResult<'a, Vec<Attribute<'a>>> {
+        self.consume(TokenType::LeftBracket)?;
+
+        let mut attributes = vec![];
+
+        while !self.at_end() && !self.peek_equals(&TokenType::RightBracket) {
+            let name = self.consume_identifier()?;
+
+            let mut arguments = vec![];
+            if self.match_token(TokenType::LeftParen)? {
+                while !self.at_end() && !self.peek_equals(&TokenType::RightParen) {
+                    // Names, or numbers like the alignment in `align(8)`.
+                    let argument = match self.scanner.peek().cloned() {
+                        Some(Ok(Spanned {
+                            node: TokenType::DecLiteral(literal),
+                            span,
+                        })) => {
+                            self.advance()?;
+                            Spanned::new_from_span(span, literal)
+                        }
+                        _ => self.consume_identifier()?,
+                    };
+
+                    arguments.push(argument);
+
+                    if !self.peek_equals(&TokenType::RightParen) {
+                        self.consume(TokenType::Comma)?;
+                    }
+                }
+
+                self.consume(TokenType::RightParen)?;
+            }
+
+            // A `cfg` that checks for nothing, or for several names at once, is most likely a mistake, so which one was
+            // meant is not guessed.
+            if name.node == CFG_ATTRIBUTE && arguments.len() != 1 {
+                self.recovered
+                    .push(Spanned::new_from_span(name.span, ParseError::CfgArguments(arguments.len())));
+            }
+
+            if name.node == ALIGN_ATTRIBUTE {
+                match arguments.as_slice() {
+                    [align] if parse_alignment(align.node).is_some() => {}
+                    [align] => self
+                        .recovered
+                        .push(Spanned::new_from_span(align.span, ParseError::InvalidAlignment)),
+                    _ => self
+                        .recovered
+                        .push(Spanned::new_from_span(name.span, ParseError::InvalidAlignment)),
+                }
+            }
+
+            attributes.push(Attribute { name, arguments });
+
+            if !self.peek_equals(&TokenType::RightBracket) {
+                self.consume(TokenType::Comma)?;
+            }
+        }
+
+        self.consume(TokenType::RightBracket)?;
+
+        Ok(attributes)
+    }
+
     fn top_level_declaration(&mut self) -> TopLevelResult<'a> {
         if self.peek_equals(&TokenType::Import) {
             return self.import_statement();
         }
 
+        if self.peek_equals(&TokenType::From) {
+            return self.selective_import_statement();
+        }
+
+        let mut attributes = vec![];
+        while self.match_token(TokenType::At)? {
+            attributes.append(&mut self.attribute_list()?);
+        }
+
+        let is_public = self.match_token(TokenType::Pub)?;
+
         if self.peek_equals(&TokenType::Type) {
-            return self.type_declaration_statement();
+            return self.type_declaration_statement(attributes, is_public);
+        }
+
+        let is_external = self.match_token(TokenType::Extern)?;
+        if is_external && self.peek_equals(&TokenType::Let) {
+            return self.extern_variable_declaration(attributes, is_public);
+        }
+
+        if self.peek_equals(&TokenType::Let) {
+            return self.constant_declaration(attributes, is_public);
         }
 
-        self.function_definition()
+        self.function_definition(attributes, is_public, is_external)
+    }
+
+    fn extern_variable_declaration(
+        &mut self,
+        attributes: Vec<Attribute<'a>>,
+        is_public: bool,
+    ) -> TopLevelResult<'a> {
+        self.consume(TokenType::Let)?;
+
+        let name = self.consume_identifier()?;
+        self.consume(TokenType::Colon)?;
+        let ty = self.consume_type()?;
+        self.consume_semicolon()?;
+
+        Ok(TopLevel::ExternVariable {
+            name,
+            ty,
+            is_public,
+            attributes,
+        })
+    }
+
+    fn constant_declaration(&mut self, attributes: Vec<Attribute<'a>>, is_public: bool) -> TopLevelResult<'a> {
+        let declaration = self.variable_declaration()?;
+        self.consume_semicolon()?;
+
+        Ok(TopLevel::Constant {
+            declaration,
+            is_public,
+            attributes,
+        })
     }
 
     fn import_statement(&mut self) -> TopLevelResult<'a> {
         self.consume(TokenType::Import)?;
         let name = self.consume_string()?;
-        self.consume(TokenType::Semicolon)?;
+        self.consume_semicolon()?;
 
-        Ok(TopLevel::Import { name })
+        Ok(TopLevel::Import {
+            name,
+            symbols: vec![],
+        })
     }
 
-    fn struct_declaration(&mut self, name: &Spanned<&'a str>) -> TopLevelResult<'a> {
-        self.consume(TokenType::Struct)?;
+    fn selective_import_statement(&mut self) -> TopLevelResult<'a> {
+        self.consume(TokenType::From)?;
+        let name = self.consume_string()?;
+        self.consume(TokenType::Import)?;
 
-        if self.peek_equals(&TokenType::Smaller) {
-            self.consume_generic_parameters()?;
+        let mut symbols = vec![self.consume_identifier()?];
+        while self.match_token(TokenType::Comma)? {
+            symbols.push(self.consume_identifier()?);
         }
 
+        self.consume_semicolon()?;
+
+        Ok(TopLevel::Import { name, symbols })
+    }
+
+    fn struct_declaration(
+        &mut self,
+        name: &Spanned<&'a str>,
+        attributes: Vec<Attribute<'a>>,
+        is_public: bool,
+    ) -> TopLevelResult<'a> {
+        self.consume(TokenType::Struct)?;
+
+        let generic_parameters = if self.peek_equals(&TokenType::Smaller) {
+            self.consume_generic_parameters()?
+        } else {
+            vec![]
+        };
+
+        self.type_parameters = generic_parameters.iter().map(|parameter| parameter.node).collect();
+        let body = self.struct_body();
+        self.type_parameters.clear();
+
+        let (fields, methods) = body?;
+
+        return Ok(TopLevel::TypeDeclaration {
+            ty: TypeDeclaration::StructDefinition {
+                name: *name,
+                generic_parameters,
+                fields,
+                methods,
+            },
+            is_public,
+            attributes,
+        });
+    }
+
+    fn struct_body(&mut self) -> ParseResult<'a, (Vec<Field<'a>>, Vec<TopLevel<'a>>)> {
         self.consume(TokenType::LeftBrace)?;
 
         let mut fields = Vec::new();
@@ -299,38 +878,26 @@ where
 
         if !self.at_end() && !self.peek_equals(&TokenType::RightBrace) {
             loop {
+                let mut attributes = vec![];
+
                 if self.peek_equals(&TokenType::At) {
                     self.advance()?;
 
-                    let field_name = self.consume_identifier()?;
-                    self.consume(TokenType::Colon)?;
+                    if self.peek_equals(&TokenType::LeftBracket) {
+                        attributes = self.attribute_list()?;
+                    } else {
+                        let field_name = self.consume_identifier()?;
+                        self.consume(TokenType::Colon)?;
 
-                    let field_type = self.consume_type()?;
-                    fields.push((field_name, field_type));
+                        let field_type = self.consume_type()?;
+                        fields.push((field_name, field_type));
+                    }
                 }
 
-                if self.peek_equals(&TokenType::Fn) {
-                    let method = self.function_definition()?;
-
-                    match method {
-                        TopLevel::FunctionDeclaration {
-                            name,
-                            arguments,
-                            body,
-                            return_type,
-                            is_external,
-                        } => {
-                            methods.push(TopLevel::FunctionDeclaration {
-                                name,
-                                arguments,
-                                body,
-                                return_type,
-                                is_external,
-                            });
-                        }
-
-                        _ => panic!("expected a method declaration"),
-                    }
+                if self.peek_equals(&TokenType::Fn) || !attributes.is_empty() {
+                    // Methods are visible wherever their struct is.
+                    let is_external = self.match_token(TokenType::Extern)?;
+                    methods.push(self.function_definition(attributes, false, is_external)?);
                 }
 
                 if self.at_end() || self.peek_equals(&TokenType::RightBrace) {
@@ -343,16 +910,15 @@ where
 
         self.consume(TokenType::RightBrace)?;
 
-        return Ok(TopLevel::TypeDeclaration {
-            ty: TypeDeclaration::StructDefinition {
-                name: *name,
-                fields,
-                methods,
-            },
-        });
+        Ok((fields, methods))
     }
 
-    fn enum_declaration(&mut self, name: &Spanned<&'a str>) -> TopLevelResult<'a> {
+    fn enum_declaration(
+        &mut self,
+        name: &Spanned<&'a str>,
+        attributes: Vec<Attribute<'a>>,
+        is_public: bool,
+    ) -> TopLevelResult<'a> {
         self.consume(TokenType::Enum)?;
 
         let mut ty = Spanned::new(0, 0, Type::Simple(Simple::Void));
@@ -370,6 +936,8 @@ where
                 let field_name = self.consume_identifier()?;
                 fields.push((field_name, ty.clone()));
 
+                self.match_token(TokenType::Comma)?;
+
                 if self.at_end() || self.peek_equals(&TokenType::RightBrace) {
                     break;
                 }
@@ -380,41 +948,64 @@ where
 
         return Ok(TopLevel::TypeDeclaration {
             ty: TypeDeclaration::EnumDefinition { name: *name, fields },
+            is_public,
+            attributes,
         });
     }
 
-    fn type_alias_declaration(&mut self, name: &Spanned<&'a str>) -> TopLevelResult<'a> {
-        let generic_parameters = self.consume_generic_parameters()?;
+    fn type_alias_declaration(
+        &mut self,
+        name: &Spanned<&'a str>,
+        attributes: Vec<Attribute<'a>>,
+        is_public: bool,
+    ) -> TopLevelResult<'a> {
+        // `type Int = i32;` aliases a type without any generic parameters.
+        let generic_parameters = if self.peek_equals(&TokenType::Smaller) {
+            self.consume_generic_parameters()?
+        } else {
+            vec![]
+        };
         self.consume(TokenType::Equals)?;
 
-        let ty = self.consume_type()?;
+        self.type_parameters = generic_parameters.iter().map(|parameter| parameter.node).collect();
+        let ty = self.consume_type();
+        self.type_parameters.clear();
 
-        self.consume(TokenType::Semicolon)?;
+        let ty = ty?;
+
+        self.consume_semicolon()?;
 
         return Ok(TopLevel::TypeDeclaration {
-            ty: TypeDeclaration::TypeAlias { name: *name, generic_parameters, ty }
+            ty: TypeDeclaration::TypeAlias { name: *name, generic_parameters, ty },
+            is_public,
+            attributes,
         });
     }
 
-    fn type_declaration_statement(&mut self) -> TopLevelResult<'a> {
+    fn type_declaration_statement(
+        &mut self,
+        attributes: Vec<Attribute<'a>>,
+        is_public: bool,
+    ) -> TopLevelResult<'a> {
         self.consume(TokenType::Type)?;
 
         let name = self.consume_identifier()?;
 
-        if self.peek_equals(&TokenType::Smaller) {
-            return self.type_alias_declaration(&name);
+        if self.peek_equals(&TokenType::Smaller) || self.peek_equals(&TokenType::Equals) {
+            return self.type_alias_declaration(&name, attributes, is_public);
         }
 
         if self.peek_equals(&TokenType::Struct) {
-            return self.struct_declaration(&name);
+            return self.struct_declaration(&name, attributes, is_public);
         }
 
         if self.peek_equals(&TokenType::Trait) {
-            panic!("NOT IMPLEMENTED YET")
+            let token = self.advance()?;
+            return Err(Spanned::new_from_span(token.span, ParseError::Unsupported("traits")));
         }
 
         if self.peek_equals(&TokenType::Enum) {
-            return self.enum_declaration(&name);
+            return self.enum_declaration(&name, attributes, is_public);
         }
 
         TopLevelResult::Err(Spanned::new_from_span(
@@ -437,11 +1028,13 @@ where
 
         while !self.peek_equals(&TokenType::RightParen) {
             if self.peek_equals(&TokenType::Varargs) {
+                let varargs_token = self.consume(TokenType::Varargs)?;
                 if !is_external {
-                    panic!("varargs are only supported in external functions");
+                    self.recovered.push(Spanned::new_from_span(
+                        varargs_token.span,
+                        ParseError::VarargsOutsideExtern,
+                    ));
                 }
-
-                let varargs_token = self.consume(TokenType::Varargs)?;
                 varargs = true;
 
                 let spanned = Spanned::new_from_span(varargs_token.span, "...");
@@ -491,9 +1084,14 @@ where
 
         while !self.at_end() && !self.peek_equals(&TokenType::RightBrace) {
             let identifier = self.consume_identifier()?;
-            self.consume(TokenType::Colon)?;
 
-            let expression = self.expression(false)?;
+            // `Pair { key }` is shorthand for `Pair { key: key }`.
+            let expression = if self.match_token(TokenType::Colon)? {
+                self.expression(false)?
+            } else {
+                identifier.map(|name| Expression::new(ExpressionKind::Identifier(name)))
+            };
+
             inits.push((identifier, expression));
 
             if !self.peek_equals(&TokenType::RightBrace) {
@@ -504,26 +1102,118 @@ where
         Ok(InitializerList(inits))
     }
 
+    // A block nested too deeply is skipped as a whole, so the blocks around it still find their closing braces.
     fn block(&mut self) -> ParseResult<'a, Block<'a>> {
-        self.consume(TokenType::LeftBrace)?;
+        let result = self.nested(Self::block_unchecked);
+
+        if let Err(Spanned {
+            node: ParseError::NestingTooDeep(_),
+            ..
+        }) = result
+        {
+            self.skip_braces();
+        }
+
+        result
+    }
+
+    // Skip everything up to and including the brace closing the next one.
+    fn skip_braces(&mut self) {
+        let mut open = 0;
+
+        while !self.at_end() {
+            match self.advance().map(|token| token.node) {
+                Ok(TokenType::LeftBrace) => open += 1,
+                Ok(TokenType::RightBrace) if open <= 1 => return,
+                Ok(TokenType::RightBrace) => open -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    // Skip the rest of a statement, along with every block and `else` in it. Syncing would stop at the first statement
+    // inside those blocks instead, which is just as deep if the statement is nested too deeply, and would leave their
+    // braces unbalanced after a reserved keyword like `finally { ... }`.
+    fn skip_statement(&mut self) {
+        let mut open = 0;
+
+        while !self.at_end() {
+            if open == 0 && self.peek_equals(&TokenType::RightBrace) {
+                return;
+            }
+
+            match self.advance().map(|token| token.node) {
+                Ok(TokenType::LeftBrace) => open += 1,
+                Ok(TokenType::RightBrace) if open == 1 && !self.peek_equals(&TokenType::Else) => return,
+                Ok(TokenType::RightBrace) => open -= 1,
+                Ok(TokenType::Semicolon) if open == 0 => return,
+                _ => {}
+            }
+        }
+    }
+
+    fn block_unchecked(&mut self) -> ParseResult<'a, Block<'a>> {
+        let left_brace = self.consume(TokenType::LeftBrace)?;
+        let (block, _) = self.block_contents(left_brace, false)?;
 
+        Ok(block)
+    }
+
+    // The statements up to the brace closing `left_brace`. A block used as an expression may end with an expression
+    // without a semicolon, which is its value.
+    fn block_contents(
+        &mut self,
+        left_brace: Spanned<TokenType<'a>>,
+        yields_value: bool,
+    ) -> ParseResult<'a, (Block<'a>, Option<Spanned<Expression<'a>>>)> {
         let mut statements = vec![];
+        let mut value = None;
+
         while !self.at_end() && !self.peek_equals(&TokenType::RightBrace) {
-            let statement = self.statement();
+            let may_be_value = yields_value
+                && !self.peek_starts_statement()
+                && !self.peek_equals(&TokenType::LeftBrace);
+
+            let statement = if may_be_value {
+                match self.expression(false) {
+                    Ok(expression) if self.peek_equals(&TokenType::RightBrace) => {
+                        value = Some(expression);
+                        statements.extend(self.take_recovered().into_iter().map(error_statement));
+
+                        break;
+                    }
+                    Ok(expression) => self.expression_statement(expression),
+                    Err(error) => Err(error),
+                }
+            } else {
+                self.statement()
+            };
+
             if let Ok(statement) = statement {
                 statements.push(statement);
             } else if let Err(error) = statement {
                 self.error_count += 1;
-                self.sync();
+
+                // Nothing is left to skip if the error is right before the brace closing this block, like after a block
+                // nested too deeply or a struct initializer taken for this block. Syncing would skip that brace too.
+                match error.node {
+                    _ if self.peek_equals(&TokenType::RightBrace) => {}
+                    ParseError::NestingTooDeep(_) | ParseError::ReservedKeyword(_) => self.skip_statement(),
+                    _ => self.sync(),
+                }
+
                 statements.push(error_statement(error));
             }
-        }
 
-        if !self.at_end() {
-            self.consume(TokenType::RightBrace)?;
+            statements.extend(self.take_recovered().into_iter().map(error_statement));
         }
 
-        Ok(Block(statements))
+        let right_brace = self.consume(TokenType::RightBrace)?;
+
+        Ok((
+            Block(statements, Span::new(left_brace.span.start, right_brace.span.end)),
+            value,
+        ))
     }
 
     fn consume_identifier(&mut self) -> ParseResult<'a, Spanned<&'a str>> {
@@ -545,13 +1235,14 @@ where
                     }
                 }
 
-                Err(error) => Err(error),
+                Err(error) => self.skip_error(error),
             };
         }
 
         Err(self.eof().unwrap_err())
     }
 
+    // Strings are only consumed as module names, which refer to files, so they are taken as they are written.
     fn consume_string(&mut self) -> ParseResult<'a, Spanned<&'a str>> {
         if let Some(peek) = self.scanner.peek().cloned() {
             return match peek {
@@ -562,6 +1253,11 @@ where
                     } = peek
                     {
                         self.advance()?;
+
+                        let std::borrow::Cow::Borrowed(literal) = literal else {
+                            return Err(Spanned::new_from_span(span, ParseError::EscapedModuleName));
+                        };
+
                         return Ok(Spanned::new_from_span(span, literal));
                     } else {
                         let token = Spanned::clone(&peek);
@@ -569,7 +1265,7 @@ where
                     }
                 }
 
-                Err(error) => Err(error),
+                Err(error) => self.skip_error(error),
             };
         }
 
@@ -603,6 +1299,33 @@ where
         })
     }
 
+    // The `<i32, string>` of `Pair<i32, string>`, along with the end of the closing `>`.
+    fn consume_type_arguments(&mut self) -> ParseResult<'a, (Vec<Type<'a>>, usize)> {
+        self.consume(TokenType::Smaller)?;
+
+        let mut arguments = vec![self.consume_type()?.node];
+        while self.match_token(TokenType::Comma)? {
+            arguments.push(self.consume_type()?.node);
+        }
+
+        // Nested arguments close with one `>` each, since the lexer has no `>>`. But in `let x: List<i32>= y`, the `>=`
+        // closes the arguments as well, so it is split into a `>` that is taken here and an `=` that is left.
+        if let Some(Ok(token)) = self.scanner.peek_mut() {
+            if token.node == TokenType::GreaterEquals {
+                let end = token.span.start;
+                token.node = TokenType::Equals;
+                token.span.start += 1;
+                self.previous_end = end + 1;
+
+                return Ok((arguments, end));
+            }
+        }
+
+        let greater = self.consume(TokenType::Greater)?;
+
+        Ok((arguments, greater.span.end))
+    }
+
     fn consume_generic_parameters(&mut self) -> ParseResult<'a, Vec<Spanned<&'a str>>> {
         self.consume(TokenType::Smaller)?;
 
@@ -626,6 +1349,10 @@ where
     }
 
     fn consume_type(&mut self) -> ParseResult<'a, Spanned<Type<'a>>> {
+        self.nested(Self::consume_type_unchecked)
+    }
+
+    fn consume_type_unchecked(&mut self) -> ParseResult<'a, Spanned<Type<'a>>> {
         if let Some(peek) = self.scanner.peek().cloned() {
             return match peek {
                 Ok(peek) => match peek {
@@ -637,16 +1364,33 @@ where
                         Ok(Spanned::new_from_span(span, Type::Simple(ty)))
                     }
 
+                    Spanned {
+                        node: TokenType::Identifier(name),
+                        span,
+                    } if self.type_parameters.contains(&name) => {
+                        self.advance()?;
+                        Ok(Spanned::new_from_span(span, Type::Simple(Simple::TypeParameter(name))))
+                    }
+
+                    // Only accesses belong to the name of a type, `<` starts the type arguments of a generic one.
                     Spanned {
                         node: TokenType::Identifier(_),
                         ..
                     } => {
-                        let mut expression = self.parse_expression(Precedence::Assignment, true)?;
+                        let mut expression = self.parse_expression(Precedence::Unary, true)?;
                         let identifier = self.user_identifier(&mut expression)?;
+                        let ty = Type::Simple(Simple::UserDefinedType(identifier));
+
+                        if !self.peek_equals(&TokenType::Smaller) {
+                            return Ok(Spanned::new_from_span(expression.span, ty));
+                        }
+
+                        let (arguments, end) = self.consume_type_arguments()?;
 
-                        Ok(Spanned::new_from_span(
-                            expression.span,
-                            Type::Simple(Simple::UserDefinedType(identifier)),
+                        Ok(Spanned::new(
+                            expression.span.start,
+                            end,
+                            Type::Complex(Complex::Generic(Generic::new(ty, arguments))),
                         ))
                     }
 
@@ -657,16 +1401,20 @@ where
                         let mut counter = 1;
                         let start = self.advance()?.span.start;
 
-                        while self.match_token(TokenType::Star)? {
+                        while self.peek_equals(&TokenType::Star) {
+                            let star = self.advance()?;
                             counter += 1;
+
+                            if counter > Pointer::MAX_SIZE {
+                                return Err(Spanned::new_from_span(
+                                    star.span,
+                                    ParseError::IndirectionTooDeep { indirection: star.node, max: Pointer::MAX_SIZE },
+                                ));
+                            }
                         }
 
                         let ty = self.consume_type()?;
-                        let (inner, end) = if let Type::Simple(s) = ty.node {
-                            (s, ty.span.end)
-                        } else {
-                            return Err(Spanned::new_from_span(ty.span, ParseError::InternalError("reached unreachable code while attempting to parse a pointer type")));
-                        };
+                        let (inner, end) = (ty.node, ty.span.end);
 
                         Ok(Spanned::new(
                             start,
@@ -682,16 +1430,20 @@ where
                         let mut counter = 1;
                         let start = self.advance()?.span.start;
 
-                        while self.match_token(TokenType::Ampersand)? {
+                        while self.peek_equals(&TokenType::Ampersand) {
+                            let ampersand = self.advance()?;
                             counter += 1;
+
+                            if counter > Ref::MAX_SIZE {
+                                return Err(Spanned::new_from_span(
+                                    ampersand.span,
+                                    ParseError::IndirectionTooDeep { indirection: ampersand.node, max: Ref::MAX_SIZE },
+                                ));
+                            }
                         }
 
                         let ty = self.consume_type()?;
-                        let (inner, end) = if let Type::Simple(s) = ty.node {
-                            (s, ty.span.end)
-                        } else {
-                            return Err(Spanned::new_from_span(ty.span, ParseError::InternalError("reached unreachable code while attempting to parse a reference type")));
-                        };
+                        let (inner, end) = (ty.node, ty.span.end);
 
                         Ok(Spanned::new(
                             start,
@@ -704,29 +1456,94 @@ where
                         node: TokenType::LeftBracket,
                         ..
                     } => {
-                        let mut size: Option<Expression> = None;
                         let start = self.advance()?.span.start;
 
-                        while !self.match_token(TokenType::RightBracket)? {
-                            if self.peek_equals(&TokenType::Question) {
-                                self.advance()?;
-                                size = None;
-                            } else {
-                                size = Some(self.expression(true).unwrap().node);
+                        // `[?]` declares an unsized array, everything else has to be a positive size.
+                        let size = if self.match_token(TokenType::Question)? {
+                            None
+                        } else {
+                            let size = self.expression(true)?;
+
+                            if let Ok(ConstValue::Integer(value)) = evaluate(&size.node) {
+                                if value <= 0 {
+                                    return Err(Spanned::new_from_span(size.span, ParseError::ArraySize(value)));
+                                }
                             }
-                        }
 
-                        let ty = self.consume_type()?;
-                        let (inner, end) = if let Type::Simple(s) = ty.node {
-                            (s, ty.span.end)
-                        } else {
-                            return Err(Spanned::new_from_span(ty.span, ParseError::InternalError("reached unreachable code while attempting to parse an array type")));
+                            Some(size.node)
                         };
 
+                        self.consume(TokenType::RightBracket)?;
+
+                        // The element type may be an array itself, which makes for a multi-dimensional array.
+                        let ty = self.consume_type()?;
+
                         Ok(Spanned::new(
                             start,
-                            end,
-                            Type::Complex(Complex::Array(Array::new(inner, Box::new(size))))
+                            ty.span.end,
+                            Type::Complex(Complex::Array(Array::new(ty.node, Box::new(size))))
+                        ))
+                    }
+
+                    // `fn(i32, bool) => void`
+                    Spanned {
+                        node: TokenType::Fn,
+                        ..
+                    } => {
+                        let start = self.advance()?.span.start;
+                        self.consume(TokenType::LeftParen)?;
+
+                        let mut parameters = vec![];
+                        while !self.peek_equals(&TokenType::RightParen) {
+                            parameters.push(self.consume_type()?.node);
+
+                            if !self.match_token(TokenType::Comma)? {
+                                break;
+                            }
+                        }
+
+                        self.consume(TokenType::RightParen)?;
+                        self.consume(TokenType::Arrow)?;
+
+                        let return_type = self.consume_type()?;
+                        self.require_feature(Feature::FunctionTypes, Span::new(start, return_type.span.end));
+
+                        Ok(Spanned::new(
+                            start,
+                            return_type.span.end,
+                            Type::Complex(Complex::Function(Function::new(parameters, return_type.node))),
+                        ))
+                    }
+
+                    // `(i32, bool)`, while a single type in parentheses is only grouped, like `*(fn() => i32)`.
+                    Spanned {
+                        node: TokenType::LeftParen,
+                        ..
+                    } => {
+                        let start = self.advance()?.span.start;
+                        let first = self.consume_type()?;
+
+                        if !self.peek_equals(&TokenType::Comma) {
+                            let paren = self.consume(TokenType::RightParen)?;
+                            return Ok(Spanned::new(start, paren.span.end, first.node));
+                        }
+
+                        let mut elements = vec![first.node];
+                        while self.match_token(TokenType::Comma)? {
+                            if self.peek_equals(&TokenType::RightParen) {
+                                break;
+                            }
+
+                            elements.push(self.consume_type()?.node);
+                        }
+
+                        let paren = self.consume(TokenType::RightParen)?;
+                        self.require_feature(Feature::Tuples, Span::new(start, paren.span.end));
+
+                        Ok(Spanned::new(
+                            start,
+                            paren.span.end,
+                            Type::Complex(Complex::Tuple(Tuple::new(elements))),
                         ))
                     }
 
@@ -736,12 +1553,7 @@ where
                     } => {
                         let start = self.advance()?.span.start;
                         let inner_type = self.consume_type()?;
-
-                        let (inner, end) = if let Type::Simple(s) = inner_type.node {
-                            (s, inner_type.span.end)
-                        } else {
-                            return Err(Spanned::new_from_span(inner_type.span, ParseError::InternalError("reached unreachable code while attempting to parse a nullable type")));
-                        };
+                        let (inner, end) = (inner_type.node, inner_type.span.end);
 
                         Ok(Spanned::new(
                             start,
@@ -753,11 +1565,21 @@ where
 
                     _ => {
                         let token = Spanned::clone(&peek);
+
+                        if let Some(hint) = misplaced_type_hint(&token.node) {
+                            self.error_count += 1;
+
+                            return Err(Spanned::new_from_span(
+                                token.span,
+                                ParseError::MisplacedInType { actual: token.node, hint },
+                            ));
+                        }
+
                         Err(self.consume_error(&token, "type".to_owned()).unwrap_err())
                     }
                 },
 
-                Err(error) => Err(error),
+                Err(error) => self.skip_error(error),
             };
         }
 
@@ -765,82 +1587,133 @@ where
     }
 
     fn prefix(&mut self, token: &Spanned<TokenType<'a>>, no_struct: bool) -> ExpressionResult<'a> {
+        if let Some(precedence) = token.node.prefix_precedence() {
+            return self.unary(token, precedence, no_struct);
+        }
+
         let ok_spanned = |kind| Ok(Spanned::new_from_span(token.span, Expression::new(kind)));
 
         match token.node {
             TokenType::NullLiteral => ok_spanned(ExpressionKind::NullLiteral),
             TokenType::DecLiteral(literal) => ok_spanned(ExpressionKind::DecLiteral(literal)),
             TokenType::FloatLiteral(literal) => ok_spanned(ExpressionKind::FloatLiteral(literal)),
-            TokenType::StringLiteral(literal) => ok_spanned(ExpressionKind::StringLiteral(literal)),
+            TokenType::StringLiteral(ref literal) => {
+                ok_spanned(ExpressionKind::StringLiteral(literal.clone()))
+            }
             TokenType::Char(literal) => ok_spanned(ExpressionKind::Char(literal)),
+            TokenType::True => ok_spanned(ExpressionKind::BoolLiteral(true)),
+            TokenType::False => ok_spanned(ExpressionKind::BoolLiteral(false)),
 
+            // Operands that start like a type are parsed as one, everything else is an expression: `sizeof(*i32)` is the
+            // size of a pointer, not of a dereferenced value. A plain name may be either, which the resolver decides.
             TokenType::Sizeof => {
-                let ty = self.consume_type()?;
-                let sizeof = ExpressionKind::SizeOf(ty.node);
+                let parenthesized = self.match_token(TokenType::LeftParen)?;
+
+                let (kind, end) = if self.peek_starts_type() {
+                    let ty = self.consume_type()?;
+                    (ExpressionKind::SizeOf(ty.node), ty.span.end)
+                } else {
+                    let operand = if parenthesized {
+                        self.expression(false)?
+                    } else {
+                        self.parse_expression(Precedence::Unary, no_struct)?
+                    };
+
+                    let end = operand.span.end;
+                    (ExpressionKind::SizeOfValue(Box::new(operand)), end)
+                };
 
-                Ok(Spanned::new_from_span(ty.span, Expression::new(sizeof)))
+                let end = if parenthesized {
+                    self.consume(TokenType::RightParen)?.span.end
+                } else {
+                    end
+                };
+
+                Ok(Spanned::new(token.span.start, end, Expression::new(kind)))
             }
 
+            // `new` binds like the other prefix operators. What follows it is always a value, so a struct initialization
+            // right after it cannot be the body of an `if` or a `while`, and is allowed even in their conditions:
+            // `while new Node { next: null } != null {}`.
             TokenType::New => {
-                let expression = self.expression(no_struct)?;
-                let new = ExpressionKind::New(Box::new(Spanned::new_from_span(
-                    expression.span,
-                    expression.node,
-                )));
+                let expression = self.nested(|parser| {
+                    let operand = parser.advance()?;
+                    let operand = parser.prefix(&operand, false)?;
 
-                Ok(Spanned::new_from_span(
-                    expression.span,
-                    Expression::new(new),
+                    parser.continue_expression(operand, Precedence::Unary, no_struct)
+                })?;
+
+                Ok(Spanned::new(
+                    token.span.start,
+                    expression.span.end,
+                    Expression::new(ExpressionKind::New(Box::new(expression))),
                 ))
             }
 
-            TokenType::LeftParen => {
-                let mut expression = self.expression(false)?;
+            TokenType::LeftBracket => {
+                let mut elements = vec![];
 
-                self.consume(TokenType::RightParen)?;
-                expression.span.start -= 1;
-                expression.span.end += 1;
+                while !self.at_end() && !self.peek_equals(&TokenType::RightBracket) {
+                    elements.push(self.expression(false)?);
 
-                Ok(expression)
-            }
+                    if !self.peek_equals(&TokenType::RightBracket) {
+                        self.consume(TokenType::Comma)?;
+                    }
+                }
 
-            TokenType::Minus => {
-                let next = self.parse_expression(Precedence::Unary, no_struct)?;
+                let bracket = self.consume(TokenType::RightBracket)?;
 
                 Ok(Spanned::new(
                     token.span.start,
-                    next.span.end,
-                    Expression::new(ExpressionKind::Negate(token.clone(), Box::new(next))),
+                    bracket.span.end,
+                    Expression::new(ExpressionKind::ArrayLiteral(elements)),
                 ))
             }
 
-            TokenType::Ampersand => {
-                let next = self.parse_expression(Precedence::Unary, no_struct)?;
+            // A comma after the first expression makes it a tuple instead of a parenthesized expression.
+            TokenType::LeftParen => {
+                let expression = self.expression(false)?;
 
-                Ok(Spanned::new(
-                    token.span.start,
-                    next.span.end,
-                    Expression::new(ExpressionKind::Reference(token.clone(), Box::new(next))),
-                ))
-            }
+                if self.peek_equals(&TokenType::Comma) {
+                    let mut elements = vec![expression];
+
+                    while self.match_token(TokenType::Comma)? {
+                        if self.peek_equals(&TokenType::RightParen) {
+                            break;
+                        }
+
+                        elements.push(self.expression(false)?);
+                    }
+
+                    let paren = self.consume(TokenType::RightParen)?;
+                    self.require_feature(Feature::Tuples, Span::new(token.span.start, paren.span.end));
+
+                    return Ok(Spanned::new(
+                        token.span.start,
+                        paren.span.end,
+                        Expression::new(ExpressionKind::Tuple(elements)),
+                    ));
+                }
 
-            TokenType::Star => {
-                let next = self.parse_expression(Precedence::Unary, no_struct)?;
+                let paren = self.consume(TokenType::RightParen)?;
 
                 Ok(Spanned::new(
                     token.span.start,
-                    next.span.end,
-                    Expression::new(ExpressionKind::Dereference(token.clone(), Box::new(next))),
+                    paren.span.end,
+                    Expression::new(ExpressionKind::Grouping(Box::new(expression))),
                 ))
             }
 
-            TokenType::Bang => {
-                let next = self.parse_expression(Precedence::Unary, no_struct)?;
+            // In a condition, the brace starts the body instead, like it does after an identifier.
+            TokenType::LeftBrace if !no_struct => {
+                let (body, value) = self.block_contents(token.clone(), true)?;
 
-                Ok(Spanned::new(
-                    token.span.start,
-                    next.span.end,
-                    Expression::new(ExpressionKind::BoolNegate(token.clone(), Box::new(next))),
+                Ok(Spanned::new_from_span(
+                    body.1,
+                    Expression::new(ExpressionKind::Block {
+                        body,
+                        value: value.map(Box::new),
+                    }),
                 ))
             }
 
@@ -869,6 +1742,37 @@ where
         }
     }
 
+    fn unary(
+        &mut self,
+        token: &Spanned<TokenType<'a>>,
+        precedence: Precedence,
+        no_struct: bool,
+    ) -> ExpressionResult<'a> {
+        let next = self.parse_expression(precedence, no_struct)?;
+        let end = next.span.end;
+
+        let operator = token.clone();
+        let next = Box::new(next);
+
+        let kind = match token.node {
+            TokenType::Minus => ExpressionKind::Negate(operator, next),
+            TokenType::Ampersand => ExpressionKind::Reference(operator, next),
+            TokenType::Star => ExpressionKind::Dereference(operator, next),
+            TokenType::Bang => ExpressionKind::BoolNegate(operator, next),
+            TokenType::Tilde => ExpressionKind::BitNot(operator, next),
+            TokenType::PlusPlus | TokenType::MinusMinus => self.increment(operator, next, false),
+
+            _ => {
+                return Err(Spanned::new_from_span(
+                    token.span,
+                    ParseError::InternalError("token has a prefix precedence, but is not a prefix operator"),
+                ))
+            }
+        };
+
+        Ok(Spanned::new(token.span.start, end, Expression::new(kind)))
+    }
+
     fn infix(
         &mut self,
         token: &Spanned<TokenType<'a>>,
@@ -910,7 +1814,7 @@ where
                     _ => ExpressionKind::Binary(
                         Box::new(left),
                         token.clone(),
-                        Box::new(Spanned::new_from_span(right.span, right.node)),
+                        Box::new(right),
                     ),
                 };
 
@@ -932,6 +1836,13 @@ where
                 ))
             }
 
+            TokenType::PlusPlus | TokenType::MinusMinus => {
+                let span = Span::new(left.span.start, token.span.end);
+                let increment = self.increment(token.clone(), Box::new(left), true);
+
+                Ok(Spanned::new_from_span(span, Expression::new(increment)))
+            }
+
             TokenType::Dot => {
                 let identifier = self.consume_identifier()?;
                 Ok(Spanned::new(
@@ -944,6 +1855,20 @@ where
                 ))
             }
 
+            TokenType::LeftBracket => {
+                let index = self.expression(false)?;
+                let end = self.consume(TokenType::RightBracket)?.span.end;
+
+                Ok(Spanned::new(
+                    left.span.start,
+                    end,
+                    Expression::new(ExpressionKind::Index {
+                        array: Box::new(left),
+                        index: Box::new(index),
+                    }),
+                ))
+            }
+
             TokenType::LeftBrace => {
                 let initializer_list = self.initializer_list()?;
                 let brace = self.consume(TokenType::RightBrace)?;
@@ -979,6 +1904,28 @@ where
         }
     }
 
+    // Like the left side of an assignment, the operand has to be something that can be assigned to, which
+    // an increment is not, so `i++++` is reported as well.
+    fn increment(
+        &mut self,
+        operator: Spanned<TokenType<'a>>,
+        operand: Box<Spanned<Expression<'a>>>,
+        is_postfix: bool,
+    ) -> ExpressionKind<'a> {
+        if operand.node.is_r_value() {
+            self.recovered.push(Spanned::new_from_span(
+                operand.span,
+                ParseError::InvalidIncrementTarget(operator.node.clone()),
+            ));
+        }
+
+        ExpressionKind::Increment {
+            operator,
+            operand,
+            is_postfix,
+        }
+    }
+
     fn get_info_about_callee(
         &self,
         expression: Spanned<Expression<'a>>,
@@ -987,10 +1934,7 @@ where
             if let ExpressionKind::Identifier(module) = left.node.kind() {
                 return (
                     module,
-                    Spanned::new_from_span(
-                        identifier.span,
-                        Expression::new(ExpressionKind::Identifier(identifier.node)),
-                    ),
+                    identifier.map(|name| Expression::new(ExpressionKind::Identifier(name))),
                 );
             }
         }
@@ -1002,11 +1946,23 @@ where
         let length = self.source.code.len();
         let span = Span::new(length, length);
 
-        self.lexer_error(span, "unexpected eof")
+        self.lexer_error(span, LexingErrorKind::UnexpectedEof)
     }
 
+    // The end of file token is never consumed, so that everything trying to read past the end keeps seeing it.
     fn advance(&mut self) -> Scanned<'a> {
-        self.scanner.next().unwrap_or_else(|| self.eof())
+        if let Some(Ok(eof @ Spanned { node: TokenType::Eof, .. })) = self.scanner.peek() {
+            return Ok(eof.clone());
+        }
+
+        let next = self.scanner.next().unwrap_or_else(|| self.eof());
+        if let Ok(token) = &next {
+            // Spans end at the first byte of their last character, which may take more than one byte.
+            let last = self.source.code.get(token.span.end..).and_then(|rest| rest.chars().next());
+            self.previous_end = token.span.end + last.map_or(1, char::len_utf8);
+        }
+
+        next
     }
 
     fn match_token(&mut self, expected: TokenType<'a>) -> ParseResult<'a, bool> {
@@ -1037,39 +1993,135 @@ where
                     return self.consume_error(&token, expected.to_string());
                 }
             } else {
-                return peek.clone();
+                let error = peek.clone().unwrap_err();
+                return self.skip_error(error);
             }
         }
 
         self.eof()
     }
 
-    fn lexer_error(&mut self, span: Span, cause: &'a str) -> Scanned<'a> {
+    // Lexing errors that were only peeked at are consumed when they are returned, so that recovery goes on right after
+    // them instead of running into the same error again.
+    fn skip_error<R>(&mut self, error: Spanned<ParseError<'a>>) -> ParseResult<'a, R> {
+        self.scanner.next();
+        Err(error)
+    }
+
+    // Run `parse` one level deeper, failing at the next token if that is too deep already.
+    fn nested<R, F>(&mut self, parse: F) -> ParseResult<'a, R>
+    where
+        F: FnOnce(&mut Self) -> ParseResult<'a, R>,
+    {
+        self.chain(|parser| {
+            parser.descend()?;
+            parse(parser)
+        })
+    }
+
+    // Run `parse`, which may go deeper several times, and come back to the current depth once it is done.
+    fn chain<R, F>(&mut self, parse: F) -> ParseResult<'a, R>
+    where
+        F: FnOnce(&mut Self) -> ParseResult<'a, R>,
+    {
+        let depth = self.depth;
+        let result = parse(self);
+        self.depth = depth;
+
+        result
+    }
+
+    // Go one level deeper, failing at the next token if that is too deep already.
+    fn descend(&mut self) -> ParseResult<'a, ()> {
+        if self.depth >= self.options.max_depth {
+            let span = match self.scanner.peek() {
+                Some(Ok(token)) => token.span,
+                _ => Span::new(self.previous_end, self.previous_end),
+            };
+
+            return Err(Spanned::new_from_span(
+                span,
+                ParseError::NestingTooDeep(self.options.max_depth),
+            ));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    // A missing semicolon in front of something that clearly starts the next statement is reported, but parsing goes
+    // on as if it was there, instead of throwing away the statement and everything up to the next semicolon.
+    fn consume_semicolon(&mut self) -> ParseResult<'a, ()> {
+        match self.consume(TokenType::Semicolon) {
+            Ok(_) => Ok(()),
+            Err(error) if self.peek_starts_statement() => {
+                self.recovered.push(error);
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn peek_starts_statement(&mut self) -> bool {
+        matches!(self.scanner.peek(), Some(Ok(Spanned { node, .. })) if node.starts_statement())
+    }
+
+    fn peek_starts_type(&mut self) -> bool {
+        matches!(
+            self.scanner.peek(),
+            Some(Ok(Spanned {
+                node: TokenType::TypeIdentifier(_)
+                    | TokenType::Star
+                    | TokenType::Ampersand
+                    | TokenType::LeftBracket
+                    | TokenType::Question
+                    | TokenType::Fn,
+                ..
+            }))
+        )
+    }
+
+    // Uses of a disabled feature are parsed all the same, they are only reported.
+    fn require_feature(&mut self, feature: Feature, span: Span) {
+        if !self.options.is_enabled(feature) {
+            self.recovered
+                .push(Spanned::new_from_span(span, ParseError::FeatureNotEnabled(feature)));
+        }
+    }
+
+    fn take_recovered(&mut self) -> Vec<Spanned<ParseError<'a>>> {
+        std::mem::take(&mut self.recovered)
+    }
+
+    fn lexer_error(&mut self, span: Span, kind: LexingErrorKind) -> Scanned<'a> {
         self.error_count += 1;
 
         Err(Spanned {
             span,
-            node: ParseError::LexingError(LexingError::with_cause(cause)),
+            node: ParseError::LexingError(LexingError::new(kind)),
         })
     }
 
     fn prefix_error(&mut self, token: &Spanned<TokenType<'a>>) -> ExpressionResult<'a> {
         self.error_count += 1;
 
-        let s = format!("invalid token in prefix expression '{}'", token.node);
+        // `finally` is kept for a `try` construct later on, so using it is not a typo of some other token.
+        if token.node == TokenType::Finally {
+            return Err(Spanned::new_from_span(token.span, ParseError::ReservedKeyword(token.node.clone())));
+        }
+
         Err(Spanned {
             span: token.span,
-            node: ParseError::PrefixError(s),
+            node: ParseError::PrefixError(token.node.clone()),
         })
     }
 
     fn infix_error(&mut self, token: &Spanned<TokenType<'a>>) -> ExpressionResult<'a> {
         self.error_count += 1;
 
-        let s = format!("invalid token in infix expression '{}'", token.node);
         Err(Spanned {
             span: token.span,
-            node: ParseError::InfixError(s),
+            node: ParseError::InfixError(token.node.clone()),
         })
     }
 
@@ -1081,37 +2133,43 @@ where
             node: ParseError::ConsumeError {
                 actual: actual.node.clone(),
                 expected,
+                position: self.previous_end,
             },
         })
     }
 
+    // Skip to the start of the next statement, which is either right after a semicolon or at a token only a statement
+    // starts with. A lexing error stops the skipping as well, so that it gets reported on its own.
     fn sync(&mut self) {
-        let mut previous = self.advance();
-
         while let Some(Ok(peek)) = self.scanner.peek() {
-            if let Ok(Spanned {
-                node: TokenType::Semicolon,
-                ..
-            }) = previous
-            {
-                break;
-            }
-
             match peek.node {
                 TokenType::Type
                 | TokenType::Fn
                 | TokenType::If
                 | TokenType::Let
-                | TokenType::Return => return,
+                | TokenType::Return
+                | TokenType::Eof => return,
 
                 _ => {}
             }
 
-            previous = self.advance();
+            if let Ok(Spanned {
+                node: TokenType::Semicolon,
+                ..
+            }) = self.advance()
+            {
+                return;
+            }
         }
     }
 
     fn at_end(&mut self) -> bool {
-        self.scanner.peek().is_none()
+        matches!(
+            self.scanner.peek(),
+            None | Some(Ok(Spanned {
+                node: TokenType::Eof,
+                ..
+            }))
+        )
     }
 }
@@ -1,4 +1,4 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -27,6 +27,28 @@ impl<T> Spanned<T> {
     pub fn new_from_span(span: Span, node: T) -> Self {
         Self { span, node }
     }
+
+    // Transform the node while keeping its span.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned {
+            span: self.span,
+            node: f(self.node),
+        }
+    }
+
+    pub fn as_ref(&self) -> Spanned<&T> {
+        Spanned {
+            span: self.span,
+            node: &self.node,
+        }
+    }
+
+    pub fn as_mut(&mut self) -> Spanned<&mut T> {
+        Spanned {
+            span: self.span,
+            node: &mut self.node,
+        }
+    }
 }
 
 impl<T: Clone> Clone for Spanned<T> {
@@ -0,0 +1,274 @@
+/*
+ * The call graph of a program, which tells the functions that can ever run apart from those that never do.
+ *
+ * Every function and method is a node, and every function named in the body of another one is a callee of it. Naming
+ * a function without calling it still counts, since the value may be called later on. `a.get()` is parsed as a call of
+ * `get` from the module `a`, but `a` may just as well be a variable, so it counts as a call of the method `get` of the
+ * struct `a` if there is one, and of every method named `get` otherwise. The graph is only ever too large this way, so
+ * nothing that can run is considered unused.
+ *
+ * `main` and the public functions are the roots, everything they do not reach can be left out by the backends.
+ *
+ * Newton (C) 2023
+ */
+
+use super::typecheck::FunctionKey;
+use crate::ast::ast::*;
+use crate::parser::span::*;
+use crate::types::types::*;
+
+#[derive(Debug)]
+struct Node<'a> {
+    // Span of the function's name.
+    span: Span,
+    is_external: bool,
+    callees: Vec<FunctionKey<'a>>,
+}
+
+#[derive(Debug, Default)]
+pub struct CallGraph<'a> {
+    nodes: std::collections::HashMap<FunctionKey<'a>, Node<'a>>,
+    reachable: std::collections::HashSet<FunctionKey<'a>>,
+}
+
+impl<'a> CallGraph<'a> {
+    // The graph of `program`, the code of the module `module`. Calls into other modules are not part of it.
+    pub fn new(module: &str, program: &Program<'a>) -> Self {
+        let mut functions = vec![];
+        for top_level in &program.0 {
+            collect_functions(top_level, None, &mut functions);
+        }
+
+        let keys = functions
+            .iter()
+            .map(|(key, ..)| *key)
+            .collect::<std::collections::HashSet<_>>();
+
+        let mut graph = Self::default();
+        let mut roots = vec![];
+
+        for (key, top_level) in functions {
+            let TopLevel::FunctionDeclaration {
+                name,
+                body,
+                is_external,
+                is_public,
+                ..
+            } = top_level
+            else {
+                continue;
+            };
+
+            if *is_public || key == (None, "main") {
+                roots.push(key);
+            }
+
+            let mut callees = Callees {
+                module,
+                functions: &keys,
+                found: vec![],
+            };
+            callees.block(body);
+
+            let mut callees = callees.found;
+            callees.sort();
+            callees.dedup();
+
+            let node = Node {
+                span: name.span,
+                is_external: *is_external,
+                callees,
+            };
+            graph.nodes.insert(key, node);
+        }
+
+        while let Some(key) = roots.pop() {
+            if graph.reachable.insert(key) {
+                roots.extend(graph.callees(key.0, key.1));
+            }
+        }
+
+        graph
+    }
+
+    // Every function the function or method named a single time or more, in no particular order.
+    pub fn callees(&self, owner: Option<&'a str>, function: &'a str) -> &[FunctionKey<'a>] {
+        self.nodes
+            .get(&(owner, function))
+            .map_or(&[], |node| node.callees.as_slice())
+    }
+
+    // Whether the function can run at all, which is what backends check to skip emitting it.
+    pub fn is_reachable(&self, owner: Option<&'a str>, function: &'a str) -> bool {
+        self.reachable.contains(&(owner, function))
+    }
+
+    // The functions with a body that are not reachable, along with the spans of their names, in source order.
+    pub fn unused_functions(&self) -> Vec<(FunctionKey<'a>, Span)> {
+        let mut unused = self
+            .nodes
+            .iter()
+            .filter(|(key, node)| !node.is_external && !self.reachable.contains(*key))
+            .map(|(key, node)| (*key, node.span))
+            .collect::<Vec<_>>();
+
+        unused.sort_by_key(|(_, span)| span.start);
+        unused
+    }
+}
+
+fn collect_functions<'a, 'b>(
+    top_level: &'b TopLevel<'a>,
+    owner: Option<&'a str>,
+    functions: &mut Vec<(FunctionKey<'a>, &'b TopLevel<'a>)>,
+) {
+    match top_level {
+        TopLevel::FunctionDeclaration { name, .. } => {
+            functions.push(((owner, name.node), top_level))
+        }
+
+        TopLevel::TypeDeclaration {
+            ty: TypeDeclaration::StructDefinition { name, methods, .. },
+            ..
+        } => {
+            for method in methods {
+                collect_functions(method, Some(name.node), functions);
+            }
+        }
+
+        _ => {}
+    }
+}
+
+struct Callees<'a, 'b> {
+    module: &'b str,
+    functions: &'b std::collections::HashSet<FunctionKey<'a>>,
+    found: Vec<FunctionKey<'a>>,
+}
+
+impl<'a, 'b> Callees<'a, 'b> {
+    fn block(&mut self, block: &Block<'a>) {
+        for statement in &block.0 {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement<'a>) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => self.expression(&declaration.value),
+
+            Statement::ExpressionStatement(expression)
+            | Statement::ReturnStatement(_, Some(expression)) => self.expression(expression),
+
+            Statement::DeleteStatement(expression) => self.expression(expression),
+
+            Statement::IfStatement(statement) => {
+                self.expression(&statement.condition);
+                self.block(&statement.then_block);
+
+                match statement.else_branch.as_deref() {
+                    Some(Else::IfStatement(statement)) => self.statement(statement),
+                    Some(Else::Block(block)) => self.block(block),
+                    None => {}
+                }
+            }
+
+            Statement::WhileStatement(statement) => {
+                self.expression(&statement.condition);
+                self.block(&statement.body);
+            }
+
+            Statement::BlockStatement(statement) => self.block(&statement.body),
+
+            // Only the enclosing function is a node of the graph, so it is the one calling whatever its nested
+            // functions call.
+            Statement::FunctionDeclaration(function) => {
+                if let TopLevel::FunctionDeclaration { body, .. } = function.as_ref() {
+                    self.block(body);
+                }
+            }
+
+            Statement::MatchStatement(statement) => {
+                self.expression(&statement.scrutinee);
+
+                for (pattern, block) in &statement.arms {
+                    self.expression(pattern);
+                    self.block(block);
+                }
+
+                if let Some(block) = &statement.default {
+                    self.block(block);
+                }
+            }
+
+            Statement::ReturnStatement(_, None)
+            | Statement::BreakStatement(_)
+            | Statement::ContinueStatement(_) => {}
+        }
+    }
+
+    fn expression(&mut self, expression: &Spanned<Expression<'a>>) {
+        match expression.node.kind() {
+            ExpressionKind::Error(_) => return,
+
+            ExpressionKind::Identifier(name) => self.function(name),
+
+            // The callee of a call by name is not a sub-expression.
+            ExpressionKind::Call { module, callee, .. } => {
+                if let ExpressionKind::Identifier(name) = callee.node.kind() {
+                    if *module == self.module {
+                        self.function(name);
+                    } else {
+                        self.methods(Some(module), name);
+                    }
+                }
+            }
+
+            // The statements of the block are not sub-expressions, but may call functions all the same.
+            ExpressionKind::Block { body, .. } => self.block(body),
+
+            // `pair.get` without a call, with the type of `pair` known from the resolver.
+            ExpressionKind::Access { left, identifier } => {
+                let owner = left.node.ty().as_ref().and_then(struct_name);
+                self.methods(owner, identifier.node);
+            }
+
+            _ => {}
+        }
+
+        for sub_expression in expression.node.sub_expressions() {
+            self.expression(sub_expression);
+        }
+    }
+
+    fn function(&mut self, name: &'a str) {
+        if self.functions.contains(&(None, name)) {
+            self.found.push((None, name));
+        }
+    }
+
+    // The methods named `name` of the struct `owner`, or of every struct if `owner` is not known to have methods.
+    fn methods(&mut self, owner: Option<&'a str>, name: &'a str) {
+        let owner = owner.filter(|owner| self.functions.iter().any(|(o, _)| *o == Some(*owner)));
+
+        let methods = self.functions.iter().filter(|(method_owner, method)| {
+            *method == name && method_owner.is_some() && (owner.is_none() || *method_owner == owner)
+        });
+        self.found.extend(methods);
+    }
+}
+
+// The struct methods are looked up in for a value of type `ty`, which can also be a pointer to the struct.
+fn struct_name<'a>(ty: &Type<'a>) -> Option<&'a str> {
+    match ty {
+        Type::Simple(Simple::UserDefinedType(identifier)) => Some(identifier.clone().name()),
+        Type::Complex(Complex::Pointer(pointer)) if pointer.size() == 1 => {
+            struct_name(pointer.base_type())
+        }
+        Type::Complex(Complex::Ref(reference)) if reference.size() == 1 => {
+            struct_name(reference.base_type())
+        }
+        Type::Complex(Complex::Generic(generic)) => struct_name(generic.base_type()),
+        _ => None,
+    }
+}
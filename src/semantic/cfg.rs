@@ -0,0 +1,39 @@
+/*
+ * Conditional compilation, which leaves out the declarations that are not meant for what is being compiled.
+ *
+ * A top level declaration marked with `@[cfg(name)]` is only kept if `name` is active, like the name of the target the
+ * program is compiled for. Methods can be marked the same way. Everything else is dropped right after parsing, before
+ * any definition is collected, so a function can be declared once for every target, with the same name each time.
+ * Declarations without a `cfg` are always kept.
+ *
+ * Newton (C) 2023
+ */
+
+use crate::ast::ast::*;
+use crate::parser::parser::CFG_ATTRIBUTE;
+
+// Whether every `cfg` attribute of a declaration checks for an active name.
+pub fn is_active(attributes: &[Attribute], active: &std::collections::HashSet<String>) -> bool {
+    attributes
+        .iter()
+        .filter(|attribute| attribute.name.node == CFG_ATTRIBUTE)
+        .flat_map(|attribute| &attribute.arguments)
+        .all(|name| active.contains(name.node))
+}
+
+// Drop every declaration of `program` and every method of its structs that is not active.
+pub fn remove_inactive(program: &mut Program, active: &std::collections::HashSet<String>) {
+    program
+        .0
+        .retain(|top_level| is_active(top_level.attributes(), active));
+
+    for top_level in &mut program.0 {
+        if let TopLevel::TypeDeclaration {
+            ty: TypeDeclaration::StructDefinition { methods, .. },
+            ..
+        } = top_level
+        {
+            methods.retain(|method| is_active(method.attributes(), active));
+        }
+    }
+}
@@ -0,0 +1,402 @@
+/*
+ * Newton's constant evaluator, along with the constant folding pass built on top of it.
+ *
+ * Only integer and boolean expressions are evaluated. Integers are computed as `i128` and, if the expression has been
+ * typed already, checked against the range of that type; an operation that would trap at runtime (division by zero,
+ * overflow) is never folded.
+ *
+ * Top-level constants are evaluated here as well, in the order their values depend on each other.
+ *
+ * Newton (C) 2023
+ */
+
+use crate::ast::ast::*;
+use crate::lexer::token::*;
+use crate::parser::span::*;
+use crate::types::types::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ConstValue {
+    Integer(i128),
+    Bool(bool),
+}
+
+impl std::fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Integer(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstEvalError {
+    NotConstant,
+    DivisionByZero,
+    Overflow,
+    MismatchedOperands,
+}
+
+impl std::fmt::Display for ConstEvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::NotConstant => write!(f, "expression is not constant"),
+            Self::DivisionByZero => write!(f, "attempted to divide by zero"),
+            Self::Overflow => write!(f, "constant expression overflows its type"),
+            Self::MismatchedOperands => write!(f, "mismatched operands in constant expression"),
+        }
+    }
+}
+
+pub type ConstResult = Result<ConstValue, ConstEvalError>;
+
+pub fn evaluate(expression: &Expression) -> ConstResult {
+    evaluate_in(expression, &|_| None)
+}
+
+// Like `evaluate`, with the values of the constants that identifiers can refer to.
+pub fn evaluate_in(
+    expression: &Expression,
+    constants: &dyn Fn(&str) -> Option<ConstValue>,
+) -> ConstResult {
+    let value = match expression.kind() {
+        ExpressionKind::DecLiteral(literal) => ConstValue::Integer(
+            literal
+                .parse::<i128>()
+                .map_err(|_| ConstEvalError::Overflow)?,
+        ),
+        ExpressionKind::BoolLiteral(value) => ConstValue::Bool(*value),
+        ExpressionKind::Constant(value) => *value,
+        ExpressionKind::Identifier(name) => constants(name).ok_or(ConstEvalError::NotConstant)?,
+        ExpressionKind::Grouping(expression) => evaluate_in(&expression.node, constants)?,
+
+        ExpressionKind::Negate(_, expression) => match evaluate_in(&expression.node, constants)? {
+            ConstValue::Integer(value) => {
+                ConstValue::Integer(value.checked_neg().ok_or(ConstEvalError::Overflow)?)
+            }
+            ConstValue::Bool(_) => return Err(ConstEvalError::MismatchedOperands),
+        },
+
+        // Integers are two's complement, so the bits above an unsigned type's width have to be cleared again.
+        ExpressionKind::BitNot(_, inner) => match evaluate_in(&inner.node, constants)? {
+            ConstValue::Integer(value) => match expression.clone_ty() {
+                Some(Type::Simple(Simple::Integer(integer))) if !{ integer }.signed() => {
+                    ConstValue::Integer(!value & ((1i128 << { integer }.size()) - 1))
+                }
+                _ => ConstValue::Integer(!value),
+            },
+            ConstValue::Bool(_) => return Err(ConstEvalError::MismatchedOperands),
+        },
+
+        ExpressionKind::BoolNegate(_, expression) => {
+            match evaluate_in(&expression.node, constants)? {
+                ConstValue::Bool(value) => ConstValue::Bool(!value),
+                ConstValue::Integer(_) => return Err(ConstEvalError::MismatchedOperands),
+            }
+        }
+
+        ExpressionKind::Binary(left, operator, right) => {
+            let (left, right) = match (
+                evaluate_in(&left.node, constants)?,
+                evaluate_in(&right.node, constants)?,
+            ) {
+                (ConstValue::Integer(left), ConstValue::Integer(right)) => (left, right),
+                _ => return Err(ConstEvalError::MismatchedOperands),
+            };
+
+            let value = match operator.node {
+                TokenType::Plus => left.checked_add(right),
+                TokenType::Minus => left.checked_sub(right),
+                TokenType::Star => left.checked_mul(right),
+                TokenType::Slash | TokenType::Percent if right == 0 => {
+                    return Err(ConstEvalError::DivisionByZero)
+                }
+                TokenType::Slash => left.checked_div(right),
+                TokenType::Percent => left.checked_rem(right),
+
+                _ => return Err(ConstEvalError::NotConstant),
+            };
+
+            ConstValue::Integer(value.ok_or(ConstEvalError::Overflow)?)
+        }
+
+        ExpressionKind::BoolBinary(left, operator, right) => {
+            match (
+                evaluate_in(&left.node, constants)?,
+                evaluate_in(&right.node, constants)?,
+            ) {
+                (ConstValue::Integer(left), ConstValue::Integer(right)) => {
+                    ConstValue::Bool(match operator.node {
+                        TokenType::EqualsEquals => left == right,
+                        TokenType::BangEquals => left != right,
+                        TokenType::Smaller => left < right,
+                        TokenType::SmallerEquals => left <= right,
+                        TokenType::Greater => left > right,
+                        TokenType::GreaterEquals => left >= right,
+
+                        _ => return Err(ConstEvalError::MismatchedOperands),
+                    })
+                }
+
+                (ConstValue::Bool(left), ConstValue::Bool(right)) => {
+                    ConstValue::Bool(match operator.node {
+                        TokenType::EqualsEquals => left == right,
+                        TokenType::BangEquals => left != right,
+                        TokenType::AmpersandAmpersand => left && right,
+                        TokenType::PipePipe => left || right,
+
+                        _ => return Err(ConstEvalError::MismatchedOperands),
+                    })
+                }
+
+                _ => return Err(ConstEvalError::MismatchedOperands),
+            }
+        }
+
+        _ => return Err(ConstEvalError::NotConstant),
+    };
+
+    if let (ConstValue::Integer(value), Some(Type::Simple(Simple::Integer(integer)))) =
+        (value, expression.clone_ty())
+    {
+        if !fits(value, integer) {
+            return Err(ConstEvalError::Overflow);
+        }
+    }
+
+    Ok(value)
+}
+
+// Whether `value` is in the range of the integer type `integer`.
+pub fn fits(value: i128, mut integer: Integer) -> bool {
+    let size = integer.size() as u32;
+
+    if integer.signed() {
+        let bound = 1i128 << (size - 1);
+        (-bound..bound).contains(&value)
+    } else {
+        (0..1i128 << size).contains(&value)
+    }
+}
+
+// Fold every constant subexpression of `expression`, leaving the rest of the tree as it was. A folded region keeps the
+// span of the expression it replaces, so diagnostics still point at what the user wrote.
+pub fn fold<'a>(expression: &Spanned<Expression<'a>>) -> Spanned<Expression<'a>> {
+    let Spanned { span, node } = expression;
+
+    let already_folded = matches!(
+        node.kind(),
+        ExpressionKind::DecLiteral(_)
+            | ExpressionKind::BoolLiteral(_)
+            | ExpressionKind::Constant(_)
+    );
+
+    if !already_folded {
+        if let Ok(value) = evaluate(node) {
+            return Spanned::new_from_span(*span, with_ty(node, ExpressionKind::Constant(value)));
+        }
+    }
+
+    let fold_boxed = |expression: &Spanned<Expression<'a>>| Box::new(fold(expression));
+
+    let kind = match node.kind() {
+        ExpressionKind::Reference(token, expression) => {
+            ExpressionKind::Reference(token.clone(), fold_boxed(expression))
+        }
+        ExpressionKind::Dereference(token, expression) => {
+            ExpressionKind::Dereference(token.clone(), fold_boxed(expression))
+        }
+        ExpressionKind::Negate(token, expression) => {
+            ExpressionKind::Negate(token.clone(), fold_boxed(expression))
+        }
+        ExpressionKind::BoolNegate(token, expression) => {
+            ExpressionKind::BoolNegate(token.clone(), fold_boxed(expression))
+        }
+        ExpressionKind::BitNot(token, expression) => {
+            ExpressionKind::BitNot(token.clone(), fold_boxed(expression))
+        }
+        ExpressionKind::Binary(left, operator, right) => {
+            ExpressionKind::Binary(fold_boxed(left), operator.clone(), fold_boxed(right))
+        }
+        ExpressionKind::BoolBinary(left, operator, right) => {
+            ExpressionKind::BoolBinary(fold_boxed(left), operator.clone(), fold_boxed(right))
+        }
+        ExpressionKind::Cast(expression, token, ty) => {
+            ExpressionKind::Cast(fold_boxed(expression), token.clone(), ty.clone())
+        }
+        ExpressionKind::New(expression) => ExpressionKind::New(fold_boxed(expression)),
+        ExpressionKind::Grouping(expression) => ExpressionKind::Grouping(fold_boxed(expression)),
+
+        ExpressionKind::Assignment { left, eq, value } => ExpressionKind::Assignment {
+            left: left.clone(),
+            eq: eq.clone(),
+            value: fold_boxed(value),
+        },
+
+        ExpressionKind::Call {
+            module,
+            callee,
+            arguments,
+        } => ExpressionKind::Call {
+            module,
+            callee: callee.clone(),
+            arguments: ArgumentList(arguments.0.iter().map(fold).collect()),
+        },
+
+        ExpressionKind::Access { left, identifier } => ExpressionKind::Access {
+            left: fold_boxed(left),
+            identifier: *identifier,
+        },
+
+        ExpressionKind::Index { array, index } => ExpressionKind::Index {
+            array: fold_boxed(array),
+            index: fold_boxed(index),
+        },
+
+        ExpressionKind::StructInitialization { identifier, fields } => {
+            ExpressionKind::StructInitialization {
+                identifier: identifier.clone(),
+                fields: InitializerList(
+                    fields
+                        .0
+                        .iter()
+                        .map(|(name, expression)| (*name, fold(expression)))
+                        .collect(),
+                ),
+            }
+        }
+
+        ExpressionKind::ArrayLiteral(elements) => {
+            ExpressionKind::ArrayLiteral(elements.iter().map(fold).collect())
+        }
+
+        ExpressionKind::Tuple(elements) => {
+            ExpressionKind::Tuple(elements.iter().map(fold).collect())
+        }
+
+        kind => kind.clone(),
+    };
+
+    Spanned::new_from_span(*span, with_ty(node, kind))
+}
+
+fn with_ty<'a>(original: &Expression<'a>, kind: ExpressionKind<'a>) -> Expression<'a> {
+    match original.clone_ty() {
+        Some(ty) => Expression::new_with_ty(ty, kind),
+        None => Expression::new(kind),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstantError<'a> {
+    Evaluation(ConstEvalError),
+    // The constants the value depends on in turn, starting and ending with the one the cycle is reported for.
+    Cycle(Vec<&'a str>),
+    // The value depends on a constant with an error of its own, which is only reported once, for that constant.
+    Dependency,
+}
+
+pub type ConstantValues<'a> =
+    std::collections::HashMap<&'a str, Result<ConstValue, ConstantError<'a>>>;
+
+// The values of every top-level constant of `program`. Constants can refer to each other regardless of the order they
+// are declared in, so each one is evaluated after the constants it depends on, and a cycle is an error.
+pub fn evaluate_constants<'a>(program: &Program<'a>) -> ConstantValues<'a> {
+    let mut evaluator = ConstantEvaluator {
+        declarations: std::collections::HashMap::new(),
+        values: std::collections::HashMap::new(),
+        stack: vec![],
+    };
+
+    let mut names = vec![];
+    for top_level in &program.0 {
+        if let TopLevel::Constant { declaration, .. } = top_level {
+            evaluator
+                .declarations
+                .insert(declaration.name.node, declaration);
+            names.push(declaration.name.node);
+        }
+    }
+
+    for name in names {
+        // Every result ends up in `values`, along with those of the constants evaluated along the way.
+        let _ = evaluator.value_of(name);
+    }
+
+    evaluator.values
+}
+
+struct ConstantEvaluator<'a, 'b> {
+    declarations: std::collections::HashMap<&'a str, &'b VariableDeclaration<'a>>,
+    values: ConstantValues<'a>,
+    // Constants being evaluated right now, the innermost one last.
+    stack: Vec<&'a str>,
+}
+
+impl<'a, 'b> ConstantEvaluator<'a, 'b> {
+    // Errors of other constants surface as `Dependency`, except for a cycle found just now, which is passed on until it
+    // reaches the constant it started at.
+    fn value_of(&mut self, name: &'a str) -> Result<ConstValue, ConstantError<'a>> {
+        if let Some(value) = self.values.get(name) {
+            return value.clone().map_err(|_| ConstantError::Dependency);
+        }
+
+        if let Some(position) = self.stack.iter().position(|constant| *constant == name) {
+            let mut cycle = self.stack[position..].to_vec();
+            cycle.push(name);
+            return Err(ConstantError::Cycle(cycle));
+        }
+
+        let declaration = self.declarations[name];
+        let mut dependencies = vec![];
+        self.dependencies(&declaration.value, &mut dependencies);
+
+        self.stack.push(name);
+        let mut constants = std::collections::HashMap::new();
+        let mut result = Ok(());
+
+        for dependency in dependencies {
+            match self.value_of(dependency) {
+                Ok(value) => {
+                    constants.insert(dependency, value);
+                }
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+        self.stack.pop();
+
+        let value = match result {
+            Ok(()) => evaluate_in(&declaration.value.node, &|name| {
+                constants.get(name).copied()
+            })
+            .map_err(ConstantError::Evaluation),
+
+            Err(ConstantError::Cycle(cycle)) if cycle[0] != name => {
+                self.values.insert(name, Err(ConstantError::Dependency));
+                return Err(ConstantError::Cycle(cycle));
+            }
+
+            Err(error) => Err(error),
+        };
+
+        self.values.insert(name, value.clone());
+        value.map_err(|_| ConstantError::Dependency)
+    }
+
+    // The constants `expression` refers to, in the order they first appear.
+    fn dependencies(&self, expression: &Spanned<Expression<'a>>, found: &mut Vec<&'a str>) {
+        if let ExpressionKind::Identifier(name) = expression.node.kind() {
+            if self.declarations.contains_key(name) && !found.contains(name) {
+                found.push(name);
+            }
+        }
+
+        for sub_expression in expression.node.sub_expressions() {
+            self.dependencies(sub_expression, found);
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::error_to_string;
 use crate::find_line_index;
 use crate::format_error;
+use crate::format_warning;
 use crate::lexer::token::*;
+use crate::semantic::consteval::ConstEvalError;
+use crate::types::layout::*;
 use crate::types::types::*;
 use crate::Source;
 use crate::Span;
@@ -57,8 +60,34 @@ impl<'a> std::fmt::Display for ResolverError<'a> {
                 format!("{}\n\nreason:\n{}", binoperr(binary_operator_error), reason)
             }
 
-            ResolveErrorType::NotDefined(DefinitionError { name }) => {
-                self.format_error(&format!("'{}' is not defined in the current scope", name))
+            ResolveErrorType::NotDefined(DefinitionError { name, other_uses }) => {
+                let error =
+                    self.format_error(&format!("'{}' is not defined in the current scope", name));
+
+                if other_uses.is_empty() {
+                    error
+                } else {
+                    let lines = other_uses
+                        .iter()
+                        .map(|span| find_line_index(self.source, span.start).0.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let (times, lines_word) = if other_uses.len() == 1 {
+                        ("time", "line")
+                    } else {
+                        ("times", "lines")
+                    };
+
+                    format!(
+                        "{}\nnote: '{}' is used {} more {}, on {} {}",
+                        error,
+                        name,
+                        other_uses.len(),
+                        times,
+                        lines_word,
+                        lines
+                    )
+                }
             }
 
             ResolveErrorType::IllegalOperation(ref error) => binoperr(error),
@@ -80,6 +109,19 @@ impl<'a> std::fmt::Display for ResolverError<'a> {
                 struct_name, field_name
             )),
 
+            ResolveErrorType::InvalidCast(CastError { from, to }) => self.format_error(&format!(
+                "cannot cast '{}' to '{}', enums can only be cast to and from integers",
+                from, to
+            )),
+
+            ResolveErrorType::NoSuchMethod(StructMethodError {
+                struct_name,
+                method_name,
+            }) => self.format_error(&format!(
+                "'{}' has no method named '{}'",
+                struct_name, method_name
+            )),
+
             ResolveErrorType::SelfImport(_) => {
                 self.format_error("cannot recursively import the current module")
             }
@@ -90,6 +132,16 @@ impl<'a> std::fmt::Display for ResolverError<'a> {
                 self.format_error(&format!("{} cannot be dereferenced", ty))
             }
 
+            ResolveErrorType::IndexNonArray(NonArrayError(ty)) => self.format_error(&format!(
+                "'{}' cannot be indexed, only arrays and pointers can",
+                ty
+            )),
+
+            ResolveErrorType::NonIntegerIndex(IndexTypeError(ty)) => self.format_error(&format!(
+                "indices must be integers, but this one is of type '{}'",
+                ty
+            )),
+
             ResolveErrorType::Delete(NonPointerError(ty)) => self.format_error(&format!(
                 "non-heap allocated pointer {} cannot be deleted",
                 ty
@@ -104,12 +156,340 @@ impl<'a> std::fmt::Display for ResolverError<'a> {
                 "tried to call variable of type '{}', but ufcs is not yet supported",
                 error.0
             )),
+
+            ResolveErrorType::AccessNonStruct(NonStructError(ty)) => {
+                self.format_error(&format!("'{}' has no fields", ty))
+            }
+
+            ResolveErrorType::ImplicitIndirection(IndirectionError(ty)) => self.format_error(&format!(
+                "fields cannot be accessed through '{}' implicitly, dereference it first",
+                ty
+            )),
+
+            ResolveErrorType::UnknownLabel(LabelError { label }) => {
+                self.format_error(&format!("use of undeclared label '{}'", label))
+            }
+
+            ResolveErrorType::OutsideOfLoop(LoopControlError { keyword }) => {
+                self.format_error(&format!("'{}' outside of a loop", keyword))
+            }
+
+            ResolveErrorType::ContinueNonLoop(LabelError { label }) => self.format_error(&format!(
+                "cannot continue '{}', since it labels a block and not a loop",
+                label
+            )),
+
+            ResolveErrorType::Layout(ref error) => self.format_error(&error.to_string()),
+
+            ResolveErrorType::UnknownModule(ModuleError { module }) => {
+                self.format_error(&format!("cannot find module '{}'", module))
+            }
+
+            ResolveErrorType::NotExported(ImportError { module, name }) => self.format_error(&format!(
+                "'{}' is not marked as 'pub' in module '{}', so it cannot be imported",
+                name, module
+            )),
+
+            ResolveErrorType::NotInModule(ImportError { module, name }) => {
+                self.format_error(&format!("module '{}' has no symbol named '{}'", module, name))
+            }
+
+            ResolveErrorType::ArgumentCount(ArgumentCountError {
+                function,
+                expected,
+                actual,
+                varargs,
+            }) => self.format_error(&format!(
+                "'{}' takes {}{} argument{}, but {} {} given",
+                function,
+                if *varargs { "at least " } else { "" },
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                actual,
+                if *actual == 1 { "was" } else { "were" }
+            )),
+
+            ResolveErrorType::TypeArgumentCount(TypeArgumentCountError {
+                name,
+                expected,
+                actual,
+            }) => self.format_error(&format!(
+                "expected {} type argument{} for '{}', found {}",
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                name,
+                actual
+            )),
+
+            ResolveErrorType::NoSuchVariant(VariantError { enum_name, variant }) => self.format_error(
+                &format!("'{}' has no variant named '{}'", enum_name, variant),
+            ),
+
+            ResolveErrorType::ChainedComparison(ChainedComparisonError { first, second }) => {
+                self.format_error(&format!(
+                    "comparisons cannot be chained, write 'a {} b && b {} c' instead of 'a {} b {} c'",
+                    first, second, first, second
+                ))
+            }
+
+            ResolveErrorType::LiteralOutOfRange(LiteralRangeError(ty)) => self.format_error(&format!(
+                "'{}' is out of the range of '{}'",
+                self.error_token(),
+                ty
+            )),
+
+            ResolveErrorType::NotConstant(ConstantValueError { name, error }) => self.format_error(&format!(
+                "the value of constant '{}' cannot be computed at compile time, {}",
+                name, error
+            )),
+
+            ResolveErrorType::ConstantCycle(ConstantCycleError { cycle }) => self.format_error(&format!(
+                "the value of constant '{}' depends on itself: {}",
+                cycle[0],
+                cycle.join(" -> ")
+            )),
+
+            ResolveErrorType::Capture(CaptureError { function, name }) => self.format_error(&format!(
+                "nested function '{}' cannot use '{}', which belongs to the function it is declared in",
+                function, name
+            )),
+
+            ResolveErrorType::UnknownStruct(UnknownStructError { name }) => {
+                self.format_error(&format!("there is no struct named '{}'", name))
+            }
+
+            ResolveErrorType::MissingFields(MissingFieldsError { struct_name, fields }) => {
+                let names = fields
+                    .iter()
+                    .map(|field| format!("'{}'", field))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let noun = if fields.len() == 1 { "field" } else { "fields" };
+
+                self.format_error(&format!("initialization of '{}' is missing the {} {}", struct_name, noun, names))
+            }
+
+            ResolveErrorType::DiscardRead(DiscardError) => {
+                self.format_error("'_' only discards values, it cannot be read")
+            }
+
+            ResolveErrorType::MissingReturn(MissingReturnError {
+                function,
+                return_type,
+            }) => self.format_error(&format!(
+                "'{}' returns '{}', but the end of its body can be reached without returning a value",
+                function, return_type
+            )),
         };
 
         write!(f, "{}", result)
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct ResolverWarning<'a> {
+    pub source: &'a Source,
+    pub warning: ResolveWarningType<'a>,
+    pub warning_span: Span,
+    pub expression_span: Span,
+}
+
+impl<'a> ResolverWarning<'a> {
+    fn format_warning(&self, message: &str) -> String {
+        format_warning(self.source, self.expression_span, self.warning_span, message)
+    }
+}
+
+impl<'a> std::fmt::Display for ResolverWarning<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let result = match &self.warning {
+            ResolveWarningType::UnusedResult(UnusedResultWarning {
+                function,
+                return_type,
+            }) => self.format_warning(&format!(
+                "unused result of '{}', which returns '{}'",
+                function, return_type
+            )),
+
+            ResolveWarningType::AssignmentInCondition => self.format_warning(
+                "assignment used as a condition, did you mean '=='? Wrap it in another pair of parentheses if the \
+                 assignment is intended",
+            ),
+
+            ResolveWarningType::NonExhaustiveMatch(NonExhaustiveMatchWarning {
+                enum_name,
+                missing,
+            }) => self.format_warning(&format!(
+                "match on '{}' does not handle {}; add the missing cases or a 'default'",
+                enum_name,
+                missing
+                    .iter()
+                    .map(|variant| format!("'{}'", variant))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+
+            ResolveWarningType::RedundantCast(RedundantCastWarning { ty }) => self.format_warning(
+                &format!("redundant cast, the expression already is of type '{}'", ty),
+            ),
+
+            ResolveWarningType::EmptyBody(EmptyBodyWarning { statement }) => {
+                self.format_warning(&format!("empty body of '{}' statement", statement))
+            }
+
+            ResolveWarningType::FieldShadowsMethod(FieldShadowsMethodWarning {
+                struct_name,
+                name,
+                method_span,
+            }) => format!(
+                "{}\nnote: the method '{}' is declared on line {}",
+                self.format_warning(&format!(
+                    "field '{}' of '{}' has the same name as a method, so '.{}' is ambiguous",
+                    name, struct_name, name
+                )),
+                name,
+                find_line_index(self.source, method_span.start).0
+            ),
+
+            ResolveWarningType::DeadBranch(DeadBranchWarning { branch, condition }) => self
+                .format_warning(&format!(
+                    "{} is never run, since the condition is always '{}'",
+                    branch, condition
+                )),
+
+            ResolveWarningType::InfiniteLoop => self.format_warning(
+                "this loop never ends, its condition is always 'true' and there is no 'break' or 'return' in it",
+            ),
+
+            ResolveWarningType::UnusedFunction(UnusedFunctionWarning { owner, function }) => {
+                match owner {
+                    Some(owner) => self.format_warning(&format!(
+                        "method '{}' of '{}' is never used",
+                        function, owner
+                    )),
+                    None => self.format_warning(&format!("function '{}' is never used", function)),
+                }
+            }
+
+            ResolveWarningType::StubFunction(StubFunctionWarning {
+                function,
+                return_type,
+            }) => self.format_warning(&format!(
+                "'{}' returns '{}', but there is no 'return' in it at all, it may still be a stub",
+                function, return_type
+            )),
+
+            ResolveWarningType::SignednessMismatch(SignednessWarning {
+                left_type,
+                right_type,
+                promoted,
+            }) => self.format_warning(&format!(
+                "'{}' and '{}' differ in signedness, the result is '{}', which cannot hold every value of both",
+                left_type, right_type, promoted
+            )),
+
+            ResolveWarningType::EnumCastOutOfRange(EnumCastWarning {
+                enum_name,
+                value,
+                variants,
+            }) => self.format_warning(&format!(
+                "no variant of '{}' has the value {}, only 0 to {} are valid",
+                enum_name,
+                value,
+                variants - 1
+            )),
+        };
+
+        write!(f, "{}", result)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResolveWarningType<'a> {
+    UnusedResult(UnusedResultWarning<'a>),
+    AssignmentInCondition,
+    EmptyBody(EmptyBodyWarning),
+    NonExhaustiveMatch(NonExhaustiveMatchWarning<'a>),
+    RedundantCast(RedundantCastWarning<'a>),
+    FieldShadowsMethod(FieldShadowsMethodWarning<'a>),
+    UnusedFunction(UnusedFunctionWarning<'a>),
+    DeadBranch(DeadBranchWarning),
+    // A `while` whose condition is constantly `true`, without a `break` or `return` in it.
+    InfiniteLoop,
+    StubFunction(StubFunctionWarning<'a>),
+    SignednessMismatch(SignednessWarning<'a>),
+    EnumCastOutOfRange(EnumCastWarning<'a>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnusedResultWarning<'a> {
+    pub function: &'a str,
+    pub return_type: Type<'a>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct EmptyBodyWarning {
+    // The keyword owning the body: `if`, `else` or `while`.
+    pub statement: &'static str,
+}
+
+// A branch that is never run because of a constant condition. The warning points at the branch.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeadBranchWarning {
+    // What is never run, like `the body of this 'if'`.
+    pub branch: &'static str,
+    pub condition: bool,
+}
+
+// `owner` is the struct of a method.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnusedFunctionWarning<'a> {
+    pub owner: Option<&'a str>,
+    pub function: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StubFunctionWarning<'a> {
+    pub function: &'a str,
+    pub return_type: Type<'a>,
+}
+
+// The operands of arithmetic on a signed and an unsigned integer, and the type they are promoted to.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SignednessWarning<'a> {
+    pub left_type: Type<'a>,
+    pub right_type: Type<'a>,
+    pub promoted: Type<'a>,
+}
+
+// The warning points at the field, the method is only noted.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FieldShadowsMethodWarning<'a> {
+    pub struct_name: &'a str,
+    pub name: &'a str,
+    pub method_span: Span,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct NonExhaustiveMatchWarning<'a> {
+    pub enum_name: &'a str,
+    // The variants without an arm, in declaration order.
+    pub missing: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RedundantCastWarning<'a> {
+    pub ty: Type<'a>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnumCastWarning<'a> {
+    pub enum_name: &'a str,
+    pub value: i128,
+    // How many variants the enum has, their values go from 0 to one less than that.
+    pub variants: usize,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ResolveErrorType<'a> {
     IllegalAssignment(Box<AssignmentError<'a>>),
@@ -117,12 +497,37 @@ pub enum ResolveErrorType<'a> {
     IllegalOperation(BinaryOperationError<'a>),
     IllegalType(IllegalTypeError<'a>),
     NoSuchField(StructFieldError<'a>),
+    NoSuchMethod(StructMethodError<'a>),
+    InvalidCast(CastError<'a>),
     SelfImport(SelfImportError),
     Inference(TypeInferenceError),
     Dereference(NonPointerError<'a>),
+    IndexNonArray(NonArrayError<'a>),
+    NonIntegerIndex(IndexTypeError<'a>),
     Delete(NonPointerError<'a>),
     NotArithmetic(ArithmeticError<'a>),
     CallNonFunction(NonFunctionError<'a>),
+    AccessNonStruct(NonStructError<'a>),
+    ImplicitIndirection(IndirectionError<'a>),
+    UnknownLabel(LabelError<'a>),
+    OutsideOfLoop(LoopControlError<'a>),
+    ContinueNonLoop(LabelError<'a>),
+    Layout(LayoutError<'a>),
+    UnknownModule(ModuleError<'a>),
+    NotExported(ImportError<'a>),
+    NotInModule(ImportError<'a>),
+    MissingReturn(MissingReturnError<'a>),
+    NoSuchVariant(VariantError<'a>),
+    ArgumentCount(ArgumentCountError<'a>),
+    TypeArgumentCount(TypeArgumentCountError<'a>),
+    ChainedComparison(ChainedComparisonError<'a>),
+    LiteralOutOfRange(LiteralRangeError<'a>),
+    DiscardRead(DiscardError),
+    NotConstant(ConstantValueError<'a>),
+    ConstantCycle(ConstantCycleError<'a>),
+    Capture(CaptureError<'a>),
+    UnknownStruct(UnknownStructError<'a>),
+    MissingFields(MissingFieldsError<'a>),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -135,6 +540,8 @@ pub struct AssignmentError<'a> {
 #[derive(Debug, PartialEq, Eq)]
 pub struct DefinitionError<'a> {
     pub name: &'a str,
+    // Every later use of the same name, which is reported along with the first one instead of on its own.
+    pub other_uses: Vec<Span>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -156,6 +563,19 @@ pub struct StructFieldError<'a> {
     pub field_name: &'a str,
 }
 
+// A cast an enum is involved in that is not from or to an integer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CastError<'a> {
+    pub from: Type<'a>,
+    pub to: Type<'a>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StructMethodError<'a> {
+    pub struct_name: &'a str,
+    pub method_name: &'a str,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct SelfImportError;
 
@@ -165,11 +585,121 @@ pub struct TypeInferenceError;
 #[derive(Debug, PartialEq, Eq)]
 pub struct NonPointerError<'a>(pub Type<'a>);
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct NonArrayError<'a>(pub Type<'a>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct IndexTypeError<'a>(pub Type<'a>);
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ArithmeticError<'a> {
-    ty: Type<'a>,
-    operator: TokenType<'a>,
+    pub ty: Type<'a>,
+    pub operator: TokenType<'a>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct NonFunctionError<'a>(pub Type<'a>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct NonStructError<'a>(pub Type<'a>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct IndirectionError<'a>(pub Type<'a>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LabelError<'a> {
+    pub label: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LoopControlError<'a> {
+    pub keyword: TokenType<'a>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ModuleError<'a> {
+    pub module: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportError<'a> {
+    pub module: &'a str,
+    pub name: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MissingReturnError<'a> {
+    pub function: &'a str,
+    pub return_type: Type<'a>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct VariantError<'a> {
+    pub enum_name: &'a str,
+    // The pattern as it was written.
+    pub variant: &'a str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArgumentCountError<'a> {
+    pub function: &'a str,
+    // Not counting varargs.
+    pub expected: usize,
+    pub actual: usize,
+    pub varargs: bool,
+}
+
+// A generic struct used with another number of type arguments than it has type parameters.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TypeArgumentCountError<'a> {
+    pub name: &'a str,
+    pub expected: usize,
+    pub actual: usize,
+}
+
+// The operators of `a < b < c`, in order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChainedComparisonError<'a> {
+    pub first: TokenType<'a>,
+    pub second: TokenType<'a>,
+}
+
+// The integer type a literal does not fit into.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LiteralRangeError<'a>(pub Type<'a>);
+
+// `_` used as a value.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiscardError;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConstantValueError<'a> {
+    pub name: &'a str,
+    pub error: ConstEvalError,
+}
+
+// The constants of the cycle in the order they refer to each other, the first one again at the end.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConstantCycleError<'a> {
+    pub cycle: Vec<&'a str>,
+}
+
+// A variable of an enclosing function used by a nested function, which cannot capture anything.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CaptureError<'a> {
+    pub function: &'a str,
+    pub name: &'a str,
+}
+
+// A struct initialization of a type that is not a struct, or does not exist at all.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownStructError<'a> {
+    pub name: &'a str,
+}
+
+// The fields a struct initialization leaves out, in declaration order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MissingFieldsError<'a> {
+    pub struct_name: &'a str,
+    pub fields: Vec<&'a str>,
+}
@@ -0,0 +1,181 @@
+/*
+ * Newton's control flow analysis, which finds out whether the end of a block can be reached.
+ *
+ * Statements after a `return`, `break` or `continue` are never reached. A loop whose condition is constantly `true`
+ * only ends through a `break`, so a function ending in `while true { ... }` without one does not need a trailing
+ * `return`. Other conditions are assumed to eventually be false.
+ *
+ * Newton (C) 2023
+ */
+
+use super::consteval::*;
+use crate::ast::ast::*;
+
+// How control leaves a statement.
+#[derive(Debug, Default)]
+struct Flow<'a> {
+    // Whether the statement can complete normally, continuing with the next one.
+    completes: bool,
+    // Targets of the `break`s that leave the statement: the name of a label, or `None` for the innermost loop.
+    breaks: Vec<Option<&'a str>>,
+}
+
+// Whether control can reach the end of `block`, e.g. to find functions that may end without returning a value.
+pub fn block_completes(block: &Block) -> bool {
+    analyze_block(block).completes
+}
+
+// Whether a loop can never be left: its condition is constantly `true`, and nothing in it breaks out or returns.
+pub fn loop_never_ends(statement: &WhileStatement) -> bool {
+    let flow = analyze_while(statement);
+
+    !flow.completes && flow.breaks.is_empty() && !block_returns(&statement.body)
+}
+
+fn analyze_block<'a>(block: &Block<'a>) -> Flow<'a> {
+    let mut flow = Flow {
+        completes: true,
+        breaks: vec![],
+    };
+
+    for statement in &block.0 {
+        // Everything after this is unreachable, so it can neither complete nor break.
+        if !flow.completes {
+            break;
+        }
+
+        let statement = analyze_statement(statement);
+        flow.completes = statement.completes;
+        flow.breaks.extend(statement.breaks);
+    }
+
+    flow
+}
+
+fn analyze_statement<'a>(statement: &Statement<'a>) -> Flow<'a> {
+    match statement {
+        Statement::ReturnStatement(..) | Statement::ContinueStatement(_) => Flow::default(),
+
+        Statement::BreakStatement(control) => Flow {
+            completes: false,
+            breaks: vec![control.label.map(|label| label.node)],
+        },
+
+        Statement::IfStatement(statement) => {
+            let then_flow = analyze_block(&statement.then_block);
+            let else_flow = match statement.else_branch.as_deref() {
+                Some(Else::IfStatement(statement)) => analyze_statement(statement),
+                Some(Else::Block(block)) => analyze_block(block),
+                None => Flow {
+                    completes: true,
+                    breaks: vec![],
+                },
+            };
+
+            Flow {
+                completes: then_flow.completes || else_flow.completes,
+                breaks: [then_flow.breaks, else_flow.breaks].concat(),
+            }
+        }
+
+        Statement::WhileStatement(statement) => analyze_while(statement),
+
+        // Unlabeled blocks are not break targets, their `break`s leave the enclosing loop.
+        Statement::BlockStatement(statement) => {
+            let body = analyze_block(&statement.body);
+
+            match statement.label {
+                Some(label) => {
+                    let (own, outer): (Vec<_>, Vec<_>) = body
+                        .breaks
+                        .into_iter()
+                        .partition(|target| *target == Some(label.node));
+
+                    Flow {
+                        completes: body.completes || !own.is_empty(),
+                        breaks: outer,
+                    }
+                }
+
+                None => body,
+            }
+        }
+
+        // Without a `default` no arm may match, unless the resolver found that the arms cover every variant of an enum.
+        Statement::MatchStatement(statement) => {
+            let arms = statement
+                .arms
+                .iter()
+                .map(|(_, block)| block)
+                .chain(statement.default.as_ref())
+                .map(analyze_block)
+                .collect::<Vec<_>>();
+
+            Flow {
+                completes: (statement.default.is_none() && !statement.exhaustive.get())
+                    || arms.iter().any(|arm| arm.completes),
+                breaks: arms.into_iter().flat_map(|arm| arm.breaks).collect(),
+            }
+        }
+
+        // The body of a nested function is analyzed when the resolver resolves that function.
+        Statement::VariableDeclaration(_)
+        | Statement::ExpressionStatement(_)
+        | Statement::DeleteStatement(_)
+        | Statement::FunctionDeclaration(_) => Flow {
+            completes: true,
+            breaks: vec![],
+        },
+    }
+}
+
+fn analyze_while<'a>(statement: &WhileStatement<'a>) -> Flow<'a> {
+    let label = statement.label.map(|label| label.node);
+    let body = analyze_block(&statement.body);
+    let (own, outer): (Vec<_>, Vec<_>) = body
+        .breaks
+        .into_iter()
+        .partition(|target| target.is_none() || *target == label);
+
+    let infinite = matches!(
+        evaluate(&statement.condition.node),
+        Ok(ConstValue::Bool(true))
+    );
+
+    Flow {
+        completes: !infinite || !own.is_empty(),
+        breaks: outer,
+    }
+}
+
+// Whether there is a `return` anywhere in `block`, reachable or not.
+pub fn block_returns(block: &Block) -> bool {
+    block.0.iter().any(statement_returns)
+}
+
+fn statement_returns(statement: &Statement) -> bool {
+    match statement {
+        Statement::ReturnStatement(..) => true,
+
+        Statement::IfStatement(statement) => {
+            block_returns(&statement.then_block)
+                || match statement.else_branch.as_deref() {
+                    Some(Else::IfStatement(statement)) => statement_returns(statement),
+                    Some(Else::Block(block)) => block_returns(block),
+                    None => false,
+                }
+        }
+
+        Statement::WhileStatement(statement) => block_returns(&statement.body),
+        Statement::BlockStatement(statement) => block_returns(&statement.body),
+
+        Statement::MatchStatement(statement) => statement
+            .arms
+            .iter()
+            .map(|(_, block)| block)
+            .chain(statement.default.as_ref())
+            .any(block_returns),
+
+        _ => false,
+    }
+}
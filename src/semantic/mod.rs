@@ -2,3 +2,7 @@ pub mod error;
 pub mod symtable;
 pub mod typecheck;
 pub mod modulemap;
+pub mod consteval;
+pub mod flow;
+pub mod callgraph;
+pub mod cfg;
@@ -1,10 +1,13 @@
 use crate::types::types::*;
-use crate::{FunctionDefinition, FunctionMap, UserTypeDefinition, UserTypeMap};
+use crate::{
+    FunctionDefinition, FunctionMap, GlobalDefinition, GlobalMap, UserTypeDefinition, UserTypeMap,
+};
 
 #[derive(Debug, Default)]
 struct Module<'a> {
     user_types: UserTypeMap<'a>,
     functions: FunctionMap<'a>,
+    globals: GlobalMap<'a>,
 }
 
 type ModuleName<'a> = &'a str;
@@ -19,6 +22,10 @@ impl<'a> ModuleMap<'a> {
         self.modules.insert(module, Module::default());
     }
 
+    pub fn module_defined(&self, module: ModuleName) -> bool {
+        self.modules.contains_key(module)
+    }
+
     pub fn define_function(
         &mut self,
         module: ModuleName<'a>,
@@ -45,6 +52,19 @@ impl<'a> ModuleMap<'a> {
             .insert(name, definition);
     }
 
+    pub fn define_global(
+        &mut self,
+        module: ModuleName<'a>,
+        name: &'a str,
+        definition: GlobalDefinition<'a>,
+    ) {
+        self.modules
+            .entry(module)
+            .or_default()
+            .globals
+            .insert(name, definition);
+    }
+
     fn and_then<'b, T, F>(&'b self, module: ModuleName, f: F) -> Option<T>
     where
         F: FnOnce(&'b Module<'a>) -> Option<T>,
@@ -68,10 +88,24 @@ impl<'a> ModuleMap<'a> {
         self.and_then(module, |m| m.functions.get(name))
     }
 
+    pub fn get_global(&self, module: ModuleName, name: &str) -> Option<&GlobalDefinition<'a>> {
+        self.and_then(module, |m| m.globals.get(name))
+    }
+
     pub fn get_user_type(&self, module: ModuleName, name: &str) -> Option<&UserTypeDefinition<'a>> {
         self.and_then(module, |m| m.user_types.get(name))
     }
 
+    pub fn get_user_type_mut(
+        &mut self,
+        module: ModuleName,
+        name: &str,
+    ) -> Option<&mut UserTypeDefinition<'a>> {
+        self.modules
+            .get_mut(module)
+            .and_then(|m| m.user_types.get_mut(name))
+    }
+
     pub fn iter_functions<'b>(
         &'b self,
     ) -> impl Iterator<Item = (ModuleName<'a>, &'b FunctionDefinition<'a>)> {
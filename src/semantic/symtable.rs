@@ -27,7 +27,8 @@ impl<'a> Symbol<'a> {
 
 #[derive(Debug)]
 pub struct SymbolTable<'a> {
-    scopes: Vec<std::collections::HashMap<&'a str, Spanned<Symbol<'a>>>>,
+    // A name bound without a symbol is a variable whose type could not be determined.
+    scopes: Vec<std::collections::HashMap<&'a str, Option<Spanned<Symbol<'a>>>>>,
 }
 
 impl<'a> SymbolTable<'a> {
@@ -63,7 +64,18 @@ impl<'a> SymbolTable<'a> {
 
         let symbol = Spanned::new_from_span(span, Symbol::new(name, ty, kind));
 
-        scope.insert(name, symbol);
+        scope.insert(name, Some(symbol));
+    }
+
+    // Bind a variable whose type could not be determined. It shadows other bindings like any variable does, but it has
+    // no symbol, so its uses can be left unreported.
+    pub fn bind_unresolved(&mut self, name: &'a str) {
+        self.scopes.last_mut().unwrap().insert(name, None);
+    }
+
+    // How many scopes are open on top of the global one.
+    pub fn depth(&self) -> usize {
+        self.scopes.len() - 1
     }
 
     fn is_in_global_scope(&self) -> bool {
@@ -72,13 +84,16 @@ impl<'a> SymbolTable<'a> {
 
     pub fn lookup(&self, name: &'a str) -> Option<&Spanned<Symbol<'a>>> {
         for scope in self.scopes.iter().rev() {
-            let symbol = scope.get(name);
-
-            if symbol.is_none() {
-                return symbol;
+            if let Some(symbol) = scope.get(name) {
+                return symbol.as_ref();
             }
         }
 
         None
     }
+
+    // Whether `name` is a variable, including one whose type could not be determined.
+    pub fn is_bound(&self, name: &'a str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains_key(name))
+    }
 }
@@ -1 +1,2407 @@
+/*
+ * Newton's resolver. It walks the program after parsing and checks everything the parser cannot know about by itself.
+ * Errors are collected along the way, so a single run reports as many of them as possible.
+ *
+ * Newton (C) 2023
+ */
 
+use super::callgraph::*;
+use super::consteval::*;
+use super::error::*;
+use super::flow::*;
+use super::modulemap::*;
+use super::symtable::*;
+use crate::ast::ast::*;
+use crate::lexer::token::*;
+use crate::parser::parser::{parse_alignment, ALIGN_ATTRIBUTE, PACKED_ATTRIBUTE};
+use crate::parser::span::*;
+use crate::types::layout::*;
+use crate::types::types::*;
+use crate::{FunctionDefinition, FunctionMap, GlobalDefinition, Source, UserTypeDefinition};
+
+// The name of variables and parameters that are never bound, so their values are thrown away.
+const DISCARD: &str = "_";
+
+// The first parameter of methods, the instance they are called on.
+const SELF: &str = "self";
+
+#[derive(Debug, Default, Clone)]
+pub struct ResolverOptions {
+    // Warn about every discarded non-`void` call result, not only those of functions marked with `@[must_use]`.
+    pub warn_unused_results: bool,
+
+    // Warn about functions and methods that neither `main` nor any public function ever calls.
+    pub warn_unused_functions: bool,
+
+    // Accept integers and pointers as conditions like C does, which are true unless they are zero or null.
+    pub truthy_conditions: bool,
+
+    // Target layout used to compute the offsets of struct fields.
+    pub data_layout: DataLayout,
+
+    // Stop once this many errors are reported. By default the resolver keeps going and reports every error it finds.
+    pub max_errors: Option<usize>,
+
+    // The names `@[cfg(name)]` checks for that are active, like the target being compiled for. The compiler drops every
+    // declaration that checks for another name before resolving, see `semantic/cfg.rs`.
+    pub cfg: std::collections::HashSet<String>,
+
+    // Warn about integer constants cast to an enum that are not the value of any of its variants, like `7 as Color` for
+    // an enum with three variants.
+    pub checked_enum_casts: bool,
+}
+
+// A statement `break` and `continue` can refer to. Unlabeled blocks are never targets, so they are not tracked.
+struct Label<'a> {
+    name: Option<&'a str>,
+    is_loop: bool,
+}
+
+// A function is identified by its name and, for methods, the name of the struct it belongs to.
+pub type FunctionKey<'a> = (Option<&'a str>, &'a str);
+
+pub struct Resolver<'a> {
+    source: &'a Source,
+    options: ResolverOptions,
+    module_map: ModuleMap<'a>,
+    symbol_table: SymbolTable<'a>,
+    labels: Vec<Label<'a>>,
+
+    // Functions declared inside the function being resolved, one map per open scope. A nested function is resolved
+    // with a symbol table of its own, while the tables of the functions it is declared in are kept in `enclosing`,
+    // innermost last, only to tell captured variables apart from undefined ones.
+    nested_functions: Vec<FunctionMap<'a>>,
+    enclosing: Vec<SymbolTable<'a>>,
+
+    // Every binding made inside a function, parameters included, in the order they appear. Scopes are gone once a
+    // function is resolved, so this is what backends use to allocate stack slots.
+    locals: std::collections::HashMap<FunctionKey<'a>, Vec<Spanned<Symbol<'a>>>>,
+    current_function: Option<FunctionKey<'a>>,
+    return_type: Option<Type<'a>>,
+
+    // Names that were found to be undefined in the function being resolved, along with the index of the error reporting
+    // them.
+    undefined: std::collections::HashMap<&'a str, Option<usize>>,
+
+    // Top-level constants whose type could not be determined. The error is reported for the constant itself, not for
+    // every use of it.
+    unresolved_constants: std::collections::HashSet<&'a str>,
+
+    // Symbols brought in with `from "module" import name;`, along with the module they come from.
+    imported: std::collections::HashMap<&'a str, &'a str>,
+
+    // Names the imports of this module use for modules that are declared under another name, like `../c` for `./c`.
+    module_aliases: std::collections::HashMap<&'a str, &'a str>,
+
+    // The values of the top-level constants of the module being resolved.
+    constants: ConstantValues<'a>,
+
+    errors: Vec<ResolverError<'a>>,
+    warnings: Vec<ResolverWarning<'a>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(source: &'a Source) -> Self {
+        Self::new_with_options(source, ResolverOptions::default())
+    }
+
+    pub fn new_with_options(source: &'a Source, options: ResolverOptions) -> Self {
+        Self {
+            source,
+            options,
+            module_map: ModuleMap::default(),
+            symbol_table: SymbolTable::new(),
+            labels: vec![],
+            nested_functions: vec![],
+            enclosing: vec![],
+            locals: std::collections::HashMap::new(),
+            current_function: None,
+            return_type: None,
+            undefined: std::collections::HashMap::new(),
+            unresolved_constants: std::collections::HashSet::new(),
+            imported: std::collections::HashMap::new(),
+            module_aliases: std::collections::HashMap::new(),
+            constants: std::collections::HashMap::new(),
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    pub fn resolve(&mut self, program: &Program<'a>) {
+        self.collect_definitions(self.source.name.as_str(), program);
+        self.constants = evaluate_constants(program);
+
+        for top_level in &program.0 {
+            if self.error_limit_reached() {
+                break;
+            }
+
+            self.resolve_top_level(top_level, None);
+        }
+
+        if self.options.warn_unused_functions {
+            self.check_unused_functions(program);
+        }
+    }
+
+    // Make the declarations of another module known, so that the program being resolved can import from it.
+    pub fn declare_module(&mut self, source: &'a Source, program: &Program<'a>) {
+        self.collect_definitions(source.name.as_str(), program);
+    }
+
+    // Make imports of `alias` refer to the module `module`, which is declared under that name. The driver uses this for
+    // files imported by different paths, which are still one module.
+    pub fn alias_module(&mut self, alias: &'a str, module: &'a str) {
+        self.module_aliases.insert(alias, module);
+    }
+
+    #[inline]
+    pub fn errors(&self) -> &[ResolverError<'a>] {
+        &self.errors
+    }
+
+    #[inline]
+    pub fn warnings(&self) -> &[ResolverWarning<'a>] {
+        &self.warnings
+    }
+
+    #[inline]
+    pub fn module_map(&self) -> &ModuleMap<'a> {
+        &self.module_map
+    }
+
+    // Hand out everything that was reported once the resolver is not needed anymore.
+    pub fn finish(self) -> (Vec<ResolverError<'a>>, Vec<ResolverWarning<'a>>) {
+        (self.errors, self.warnings)
+    }
+
+    // The parameters and local variables of a function, or of a method if `owner` names its struct.
+    pub fn locals_of(
+        &self,
+        owner: Option<&'a str>,
+        function: &'a str,
+    ) -> Option<&[Spanned<Symbol<'a>>]> {
+        self.locals.get(&(owner, function)).map(Vec::as_slice)
+    }
+
+    // Register every top-level declaration before any body is resolved, so functions can be used before they are defined.
+    fn collect_definitions(&mut self, module: &'a str, program: &Program<'a>) {
+        self.module_map.create(module);
+
+        let mut structs = vec![];
+        let constants = evaluate_constants(program);
+
+        for top_level in &program.0 {
+            if let Some(definition) = function_definition(top_level) {
+                self.module_map
+                    .define_function(module, definition.name, definition);
+            }
+
+            if let TopLevel::ExternVariable {
+                name,
+                ty,
+                is_public,
+                ..
+            } = top_level
+            {
+                let definition = GlobalDefinition {
+                    ty: ty.clone(),
+                    value: None,
+                    is_public: *is_public,
+                };
+
+                self.module_map.define_global(module, name.node, definition);
+            }
+
+            if let TopLevel::Constant {
+                declaration,
+                is_public,
+                ..
+            } = top_level
+            {
+                let name = declaration.name.node;
+                let value = constants.get(name).and_then(|value| value.clone().ok());
+
+                // Without a declared type, the type follows from the value, like it would for a literal.
+                let ty = declaration.ty.borrow().clone().or_else(|| {
+                    let ty = match value? {
+                        ConstValue::Integer(_) => {
+                            Type::Simple(Simple::Integer(Integer::new_signed_int(32)))
+                        }
+                        ConstValue::Bool(_) => Type::Simple(Simple::Bool),
+                    };
+
+                    Some(Spanned::new_from_span(declaration.value.span, ty))
+                });
+
+                match ty {
+                    Some(ty) => {
+                        let definition = GlobalDefinition {
+                            ty,
+                            value,
+                            is_public: *is_public,
+                        };
+
+                        self.module_map.define_global(module, name, definition);
+                    }
+
+                    None if module == self.source.name => {
+                        self.unresolved_constants.insert(name);
+                    }
+                    None => {}
+                }
+            }
+
+            if let TopLevel::TypeDeclaration {
+                ty:
+                    TypeDeclaration::StructDefinition {
+                        name,
+                        generic_parameters,
+                        fields,
+                        methods,
+                    },
+                is_public,
+                attributes,
+            } = top_level
+            {
+                let align = attributes
+                    .iter()
+                    .filter(|attribute| attribute.name.node == ALIGN_ATTRIBUTE)
+                    .flat_map(|attribute| attribute.arguments.first())
+                    .filter_map(|argument| parse_alignment(argument.node))
+                    .max();
+
+                let definition = UserTypeDefinition {
+                    name: name.node,
+                    span: name.span,
+                    fields: fields
+                        .iter()
+                        .map(|(field, ty)| (field.node, (0, ty.clone())))
+                        .collect(),
+                    field_order: fields.iter().map(|(field, _)| field.node).collect(),
+                    variants: vec![],
+                    backing_type: None,
+                    generic_parameters: generic_parameters
+                        .iter()
+                        .map(|parameter| parameter.node)
+                        .collect(),
+                    packed: attributes
+                        .iter()
+                        .any(|attribute| attribute.name.node == PACKED_ATTRIBUTE),
+                    align,
+                    methods: methods.iter().filter_map(method_definition).collect(),
+                    is_public: *is_public,
+                };
+
+                self.module_map.define_type(module, name.node, definition);
+                structs.push(name);
+            }
+
+            if let TopLevel::TypeDeclaration {
+                ty: TypeDeclaration::EnumDefinition { name, fields },
+                is_public,
+                ..
+            } = top_level
+            {
+                let definition = UserTypeDefinition {
+                    name: name.node,
+                    span: name.span,
+                    fields: std::collections::HashMap::new(),
+                    field_order: vec![],
+                    variants: fields.iter().map(|(variant, _)| variant.node).collect(),
+                    backing_type: Some(match fields.first() {
+                        Some((_, ty)) if ty.node != Type::Simple(Simple::Void) => ty.node.clone(),
+                        _ => Type::Simple(Simple::Integer(Integer::new_signed_int(32))),
+                    }),
+                    generic_parameters: vec![],
+                    packed: false,
+                    align: None,
+                    methods: std::collections::HashMap::new(),
+                    is_public: *is_public,
+                };
+
+                self.module_map.define_type(module, name.node, definition);
+            }
+        }
+
+        self.compute_field_offsets(module, &structs);
+    }
+
+    // Struct fields can refer to types declared further down, so offsets can only be computed after collection.
+    fn compute_field_offsets(&mut self, module: &'a str, structs: &[&Spanned<&'a str>]) {
+        let mut computer = LayoutComputer::new(self.options.data_layout, &self.module_map);
+        let layouts = structs
+            .iter()
+            .map(|name| {
+                let identifier = UserIdentifier::new(module, name.node);
+                (*name, computer.struct_layout_of(&identifier))
+            })
+            .collect::<Vec<_>>();
+
+        for (name, layout) in layouts {
+            match layout {
+                Ok(layout) => {
+                    let definition = self
+                        .module_map
+                        .get_user_type_mut(module, name.node)
+                        .unwrap();
+
+                    for (field, offset) in layout.offsets {
+                        definition.fields.get_mut(field).unwrap().0 = offset as u32;
+                    }
+                }
+
+                // Unknown types are reported where they are used, and generic parameters have no layout until the
+                // struct is instantiated, so only recursion is an error at this point.
+                Err(error @ LayoutError::RecursiveType(_)) => {
+                    self.error(name.span, ResolveErrorType::Layout(error))
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    fn resolve_top_level(&mut self, top_level: &TopLevel<'a>, owner: Option<&'a str>) {
+        match top_level {
+            TopLevel::FunctionDeclaration {
+                name,
+                arguments,
+                body,
+                return_type,
+                is_external,
+                ..
+            } => {
+                for Parameter(_, ty) in &arguments.parameters {
+                    self.check_type_arguments(ty);
+                }
+                self.check_type_arguments(return_type);
+
+                let key = (owner, name.node);
+                self.locals.insert(key, vec![]);
+                self.current_function = Some(key);
+                let undefined = std::mem::take(&mut self.undefined);
+                self.return_type = Some(self.own_instance(owner, &return_type.node));
+
+                self.with_scope(|resolver| {
+                    for Parameter(name, ty) in &arguments.parameters {
+                        if name.node == DISCARD {
+                            continue;
+                        }
+
+                        let ty = resolver.own_instance(owner, &ty.node);
+                        resolver.bind(name, ty, true);
+                    }
+
+                    resolver.resolve_block(body);
+                });
+
+                debug_assert_eq!(
+                    self.symbol_table.depth(),
+                    0,
+                    "scopes of '{}' were left open",
+                    name.node
+                );
+
+                let returns_value = !is_external && return_type.node != Type::Simple(Simple::Void);
+
+                let missing_return = returns_value && block_completes(body);
+                if missing_return {
+                    let error = ResolveErrorType::MissingReturn(MissingReturnError {
+                        function: name.node,
+                        return_type: return_type.node.clone(),
+                    });
+                    self.error(name.span, error);
+                }
+
+                // Without any `return` at all, the function was most likely left to be written later. The missing
+                // return is an error already if the end of the body can be reached, which says as much.
+                if returns_value && !missing_return && !block_returns(body) {
+                    self.warnings.push(ResolverWarning {
+                        source: self.source,
+                        warning: ResolveWarningType::StubFunction(StubFunctionWarning {
+                            function: name.node,
+                            return_type: return_type.node.clone(),
+                        }),
+                        warning_span: name.span,
+                        expression_span: name.span,
+                    });
+                }
+
+                self.current_function = None;
+                self.return_type = None;
+                self.undefined = undefined;
+            }
+
+            TopLevel::TypeDeclaration {
+                ty:
+                    TypeDeclaration::StructDefinition {
+                        name,
+                        fields,
+                        methods,
+                        ..
+                    },
+                ..
+            } => {
+                for (_, ty) in fields {
+                    self.check_type_arguments(ty);
+                }
+
+                self.check_shadowed_methods(name.node, fields, methods);
+
+                for method in methods {
+                    self.resolve_top_level(method, Some(name.node));
+                }
+            }
+
+            TopLevel::Import { name, symbols } => self.resolve_import(name, symbols),
+
+            TopLevel::Constant { declaration, .. } => {
+                if let Some(ty) = declaration.ty.borrow().as_ref() {
+                    self.check_type_arguments(ty);
+                }
+
+                self.resolve_constant(declaration)
+            }
+
+            TopLevel::ExternVariable { ty, .. }
+            | TopLevel::TypeDeclaration {
+                ty: TypeDeclaration::TypeAlias { ty, .. },
+                ..
+            } => self.check_type_arguments(ty),
+
+            TopLevel::TypeDeclaration { .. } | TopLevel::Error { .. } => {}
+        }
+    }
+
+    // Errors in the value come first, a value that cannot be computed at compile time is only reported without them.
+    fn resolve_constant(&mut self, declaration: &VariableDeclaration<'a>) {
+        let errors = self.errors.len();
+        let value_type = self.resolve_expression(&declaration.value);
+        let declared_type = declaration.ty.borrow().as_ref().map(|ty| ty.node.clone());
+
+        match (declared_type, value_type) {
+            (Some(expected), Some(ty)) => {
+                self.check_type(&declaration.value, ty, expected, "constant")
+            }
+            (None, Some(ty)) => {
+                declaration
+                    .ty
+                    .replace(Some(Spanned::new_from_span(declaration.value.span, ty)));
+            }
+            _ => {}
+        }
+
+        if self.errors.len() > errors {
+            return;
+        }
+
+        let name = declaration.name.node;
+        match self.constants.get(name).cloned() {
+            Some(Err(ConstantError::Evaluation(error))) => {
+                let error = ResolveErrorType::NotConstant(ConstantValueError { name, error });
+                self.error(declaration.value.span, error);
+            }
+            Some(Err(ConstantError::Cycle(cycle))) => {
+                let error = ResolveErrorType::ConstantCycle(ConstantCycleError { cycle });
+                self.error(declaration.name.span, error);
+            }
+            _ => {}
+        }
+    }
+
+    fn check_unused_functions(&mut self, program: &Program<'a>) {
+        for ((owner, function), span) in
+            CallGraph::new(&self.source.name, program).unused_functions()
+        {
+            self.warnings.push(ResolverWarning {
+                source: self.source,
+                warning: ResolveWarningType::UnusedFunction(UnusedFunctionWarning {
+                    owner,
+                    function,
+                }),
+                warning_span: span,
+                expression_span: span,
+            });
+        }
+    }
+
+    // `s.get` could mean a field or a method if a struct has both with that name.
+    fn check_shadowed_methods(
+        &mut self,
+        struct_name: &'a str,
+        fields: &[(Spanned<&'a str>, Spanned<Type<'a>>)],
+        methods: &[TopLevel<'a>],
+    ) {
+        for (field, _) in fields {
+            let method = methods.iter().find_map(|method| match method {
+                TopLevel::FunctionDeclaration { name, .. } if name.node == field.node => Some(name),
+                _ => None,
+            });
+
+            if let Some(method) = method {
+                self.warnings.push(ResolverWarning {
+                    source: self.source,
+                    warning: ResolveWarningType::FieldShadowsMethod(FieldShadowsMethodWarning {
+                        struct_name,
+                        name: field.node,
+                        method_span: method.span,
+                    }),
+                    warning_span: field.span,
+                    expression_span: field.span,
+                });
+            }
+        }
+    }
+
+    // Methods of a generic struct name it without type arguments, `self: &Pair` in `Pair<K, V>`, meaning the instance
+    // with the struct's own type parameters, `&Pair<K, V>`.
+    fn own_instance(&self, owner: Option<&'a str>, ty: &Type<'a>) -> Type<'a> {
+        let Some(definition) =
+            owner.and_then(|owner| self.module_map.get_user_type(&self.source.name, owner))
+        else {
+            return ty.clone();
+        };
+
+        if definition.generic_parameters.is_empty() {
+            return ty.clone();
+        }
+
+        let own = UserIdentifier::new(&self.source.name, definition.name);
+        ty.replace_simple(&|simple| match simple {
+            Simple::UserDefinedType(identifier) if *identifier == own => {
+                Some(Type::Complex(Complex::Generic(Generic::new(
+                    Type::Simple(simple.clone()),
+                    definition
+                        .generic_parameters
+                        .iter()
+                        .map(|parameter| Type::Simple(Simple::TypeParameter(parameter)))
+                        .collect(),
+                ))))
+            }
+            _ => None,
+        })
+    }
+
+    // Only symbols marked with `pub` can be imported. Modules have to be declared with `declare_module` beforehand.
+    fn resolve_import(&mut self, module: &Spanned<&'a str>, symbols: &[Spanned<&'a str>]) {
+        let name = self.module_aliases.get(module.node).copied().unwrap_or(module.node);
+        if name == self.source.name {
+            self.error(module.span, ResolveErrorType::SelfImport(SelfImportError));
+            return;
+        }
+
+        if !self.module_map.module_defined(name) {
+            let error = ResolveErrorType::UnknownModule(ModuleError {
+                module: module.node,
+            });
+            self.error(module.span, error);
+
+            return;
+        }
+
+        for symbol in symbols {
+            let is_public = match (
+                self.module_map.get_function(name, symbol.node),
+                self.module_map.get_user_type(name, symbol.node),
+                self.module_map.get_global(name, symbol.node),
+            ) {
+                (Some(function), _, _) => function.is_public,
+                (_, Some(ty), _) => ty.is_public,
+                (_, _, Some(global)) => global.is_public,
+
+                (None, None, None) => {
+                    let error = ResolveErrorType::NotInModule(ImportError {
+                        module: module.node,
+                        name: symbol.node,
+                    });
+                    self.error(symbol.span, error);
+
+                    continue;
+                }
+            };
+
+            self.imported.insert(symbol.node, name);
+
+            if !is_public {
+                let error = ResolveErrorType::NotExported(ImportError {
+                    module: module.node,
+                    name: symbol.node,
+                });
+                self.error(symbol.span, error);
+            }
+        }
+    }
+
+    fn resolve_block(&mut self, block: &Block<'a>) {
+        self.with_scope(|resolver| {
+            for statement in &block.0 {
+                resolver.resolve_statement(statement);
+            }
+        });
+    }
+
+    // Run `f` in a new scope. Every scope is opened and closed here only, so they stay balanced no matter how `f` leaves,
+    // be it through a `return` in the resolved code or an early return of the resolver itself.
+    fn with_scope<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        self.symbol_table.enter_scope();
+        self.nested_functions.push(FunctionMap::new());
+        let result = f(self);
+        self.nested_functions.pop();
+        self.symbol_table.exit_scope();
+
+        result
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement<'a>) {
+        match statement {
+            Statement::VariableDeclaration(declaration) => {
+                if let Some(ty) = declaration.ty.borrow().as_ref() {
+                    self.check_type_arguments(ty);
+                }
+
+                let value_type = self.resolve_expression(&declaration.value);
+                let declared_type = declaration.ty.borrow().as_ref().map(|ty| ty.node.clone());
+
+                if let (Some(ty), Some(value_type)) = (&declared_type, &value_type) {
+                    self.check_type(&declaration.value, value_type.clone(), ty.clone(), "initial value");
+                }
+
+                if declared_type.is_none() {
+                    if let Some(ty) = &value_type {
+                        declaration.ty.replace(Some(Spanned::new_from_span(
+                            declaration.value.span,
+                            ty.clone(),
+                        )));
+                    }
+                }
+
+                // `let _ = f();` runs `f` for its side effects only.
+                if declaration.name.node == DISCARD {
+                    return;
+                }
+
+                // What kept the type from being determined is reported already, the uses of the variable are not.
+                if let Some(ty) = declared_type.or(value_type) {
+                    self.bind(&declaration.name, ty, false);
+                } else {
+                    self.symbol_table.bind_unresolved(declaration.name.node);
+                }
+            }
+
+            Statement::IfStatement(statement) => {
+                self.resolve_condition(&statement.condition);
+                self.check_constant_if(statement);
+                self.check_empty_body("if", &statement.then_block);
+                self.resolve_block(&statement.then_block);
+
+                if let Some(else_branch) = &statement.else_branch {
+                    match else_branch.as_ref() {
+                        Else::IfStatement(statement) => self.resolve_statement(statement),
+                        Else::Block(block) => {
+                            self.check_empty_body("else", block);
+                            self.resolve_block(block)
+                        }
+                    }
+                }
+            }
+
+            Statement::WhileStatement(statement) => {
+                self.resolve_condition(&statement.condition);
+                self.check_constant_while(statement);
+                self.check_empty_body("while", &statement.body);
+                self.with_label(statement.label, true, |resolver| {
+                    resolver.resolve_block(&statement.body)
+                });
+            }
+
+            Statement::BlockStatement(statement) => {
+                if statement.label.is_some() {
+                    self.with_label(statement.label, false, |resolver| {
+                        resolver.resolve_block(&statement.body)
+                    });
+                } else {
+                    self.resolve_block(&statement.body);
+                }
+            }
+
+            Statement::MatchStatement(statement) => self.resolve_match(statement),
+
+            Statement::BreakStatement(control) => self.resolve_loop_control(control, true),
+            Statement::ContinueStatement(control) => self.resolve_loop_control(control, false),
+
+            Statement::ExpressionStatement(expression) => {
+                self.resolve_expression(expression);
+                self.check_unused_result(expression);
+            }
+
+            Statement::ReturnStatement(_, Some(expression)) => {
+                if let Some(ty) = self.resolve_expression(expression) {
+                    self.check_return_type(expression, ty);
+                }
+            }
+
+            Statement::DeleteStatement(expression) => {
+                self.resolve_expression(expression);
+            }
+
+            Statement::FunctionDeclaration(function) => self.resolve_nested_function(function),
+
+            Statement::ReturnStatement(keyword, None) => self.check_missing_return_value(keyword.span),
+        }
+    }
+
+    // A nested function is visible to the statements after it in its block, and to itself so it can recurse. Its body
+    // is resolved like that of any other function, with nothing of the enclosing function but its nested functions in
+    // scope.
+    fn resolve_nested_function(&mut self, function: &TopLevel<'a>) {
+        let Some(definition) = function_definition(function) else {
+            return;
+        };
+
+        if let Some(scope) = self.nested_functions.last_mut() {
+            scope.insert(definition.name, definition);
+        }
+
+        let enclosing = std::mem::replace(&mut self.symbol_table, SymbolTable::new());
+        self.enclosing.push(enclosing);
+        let labels = std::mem::take(&mut self.labels);
+        let current_function = self.current_function;
+        let return_type = self.return_type.take();
+
+        self.resolve_top_level(function, None);
+
+        self.symbol_table = self.enclosing.pop().unwrap();
+        self.labels = labels;
+        self.current_function = current_function;
+        self.return_type = return_type;
+    }
+
+    fn nested_function(&self, name: &str) -> Option<&FunctionDefinition<'a>> {
+        self.nested_functions
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+    }
+
+    // Resolve the type of an expression and store it on the expression itself. `None` means that the type could not be
+    // determined, in which case the error has been reported already (or the expression is not typed yet).
+    fn resolve_expression(&mut self, expression: &Spanned<Expression<'a>>) -> Option<Type<'a>> {
+        let ty = match expression.node.kind() {
+            ExpressionKind::Error(_) => return None,
+
+            // A literal that does not even fit into the widest integer type cannot take on any type at all.
+            // Without a type to take on, a literal is the narrowest of `i32`, `i64` and `u64` it fits into, so large
+            // ones do not silently overflow.
+            ExpressionKind::DecLiteral(literal) => {
+                let Ok(value) = literal.parse::<u64>() else {
+                    let widest = Type::Simple(Simple::Integer(widest_integer()));
+                    let error = ResolveErrorType::LiteralOutOfRange(LiteralRangeError(widest));
+                    self.error(expression.span, error);
+
+                    return None;
+                };
+
+                let integer = if i32::try_from(value).is_ok() {
+                    Integer::new_signed_int(32)
+                } else if i64::try_from(value).is_ok() {
+                    Integer::new_signed_int(64)
+                } else {
+                    widest_integer()
+                };
+
+                Some(Type::Simple(Simple::Integer(integer)))
+            }
+            ExpressionKind::FloatLiteral(_) => Some(Type::Simple(Simple::Float(Float::new_f64()))),
+            ExpressionKind::StringLiteral(_) => Some(Type::Simple(Simple::String)),
+            ExpressionKind::Char(_) => Some(Type::Simple(Simple::Character)),
+            ExpressionKind::BoolLiteral(_) | ExpressionKind::Constant(ConstValue::Bool(_)) => {
+                Some(Type::Simple(Simple::Bool))
+            }
+            ExpressionKind::Constant(ConstValue::Integer(_)) => {
+                Some(Type::Simple(Simple::Integer(Integer::new_signed_int(32))))
+            }
+
+            ExpressionKind::Identifier(name) => self.resolve_identifier(expression.span, name),
+            ExpressionKind::Grouping(inner) => self.resolve_expression(inner),
+
+            // The value is resolved in the scope of the block, so it sees the variables declared in there.
+            ExpressionKind::Block { body, value } => self.with_scope(|resolver| {
+                for statement in &body.0 {
+                    resolver.resolve_statement(statement);
+                }
+
+                match value {
+                    Some(value) => resolver.resolve_expression(value),
+                    None => Some(Type::Simple(Simple::Void)),
+                }
+            }),
+
+            ExpressionKind::SizeOf(_) => {
+                Some(Type::Simple(Simple::Integer(Integer::new_unsigned_int(64))))
+            }
+            ExpressionKind::SizeOfValue(operand) => {
+                if !self.names_type(operand) {
+                    self.resolve_expression(operand);
+                }
+
+                Some(Type::Simple(Simple::Integer(Integer::new_unsigned_int(64))))
+            }
+
+            ExpressionKind::ArrayLiteral(elements) => {
+                self.resolve_array_literal(expression, elements)
+            }
+
+            // Every element is resolved, but a tuple with an element that has no type has no type either.
+            ExpressionKind::Tuple(elements) => {
+                let types = elements
+                    .iter()
+                    .map(|element| self.resolve_expression(element))
+                    .collect::<Vec<_>>();
+
+                types
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(|types| Type::Complex(Complex::Tuple(Tuple::new(types))))
+            }
+
+            ExpressionKind::Call {
+                module,
+                callee,
+                arguments,
+            } => self.resolve_call(module, callee, arguments),
+
+            // Casting to the type a value already has does nothing, which hints at a misunderstanding of the types.
+            ExpressionKind::Cast(inner, as_token, ty) => {
+                let inner_type = self.resolve_expression(inner);
+                self.check_enum_cast(expression, inner, inner_type.as_ref(), ty);
+
+                if inner_type.as_ref() == Some(&ty.node) {
+                    self.warnings.push(ResolverWarning {
+                        source: self.source,
+                        warning: ResolveWarningType::RedundantCast(RedundantCastWarning {
+                            ty: ty.node.clone(),
+                        }),
+                        warning_span: Span::new(as_token.span.start, ty.span.end),
+                        expression_span: expression.span,
+                    });
+                }
+
+                Some(ty.node.clone())
+            }
+
+            ExpressionKind::Access { left, identifier } => {
+                self.resolve_access(expression, left, identifier)
+            }
+
+            // Targets are variables, fields and dereferenced pointers, which have the type of what they refer to. Other
+            // targets are reported by the parser already.
+            ExpressionKind::Assignment { left, value, .. } => {
+                let target = self
+                    .resolve_expression(left)
+                    .filter(|_| left.node.is_l_value());
+                let ty = self.resolve_expression(value);
+
+                if let (Some(target), Some(ty)) = (&target, ty) {
+                    self.check_type(value, ty, target.clone(), "assigned value");
+                }
+
+                target
+            }
+
+            ExpressionKind::StructInitialization { identifier, fields } => {
+                self.resolve_struct_initialization(identifier, fields)
+            }
+
+            // `new` allocates its operand on the heap and yields a pointer to it.
+            ExpressionKind::New(inner) => match self.resolve_expression(inner)? {
+                Type::Complex(Complex::Pointer(_)) | Type::Complex(Complex::Ref(_)) => None,
+                ty => Some(Type::Complex(Complex::Pointer(Pointer::new(ty, 1)))),
+            },
+
+            ExpressionKind::Dereference(_, inner) => {
+                let ty = self.resolve_expression(inner)?;
+
+                match &ty {
+                    Type::Complex(Complex::Pointer(pointer)) if pointer.size() > 1 => {
+                        Some(Type::Complex(Complex::Pointer(Pointer::new(
+                            pointer.base_type().clone(),
+                            pointer.size() - 1,
+                        ))))
+                    }
+                    Type::Complex(Complex::Ref(reference)) if reference.size() > 1 => {
+                        Some(Type::Complex(Complex::Ref(Ref::new(
+                            reference.base_type().clone(),
+                            reference.size() - 1,
+                        ))))
+                    }
+                    Type::Complex(Complex::Pointer(pointer)) => Some(pointer.base_type().clone()),
+                    Type::Complex(Complex::Ref(reference)) => Some(reference.base_type().clone()),
+
+                    _ => {
+                        let error = ResolveErrorType::Dereference(NonPointerError(ty.clone()));
+                        self.error_in(inner.span, expression.span, error);
+
+                        None
+                    }
+                }
+            }
+
+            ExpressionKind::Binary(left, operator, right) => {
+                self.resolve_binary(expression, left, operator, right)
+            }
+
+            // Comparisons and logical operators always yield a `bool`, even if their operands have errors.
+            ExpressionKind::BoolBinary(left, operator, right) => {
+                let left_ty = self.resolve_expression(left);
+                let right_ty = self.resolve_expression(right);
+
+                if matches!(
+                    operator.node,
+                    TokenType::AmpersandAmpersand | TokenType::PipePipe
+                ) {
+                    self.check_condition(left, left_ty);
+                    self.check_condition(right, right_ty);
+                }
+
+                // `a < b < c` compares the `bool` result of `a < b` with `c`, which is never what was meant.
+                if let ExpressionKind::BoolBinary(_, first, _) = left.node.kind() {
+                    let is_comparison =
+                        |token: &TokenType| token.precedence() == Precedence::Comparison;
+
+                    if is_comparison(&first.node) && is_comparison(&operator.node) {
+                        let error = ResolveErrorType::ChainedComparison(ChainedComparisonError {
+                            first: first.node.clone(),
+                            second: operator.node.clone(),
+                        });
+                        self.error_in(operator.span, expression.span, error);
+                    }
+                }
+
+                Some(Type::Simple(Simple::Bool))
+            }
+
+            ExpressionKind::BoolNegate(_, inner) => {
+                let ty = self.resolve_expression(inner);
+                self.check_condition(inner, ty);
+
+                Some(Type::Simple(Simple::Bool))
+            }
+
+            ExpressionKind::Negate(operator, inner) => {
+                let ty = self.resolve_expression(inner)?;
+
+                if !ty.is_numerical() {
+                    let error = ResolveErrorType::NotArithmetic(ArithmeticError {
+                        ty,
+                        operator: operator.node.clone(),
+                    });
+                    self.error_in(operator.span, expression.span, error);
+
+                    return None;
+                }
+
+                Some(ty)
+            }
+
+            ExpressionKind::BitNot(operator, inner) => {
+                let ty = self.resolve_expression(inner)?;
+
+                if !ty.is_integer() {
+                    let error = ResolveErrorType::NotArithmetic(ArithmeticError {
+                        ty,
+                        operator: operator.node.clone(),
+                    });
+                    self.error_in(operator.span, expression.span, error);
+
+                    return None;
+                }
+
+                Some(ty)
+            }
+
+            // Pointers are indexed like arrays of what they point to, as they are in C.
+            ExpressionKind::Index { array, index } => {
+                let array_type = self.resolve_expression(array);
+
+                if let Some(index_type) = self.resolve_expression(index) {
+                    if !index_type.is_integer() {
+                        let error = ResolveErrorType::NonIntegerIndex(IndexTypeError(index_type));
+                        self.error_in(index.span, expression.span, error);
+                    }
+                }
+
+                match array_type? {
+                    Type::Complex(Complex::Array(array)) => Some(array.base_type().clone()),
+                    Type::Complex(Complex::Pointer(pointer)) if pointer.size() > 1 => {
+                        Some(Type::Complex(Complex::Pointer(Pointer::new(
+                            pointer.base_type().clone(),
+                            pointer.size() - 1,
+                        ))))
+                    }
+                    Type::Complex(Complex::Pointer(pointer)) => Some(pointer.base_type().clone()),
+
+                    ty => {
+                        let error = ResolveErrorType::IndexNonArray(NonArrayError(ty));
+                        self.error_in(array.span, expression.span, error);
+
+                        None
+                    }
+                }
+            }
+
+            // Pointers move by a single element, like they do with `+ 1`.
+            ExpressionKind::Increment {
+                operator, operand, ..
+            } => {
+                let ty = self.resolve_expression(operand)?;
+
+                if !ty.is_numerical() && !matches!(ty, Type::Complex(Complex::Pointer(_))) {
+                    let error = ResolveErrorType::NotArithmetic(ArithmeticError {
+                        ty,
+                        operator: operator.node.clone(),
+                    });
+                    self.error_in(operator.span, expression.span, error);
+
+                    return None;
+                }
+
+                Some(ty)
+            }
+
+            _ => {
+                for sub_expression in expression.node.sub_expressions() {
+                    self.resolve_expression(sub_expression);
+                }
+
+                None
+            }
+        };
+
+        if let Some(ty) = &ty {
+            expression.node.set_ty(ty.clone());
+        }
+
+        ty
+    }
+
+    // Arithmetic works on numbers and characters, pointers can be moved by adding or subtracting an integer, and adding
+    // two strings concatenates them. Anything else is an error on the operator, which names the types of both operands.
+    fn resolve_binary(
+        &mut self,
+        expression: &Spanned<Expression<'a>>,
+        left: &Spanned<Expression<'a>>,
+        operator: &Spanned<TokenType<'a>>,
+        right: &Spanned<Expression<'a>>,
+    ) -> Option<Type<'a>> {
+        let left_ty = self.resolve_expression(left);
+        let right_ty = self.resolve_expression(right);
+        let (left_ty, right_ty) = (left_ty?, right_ty?);
+
+        let moves_pointer = matches!(operator.node, TokenType::Plus | TokenType::Minus);
+        if moves_pointer && left_ty.is_pointer() && right_ty.is_integer() {
+            return Some(left_ty);
+        }
+        if operator.node == TokenType::Plus && left_ty.is_integer() && right_ty.is_pointer() {
+            return Some(right_ty);
+        }
+        if operator.node == TokenType::Plus && left_ty.is_string() && right_ty.is_string() {
+            return Some(left_ty);
+        }
+
+        let fits = |ty: &Type| ty.arithmetic() && !ty.is_pointer();
+        if !fits(&left_ty) || !fits(&right_ty) {
+            let error = ResolveErrorType::IllegalOperation(BinaryOperationError {
+                left_type: left_ty,
+                right_type: right_ty,
+            });
+            self.error_in(operator.span, expression.span, error);
+
+            return None;
+        }
+
+        // A literal takes on the type of the other operand, so a `u8` stays a `u8` in `byte + 1`.
+        let (mut left_ty, mut right_ty) = (left_ty, right_ty);
+        if !is_number_literal(&left.node) && self.adopt_literal_type(right, &left_ty) {
+            right_ty = left_ty.clone();
+        } else if !is_number_literal(&right.node) && self.adopt_literal_type(left, &right_ty) {
+            left_ty = right_ty.clone();
+        }
+
+        let Some(promoted) = promote(&left_ty, &right_ty) else {
+            return Some(left_ty);
+        };
+
+        if loses_sign(&left_ty, &right_ty) {
+            self.warnings.push(ResolverWarning {
+                source: self.source,
+                warning: ResolveWarningType::SignednessMismatch(SignednessWarning {
+                    left_type: left_ty,
+                    right_type: right_ty,
+                    promoted: promoted.clone(),
+                }),
+                warning_span: operator.span,
+                expression_span: expression.span,
+            });
+        }
+
+        Some(promoted)
+    }
+
+    // Calls of functions declared in a module check their arguments against the parameters. Everything else that can
+    // be called, like variables, has no function type to check against yet.
+    fn resolve_call(
+        &mut self,
+        module: &'a str,
+        callee: &Spanned<Expression<'a>>,
+        arguments: &ArgumentList<'a>,
+    ) -> Option<Type<'a>> {
+        let argument_types = arguments
+            .0
+            .iter()
+            .map(|argument| self.resolve_expression(argument))
+            .collect::<Vec<_>>();
+
+        let definition = match callee.node.kind() {
+            ExpressionKind::Identifier(name) if module == SELF => {
+                self.find_sibling_method(callee.span, name)
+            }
+            ExpressionKind::Identifier(name) if !self.symbol_table.is_bound(name) => {
+                self.find_function(module, callee.span, name)
+            }
+            ExpressionKind::Identifier(_) => None,
+
+            _ => {
+                self.resolve_expression(callee);
+                None
+            }
+        }?;
+
+        let expected = definition.number_of_parameters_without_varargs();
+        let count_matches = if definition.varargs {
+            arguments.0.len() >= expected
+        } else {
+            arguments.0.len() == expected
+        };
+
+        if !count_matches {
+            let error = ResolveErrorType::ArgumentCount(ArgumentCountError {
+                function: definition.name,
+                expected,
+                actual: arguments.0.len(),
+                varargs: definition.varargs,
+            });
+            self.error(callee.span, error);
+        }
+
+        for ((argument, ty), parameter) in arguments
+            .0
+            .iter()
+            .zip(argument_types)
+            .zip(&definition.parameters[..expected])
+        {
+            if let Some(ty) = ty {
+                self.check_argument(argument, ty, &parameter.node);
+            }
+        }
+
+        Some(definition.return_type.node)
+    }
+
+    // The function a call by name refers to. `a.foo()` calls `foo` of the module `a`, which has to be public, unless `a`
+    // is a variable, which makes it a method call. Unqualified names refer to the current module, or to the one they are
+    // imported from, so modules can define functions of the same name without them getting mixed up.
+    fn find_function(
+        &mut self,
+        module: &'a str,
+        span: Span,
+        name: &'a str,
+    ) -> Option<FunctionDefinition<'a>> {
+        if module == self.source.name {
+            let definition = self.nested_function(name).cloned().or_else(|| {
+                self.module_map
+                    .get_function(self.module_of(name), name)
+                    .cloned()
+            });
+
+            if definition.is_none() {
+                self.not_defined(span, name);
+            }
+
+            return definition;
+        }
+
+        if self.symbol_table.is_bound(module) || !self.module_map.module_defined(module) {
+            return None;
+        }
+
+        let Some(definition) = self.module_map.get_function(module, name).cloned() else {
+            let error = ResolveErrorType::NotInModule(ImportError { module, name });
+            self.error(span, error);
+
+            return None;
+        };
+
+        if !definition.is_public {
+            let error = ResolveErrorType::NotExported(ImportError { module, name });
+            self.error(span, error);
+        }
+
+        Some(definition)
+    }
+
+    // `self.other()` in a method calls another method of the same struct, with `self` passed implicitly, so its
+    // signature is the method's without `self`. A field that is called instead, like `self.callback()`, is not checked.
+    fn find_sibling_method(&mut self, span: Span, name: &'a str) -> Option<FunctionDefinition<'a>> {
+        let owner = self.current_function.and_then(|(owner, _)| owner)?;
+        let definition = self.module_map.get_user_type(&self.source.name, owner)?;
+
+        if let Some(method) = definition.methods.get(name) {
+            let mut method = method.clone();
+            method.return_type.node = self.own_instance(Some(owner), &method.return_type.node);
+            for parameter in &mut method.parameters {
+                parameter.node = self.own_instance(Some(owner), &parameter.node);
+            }
+
+            return Some(method);
+        }
+
+        if !definition.fields.contains_key(name) {
+            let error = ResolveErrorType::NoSuchMethod(StructMethodError {
+                struct_name: definition.name,
+                method_name: name,
+            });
+            self.error(span, error);
+        }
+
+        None
+    }
+
+    // The module an unqualified name refers to, which is the current one unless the name is imported and not defined in
+    // the current module.
+    fn module_of(&self, name: &str) -> &'a str {
+        let own = self.source.name.as_str();
+        let is_own = self.module_map.function_defined(own, name)
+            || self.module_map.get_global(own, name).is_some()
+            || self.module_map.get_user_type(own, name).is_some();
+
+        match self.imported.get(name) {
+            Some(module) if !is_own => module,
+            _ => own,
+        }
+    }
+
+    // Arrays decay to a pointer to their first element when passed to a function, as they do in C. So `[4]i32` can be
+    // passed as `*i32` or `[?]i32`, but not as an `i32`.
+    fn check_argument(
+        &mut self,
+        argument: &Spanned<Expression<'a>>,
+        ty: Type<'a>,
+        expected: &Type<'a>,
+    ) {
+        if self.adopt_literal_type(argument, expected)
+            || compatible(&ty, expected)
+            || decays_to(&ty, expected)
+        {
+            return;
+        }
+
+        let error = ResolveErrorType::IllegalType(IllegalTypeError {
+            expected_type: expected.clone(),
+            actual_type: ty,
+            name: "argument",
+        });
+
+        self.error(argument.span, error);
+    }
+
+    // `sizeof(Pair)` parses like an expression, but refers to the type if there is no variable of that name.
+    fn names_type(&self, expression: &Spanned<Expression<'a>>) -> bool {
+        let ExpressionKind::Identifier(name) = expression.node.kind() else {
+            return false;
+        };
+
+        !self.symbol_table.is_bound(name)
+            && self
+                .module_map
+                .get_user_type(&self.source.name, name)
+                .is_some()
+    }
+
+    fn resolve_identifier(&mut self, span: Span, name: &'a str) -> Option<Type<'a>> {
+        if name == DISCARD {
+            self.error(span, ResolveErrorType::DiscardRead(DiscardError));
+            return None;
+        }
+
+        if let Some(symbol) = self.symbol_table.lookup(name) {
+            return Some(symbol.node.ty.clone());
+        }
+        if self.symbol_table.is_bound(name) {
+            return None;
+        }
+
+        let module = self.module_of(name);
+        if let Some(global) = self.module_map.get_global(module, name) {
+            return Some(global.ty.node.clone());
+        }
+        if module == self.source.name && self.unresolved_constants.contains(name) {
+            return None;
+        }
+
+        // Functions are values too, they just don't have a type yet.
+        if !self.module_map.function_defined(module, name) && self.nested_function(name).is_none() {
+            self.not_defined(span, name);
+        }
+
+        None
+    }
+
+    // An undefined name is usually used more than once, so only its first use is reported, noting all the others.
+    fn not_defined(&mut self, span: Span, name: &'a str) {
+        if let Some((_, function)) = self.current_function {
+            if self.enclosing.iter().any(|table| table.is_bound(name)) {
+                let error = ResolveErrorType::Capture(CaptureError { function, name });
+                return self.error(span, error);
+            }
+        }
+
+        if let Some(&index) = self.undefined.get(name) {
+            if let Some(ResolveErrorType::NotDefined(error)) =
+                index.map(|index| &mut self.errors[index].error)
+            {
+                error.other_uses.push(span);
+            }
+
+            return;
+        }
+
+        let index = (!self.error_limit_reached()).then_some(self.errors.len());
+        self.undefined.insert(name, index);
+
+        let error = ResolveErrorType::NotDefined(DefinitionError {
+            name,
+            other_uses: vec![],
+        });
+        self.error(span, error);
+    }
+
+    // Fields can be accessed on a struct value directly, or through a single pointer or reference to it; `p.field` with
+    // `p: *Pair` behaves like `(*p).field`. Anything with more indirection has to be dereferenced explicitly.
+    fn resolve_access(
+        &mut self,
+        expression: &Spanned<Expression<'a>>,
+        left: &Spanned<Expression<'a>>,
+        identifier: &Spanned<&'a str>,
+    ) -> Option<Type<'a>> {
+        // A name that is not a variable may refer to an enum, like `Color.Red`, or to a module, like `math.pi`. Anything
+        // else is resolved like any other identifier, be it a global or not defined at all.
+        if let ExpressionKind::Identifier(name) = left.node.kind() {
+            if !self.symbol_table.is_bound(name) {
+                if let Some(enum_type) = self.enum_named(name) {
+                    return self.resolve_variant(enum_type, identifier, expression.span);
+                }
+
+                if self.module_map.module_defined(name) {
+                    return self.resolve_module_global(name, identifier);
+                }
+            }
+        }
+
+        let left_type = self.resolve_expression(left)?;
+
+        let accessed = match &left_type {
+            Type::Complex(Complex::Pointer(pointer)) if pointer.size() == 1 => pointer.base_type(),
+            Type::Complex(Complex::Ref(reference)) if reference.size() == 1 => {
+                reference.base_type()
+            }
+
+            Type::Complex(Complex::Pointer(_)) | Type::Complex(Complex::Ref(_)) => {
+                let error =
+                    ResolveErrorType::ImplicitIndirection(IndirectionError(left_type.clone()));
+                self.error_in(left.span, expression.span, error);
+
+                return None;
+            }
+
+            ty => ty,
+        };
+
+        // The fields of an instance of a generic struct have the instance's type arguments for its type parameters.
+        let (base_type, arguments) = match accessed {
+            Type::Simple(ty) => (ty, &[][..]),
+            Type::Complex(Complex::Generic(generic)) => {
+                (generic.base_type().simple(), generic.arguments())
+            }
+
+            _ => {
+                let error = ResolveErrorType::AccessNonStruct(NonStructError(left_type.clone()));
+                self.error_in(left.span, expression.span, error);
+
+                return None;
+            }
+        };
+
+        let definition = match base_type {
+            Simple::UserDefinedType(user_type) => {
+                let mut user_type = user_type.clone();
+                self.module_map
+                    .get_user_type(user_type.file(), user_type.name())
+            }
+
+            _ => None,
+        };
+
+        let Some(definition) = definition else {
+            let error = ResolveErrorType::AccessNonStruct(NonStructError(left_type.clone()));
+            self.error_in(left.span, expression.span, error);
+
+            return None;
+        };
+
+        if let Some((_, ty)) = definition.fields.get(identifier.node) {
+            return Some(
+                ty.node
+                    .substitute(&definition.generic_parameters, arguments),
+            );
+        }
+
+        let error = ResolveErrorType::NoSuchField(StructFieldError {
+            struct_name: definition.name,
+            field_name: identifier.node,
+        });
+
+        self.error_in(identifier.span, expression.span, error);
+
+        None
+    }
+
+    // The enum declared (or imported) as `name`, if there is one.
+    fn enum_named(&self, name: &'a str) -> Option<Type<'a>> {
+        let module = self.module_of(name);
+        let definition = self.module_map.get_user_type(module, name)?;
+
+        if definition.variants.is_empty() {
+            return None;
+        }
+
+        Some(Type::Simple(Simple::UserDefinedType(UserIdentifier::new(module, definition.name))))
+    }
+
+    // `Color.Red` is a value of the enum `Color`, as long as it has such a variant.
+    fn resolve_variant(
+        &mut self,
+        enum_type: Type<'a>,
+        variant: &Spanned<&'a str>,
+        expression_span: Span,
+    ) -> Option<Type<'a>> {
+        let (enum_name, variants) = self.enum_of(&enum_type)?;
+        if variants.contains(&variant.node) {
+            return Some(enum_type);
+        }
+
+        let error = ResolveErrorType::NoSuchVariant(VariantError {
+            enum_name,
+            variant: variant.node,
+        });
+        self.error_in(variant.span, expression_span, error);
+
+        None
+    }
+
+    // A global of another module, which has to be exported just like its functions.
+    fn resolve_module_global(&mut self, module: &'a str, name: &Spanned<&'a str>) -> Option<Type<'a>> {
+        let Some(global) = self.module_map.get_global(module, name.node) else {
+            let error = ResolveErrorType::NotInModule(ImportError { module, name: name.node });
+            self.error(name.span, error);
+
+            return None;
+        };
+
+        let ty = global.ty.node.clone();
+        if !global.is_public {
+            let error = ResolveErrorType::NotExported(ImportError { module, name: name.node });
+            self.error(name.span, error);
+        }
+
+        Some(ty)
+    }
+
+    // Every initialized field has to exist. In the methods of a generic struct, initializing the struct creates an
+    // instance of the same arguments, so the values are checked against the field types as they are declared. Outside
+    // of them, the type arguments are not known, and only the fields of structs that are not generic are checked.
+    fn resolve_struct_initialization(
+        &mut self,
+        identifier: &Spanned<UserIdentifier<'a>>,
+        fields: &InitializerList<'a>,
+    ) -> Option<Type<'a>> {
+        let ty = Type::Simple(Simple::UserDefinedType(identifier.node.clone()));
+        let owner = self.current_function.and_then(|(owner, _)| owner);
+
+        let mut user_type = identifier.node.clone();
+        let definition = self
+            .module_map
+            .get_user_type(user_type.file(), user_type.name())
+            .filter(|definition| definition.variants.is_empty())
+            .map(|definition| {
+                let checks_types =
+                    definition.generic_parameters.is_empty() || owner == Some(definition.name);
+                (definition.name, definition.fields.clone(), checks_types, definition.field_order.clone())
+            });
+
+        // Every field has to be given a value.
+        match &definition {
+            Some((struct_name, _, _, field_order)) => {
+                let missing = field_order
+                    .iter()
+                    .filter(|field| !fields.0.iter().any(|(name, _)| name.node == **field))
+                    .copied()
+                    .collect::<Vec<&'a str>>();
+
+                if !missing.is_empty() {
+                    let error = ResolveErrorType::MissingFields(MissingFieldsError {
+                        struct_name,
+                        fields: missing,
+                    });
+                    self.error(identifier.span, error);
+                }
+            }
+
+            None => {
+                let error = ResolveErrorType::UnknownStruct(UnknownStructError {
+                    name: user_type.name(),
+                });
+                self.error(identifier.span, error);
+            }
+        }
+
+        for (name, value) in &fields.0 {
+            let value_type = self.resolve_expression(value);
+            let Some((struct_name, declared, checks_types, _)) = &definition else {
+                continue;
+            };
+
+            match (declared.get(name.node), value_type) {
+                (Some((_, field_type)), Some(value_type)) if *checks_types => {
+                    self.check_type(value, value_type, field_type.node.clone(), "field value")
+                }
+                (Some(_), _) => {}
+
+                (None, _) => {
+                    let error = ResolveErrorType::NoSuchField(StructFieldError {
+                        struct_name,
+                        field_name: name.node,
+                    });
+                    self.error(name.span, error);
+                }
+            }
+        }
+
+        Some(self.own_instance(owner, &ty))
+    }
+
+    // All elements of an array literal must have the same type, which becomes the base type of the resulting sized array.
+    fn resolve_array_literal(
+        &mut self,
+        expression: &Spanned<Expression<'a>>,
+        elements: &[Spanned<Expression<'a>>],
+    ) -> Option<Type<'a>> {
+        let mut element_type: Option<Type<'a>> = None;
+
+        for element in elements {
+            let Some(ty) = self.resolve_expression(element) else {
+                continue;
+            };
+
+            match &element_type {
+                None => element_type = Some(ty),
+
+                Some(expected_type) if *expected_type != ty => {
+                    let error = ResolveErrorType::IllegalType(IllegalTypeError {
+                        expected_type: expected_type.clone(),
+                        actual_type: ty,
+                        name: "array element",
+                    });
+
+                    self.error_in(element.span, expression.span, error);
+                }
+
+                Some(_) => {}
+            }
+        }
+
+        if elements.is_empty() {
+            self.error(
+                expression.span,
+                ResolveErrorType::Inference(TypeInferenceError),
+            );
+            return None;
+        }
+
+        let base_type = element_type?;
+
+        let size = Expression::new(ExpressionKind::Constant(ConstValue::Integer(
+            elements.len() as i128,
+        )));
+
+        Some(Type::Complex(Complex::Array(Array::new(
+            base_type,
+            Box::new(Some(size)),
+        ))))
+    }
+
+    // A call whose value is thrown away is suspicious if the function returns something, since that is often an error
+    // code. This only warns for functions marked with `@[must_use]`, unless enabled for all functions in the options.
+    fn check_unused_result(&mut self, expression: &Spanned<Expression<'a>>) {
+        let ExpressionKind::Call { module, callee, .. } = expression.node.kind() else {
+            return;
+        };
+
+        let ExpressionKind::Identifier(name) = callee.node.kind() else {
+            return;
+        };
+
+        let Some(definition) = self.module_map.get_function(module, name) else {
+            return;
+        };
+
+        let return_type = &definition.return_type.node;
+        if *return_type == Type::Simple(Simple::Void)
+            || !(definition.must_use || self.options.warn_unused_results)
+        {
+            return;
+        }
+
+        let warning = ResolveWarningType::UnusedResult(UnusedResultWarning {
+            function: name,
+            return_type: return_type.clone(),
+        });
+
+        self.warnings.push(ResolverWarning {
+            source: self.source,
+            warning,
+            warning_span: callee.span,
+            expression_span: expression.span,
+        });
+    }
+
+    // Structs are returned by value, so returning the pointer `new` creates from a function declared to return the
+    // struct itself is a mismatch. Numeric types are not checked yet, since literals do not adapt to their context.
+    fn check_return_type(&mut self, expression: &Spanned<Expression<'a>>, ty: Type<'a>) {
+        let Some(expected) = self.return_type.clone() else {
+            return;
+        };
+
+        self.check_type(expression, ty, expected, "returned value");
+    }
+
+    // A bare `return;` returns nothing, which only `void` functions may do.
+    fn check_missing_return_value(&mut self, span: Span) {
+        let Some(expected) = self.return_type.clone() else {
+            return;
+        };
+
+        if expected != Type::Simple(Simple::Void) {
+            let error = ResolveErrorType::IllegalType(IllegalTypeError {
+                expected_type: expected,
+                actual_type: Type::Simple(Simple::Void),
+                name: "returned value",
+            });
+
+            self.error(span, error);
+        }
+    }
+
+    // The elements of a tuple or array literal are checked one by one, so a mismatch points at the offending element.
+    // The literal then takes on the expected type, just like its numbers convert to the expected element types.
+    fn check_type(
+        &mut self,
+        expression: &Spanned<Expression<'a>>,
+        ty: Type<'a>,
+        expected: Type<'a>,
+        name: &'a str,
+    ) {
+        if let (
+            ExpressionKind::Tuple(elements),
+            Type::Complex(Complex::Tuple(actual)),
+            Type::Complex(Complex::Tuple(tuple)),
+        ) = (expression.node.kind(), &ty, &expected)
+        {
+            if elements.len() == tuple.elements().len() {
+                for ((element, ty), expected) in
+                    elements.iter().zip(actual.elements()).zip(tuple.elements())
+                {
+                    self.check_type(element, ty.clone(), expected.clone(), "tuple element");
+                }
+
+                expression.node.set_ty(expected);
+                return;
+            }
+        }
+
+        if let (ExpressionKind::ArrayLiteral(elements), Type::Complex(Complex::Array(array))) =
+            (expression.node.kind(), &expected)
+        {
+            if array.length() == u64::try_from(elements.len()).ok() {
+                for element in elements {
+                    if let Some(ty) = element.node.clone_ty() {
+                        self.check_type(element, ty, array.base_type().clone(), "array element");
+                    }
+                }
+
+                expression.node.set_ty(expected);
+                return;
+            }
+        }
+
+        if self.adopt_literal_type(expression, &expected) || compatible(&ty, &expected) {
+            return;
+        }
+
+        let error = ResolveErrorType::IllegalType(IllegalTypeError {
+            expected_type: expected,
+            actual_type: ty,
+            name,
+        });
+
+        self.error(expression.span, error);
+    }
+
+    // A generic struct takes as many type arguments as it has type parameters, or none at all, which is how its methods
+    // refer to the struct itself, like `self: &Pair`. Types that are not defined are left alone.
+    fn check_type_arguments(&mut self, ty: &Spanned<Type<'a>>) {
+        let mut errors = vec![];
+        type_argument_errors(&ty.node, &self.module_map, &mut errors);
+
+        for error in errors {
+            self.error(ty.span, ResolveErrorType::TypeArgumentCount(error));
+        }
+    }
+
+    // Number literals have no type of their own. They take on the numeric type expected where they are used, like `0` in
+    // `let x: u8 = 0;`, and only fall back to `i32` and `f64` without one. Integer literals that do not fit into the
+    // expected integer type are an error. Returns whether the literal took on the type.
+    fn adopt_literal_type(
+        &mut self,
+        expression: &Spanned<Expression<'a>>,
+        expected: &Type<'a>,
+    ) -> bool {
+        let adopts = match expected {
+            Type::Simple(Simple::Integer(integer)) => match integer_literal(&expression.node) {
+                Some(value) => {
+                    // Literals beyond the widest type are reported when they are resolved already.
+                    if !fits(value, *integer) && fits(value.abs(), widest_integer()) {
+                        let error = ResolveErrorType::LiteralOutOfRange(LiteralRangeError(
+                            expected.clone(),
+                        ));
+                        self.error(expression.span, error);
+                    }
+
+                    true
+                }
+                None => false,
+            },
+            Type::Simple(Simple::Float(_)) => is_number_literal(&expression.node),
+
+            _ => false,
+        };
+
+        if adopts {
+            set_literal_type(&expression.node, expected);
+        }
+
+        adopts
+    }
+
+    // Matching on an enum refers to its variants by name, either on their own or qualified like `Color.Red`. Without a
+    // `default`, every variant needs an arm.
+    fn resolve_match(&mut self, statement: &MatchStatement<'a>) {
+        let scrutinee = self.resolve_expression(&statement.scrutinee);
+
+        match scrutinee.and_then(|ty| self.enum_of(&ty)) {
+            Some((name, variants)) => {
+                let mut covered = vec![];
+
+                for (pattern, _) in &statement.arms {
+                    match variant_name(name, pattern) {
+                        Some(variant) if variants.contains(&variant) => covered.push(variant),
+
+                        _ => {
+                            let error = ResolveErrorType::NoSuchVariant(VariantError {
+                                enum_name: name,
+                                variant: self.source.slice(pattern.span),
+                            });
+                            self.error(pattern.span, error);
+                        }
+                    }
+                }
+
+                let missing = variants
+                    .into_iter()
+                    .filter(|variant| !covered.contains(variant))
+                    .collect::<Vec<_>>();
+
+                if statement.default.is_none() {
+                    if missing.is_empty() {
+                        statement.exhaustive.set(true);
+                    } else {
+                        let warning =
+                            ResolveWarningType::NonExhaustiveMatch(NonExhaustiveMatchWarning {
+                                enum_name: name,
+                                missing,
+                            });
+
+                        self.warnings.push(ResolverWarning {
+                            source: self.source,
+                            warning,
+                            warning_span: statement.keyword.span,
+                            expression_span: Span::new(
+                                statement.keyword.span.start,
+                                statement.scrutinee.span.end,
+                            ),
+                        });
+                    }
+                }
+            }
+
+            None => {
+                for (pattern, _) in &statement.arms {
+                    self.resolve_expression(pattern);
+                }
+            }
+        }
+
+        for (_, block) in &statement.arms {
+            self.resolve_block(block);
+        }
+
+        if let Some(default) = &statement.default {
+            self.resolve_block(default);
+        }
+    }
+
+    // The name and the variants of the enum `ty` refers to, if it is one.
+    fn enum_of(&self, ty: &Type<'a>) -> Option<(&'a str, Vec<&'a str>)> {
+        let Type::Simple(Simple::UserDefinedType(identifier)) = ty else {
+            return None;
+        };
+
+        let mut identifier = identifier.clone();
+        let definition = self
+            .module_map
+            .get_user_type(identifier.file(), identifier.name())?;
+
+        if definition.variants.is_empty() {
+            return None;
+        }
+
+        Some((definition.name, definition.variants.clone()))
+    }
+
+    // Enums convert to and from integers only, which is how they are passed to and from C. An integer constant cast to an
+    // enum has to fit into its backing type, and with `checked_enum_casts`, it has to be the value of one of its variants.
+    fn check_enum_cast(
+        &mut self,
+        expression: &Spanned<Expression<'a>>,
+        inner: &Spanned<Expression<'a>>,
+        from: Option<&Type<'a>>,
+        to: &Spanned<Type<'a>>,
+    ) {
+        let Some(from) = from else {
+            return;
+        };
+
+        let from_enum = self.enum_of(from).is_some();
+        let to_enum = self.enum_of(&to.node);
+
+        let allowed = *from == to.node
+            || (from_enum && to.node.is_integer())
+            || (to_enum.is_some() && from.is_integer())
+            || (!from_enum && to_enum.is_none());
+
+        if !allowed {
+            let error = ResolveErrorType::InvalidCast(CastError {
+                from: from.clone(),
+                to: to.node.clone(),
+            });
+            self.error_in(to.span, expression.span, error);
+
+            return;
+        }
+
+        let (Some((name, variants)), Some(backing_type)) = (to_enum, self.backing_type_of(&to.node))
+        else {
+            return;
+        };
+
+        self.adopt_literal_type(inner, &backing_type);
+
+        let Some(value) = integer_literal(&inner.node) else {
+            return;
+        };
+
+        // Values that do not even fit into the backing type are an error already.
+        if let Type::Simple(Simple::Integer(integer)) = backing_type {
+            if !fits(value, integer) {
+                return;
+            }
+        }
+
+        if self.options.checked_enum_casts && !(0..variants.len() as i128).contains(&value) {
+            self.warnings.push(ResolverWarning {
+                source: self.source,
+                warning: ResolveWarningType::EnumCastOutOfRange(EnumCastWarning {
+                    enum_name: name,
+                    value,
+                    variants: variants.len(),
+                }),
+                warning_span: inner.span,
+                expression_span: expression.span,
+            });
+        }
+    }
+
+    fn backing_type_of(&self, ty: &Type<'a>) -> Option<Type<'a>> {
+        let Type::Simple(Simple::UserDefinedType(identifier)) = ty else {
+            return None;
+        };
+
+        let mut identifier = identifier.clone();
+        self.module_map
+            .get_user_type(identifier.file(), identifier.name())?
+            .backing_type
+            .clone()
+    }
+
+    fn resolve_condition(&mut self, condition: &Spanned<Expression<'a>>) {
+        let ty = self.resolve_expression(condition);
+        self.check_assignment_condition(condition);
+        self.check_condition(condition, ty);
+    }
+
+    // Conditions, and the operands of `&&`, `||` and `!`, are `bool`s unless the options allow C's truthiness.
+    fn check_condition(&mut self, condition: &Spanned<Expression<'a>>, ty: Option<Type<'a>>) {
+        let Some(ty) = ty else {
+            return;
+        };
+
+        let truthy = self.options.truthy_conditions && (ty.is_integer() || ty.is_pointer());
+        if ty == Type::Simple(Simple::Bool) || truthy {
+            return;
+        }
+
+        let error = ResolveErrorType::IllegalType(IllegalTypeError {
+            expected_type: Type::Simple(Simple::Bool),
+            actual_type: ty,
+            name: "condition",
+        });
+
+        self.error(condition.span, error);
+    }
+
+    // `if x = 5` is almost always a typo of `==`. Parentheses around a condition are optional, so a single pair is not
+    // a sign of intent either; only a second pair, `if ((x = 5))`, silences the warning.
+    fn check_assignment_condition(&mut self, condition: &Spanned<Expression<'a>>) {
+        let assignment = match condition.node.kind() {
+            ExpressionKind::Grouping(inner) => inner.node.kind(),
+            kind => kind,
+        };
+
+        let ExpressionKind::Assignment { eq, .. } = assignment else {
+            return;
+        };
+
+        self.warnings.push(ResolverWarning {
+            source: self.source,
+            warning: ResolveWarningType::AssignmentInCondition,
+            warning_span: eq.span,
+            expression_span: condition.span,
+        });
+    }
+
+    // A constant condition makes one of the branches dead code.
+    fn check_constant_if(&mut self, statement: &IfStatement<'a>) {
+        let Ok(ConstValue::Bool(condition)) = evaluate(&statement.condition.node) else {
+            return;
+        };
+
+        let (branch, span) = match (condition, statement.else_branch.as_deref()) {
+            (false, _) => ("the body of this 'if'", statement.then_block.1),
+            (true, Some(Else::Block(block))) => ("this 'else' branch", block.1),
+            (true, Some(Else::IfStatement(statement))) => {
+                let Statement::IfStatement(statement) = statement.as_ref() else {
+                    return;
+                };
+
+                let span = Span::new(statement.condition.span.start, statement.then_block.1.end);
+                ("this 'else if' branch", span)
+            }
+            (true, None) => return,
+        };
+
+        self.warnings.push(ResolverWarning {
+            source: self.source,
+            warning: ResolveWarningType::DeadBranch(DeadBranchWarning { branch, condition }),
+            warning_span: span,
+            expression_span: span,
+        });
+    }
+
+    fn check_constant_while(&mut self, statement: &WhileStatement<'a>) {
+        let warning = match evaluate(&statement.condition.node) {
+            Ok(ConstValue::Bool(false)) => ResolveWarningType::DeadBranch(DeadBranchWarning {
+                branch: "the body of this 'while'",
+                condition: false,
+            }),
+            Ok(ConstValue::Bool(true)) if loop_never_ends(statement) => {
+                ResolveWarningType::InfiniteLoop
+            }
+            _ => return,
+        };
+
+        let span = match warning {
+            ResolveWarningType::InfiniteLoop => statement.condition.span,
+            _ => statement.body.1,
+        };
+
+        self.warnings.push(ResolverWarning {
+            source: self.source,
+            warning,
+            warning_span: span,
+            expression_span: span,
+        });
+    }
+
+    // An empty body of a control flow statement is usually a mistake. Function bodies are left alone, since empty
+    // functions are often stubs that are filled in later.
+    fn check_empty_body(&mut self, statement: &'static str, block: &Block<'a>) {
+        if !block.0.is_empty() {
+            return;
+        }
+
+        self.warnings.push(ResolverWarning {
+            source: self.source,
+            warning: ResolveWarningType::EmptyBody(EmptyBodyWarning { statement }),
+            warning_span: block.1,
+            expression_span: block.1,
+        });
+    }
+
+    fn with_label<F>(&mut self, label: Option<Spanned<&'a str>>, is_loop: bool, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.labels.push(Label {
+            name: label.map(|label| label.node),
+            is_loop,
+        });
+
+        f(self);
+
+        self.labels.pop();
+    }
+
+    // Unlabeled `break`/`continue` target the innermost loop. A labeled `break` may leave any enclosing labeled statement,
+    // while a labeled `continue` is only valid for loops.
+    fn resolve_loop_control(&mut self, control: &LoopControl<'a>, is_break: bool) {
+        let Some(label) = control.label else {
+            if !self.labels.iter().any(|label| label.is_loop) {
+                self.error(
+                    control.keyword.span,
+                    ResolveErrorType::OutsideOfLoop(LoopControlError {
+                        keyword: control.keyword.node.clone(),
+                    }),
+                );
+            }
+
+            return;
+        };
+
+        let target = self
+            .labels
+            .iter()
+            .rev()
+            .find(|target| target.name == Some(label.node));
+
+        match target {
+            None => self.error(
+                label.span,
+                ResolveErrorType::UnknownLabel(LabelError { label: label.node }),
+            ),
+
+            Some(target) if !is_break && !target.is_loop => self.error(
+                label.span,
+                ResolveErrorType::ContinueNonLoop(LabelError { label: label.node }),
+            ),
+
+            Some(_) => {}
+        }
+    }
+
+    fn bind(&mut self, name: &Spanned<&'a str>, ty: Type<'a>, is_parameter: bool) {
+        if let Some(locals) = self
+            .current_function
+            .and_then(|key| self.locals.get_mut(&key))
+        {
+            let kind = if is_parameter {
+                SymbolType::Parameter
+            } else {
+                SymbolType::Local
+            };
+
+            locals.push(Spanned::new_from_span(
+                name.span,
+                Symbol::new(name.node, ty.clone(), kind),
+            ));
+        }
+
+        self.symbol_table
+            .bind(name.node, name.span, ty, is_parameter);
+    }
+
+    fn error_limit_reached(&self) -> bool {
+        matches!(self.options.max_errors, Some(max_errors) if self.errors.len() >= max_errors)
+    }
+
+    fn error(&mut self, span: Span, error: ResolveErrorType<'a>) {
+        self.error_in(span, span, error);
+    }
+
+    fn error_in(&mut self, error_span: Span, expression_span: Span, error: ResolveErrorType<'a>) {
+        if self.error_limit_reached() {
+            return;
+        }
+
+        self.errors.push(ResolverError {
+            source: self.source,
+            error,
+            error_span,
+            expression_span,
+        });
+    }
+}
+
+// Numbers convert into each other implicitly as long as every value fits, also as elements of a tuple. Literals that
+// fit are checked on their own, so `let x: u8 = 1;` is fine, but an `i32` variable is not.
+fn compatible<'a>(ty: &Type<'a>, expected: &Type<'a>) -> bool {
+    match (ty, expected) {
+        (Type::Complex(Complex::Tuple(ty)), Type::Complex(Complex::Tuple(expected))) => {
+            ty.elements().len() == expected.elements().len()
+                && ty
+                    .elements()
+                    .iter()
+                    .zip(expected.elements())
+                    .all(|(ty, expected)| compatible(ty, expected))
+        }
+
+        (Type::Complex(Complex::Array(ty)), Type::Complex(Complex::Array(expected))) => {
+            same_array(ty, expected)
+        }
+        (Type::Simple(ty), Type::Simple(expected)) => widens(ty, expected),
+
+        _ => ty == expected,
+    }
+}
+
+// Array sizes are expressions, which only have to agree on their value. The elements have to be of the same type, as
+// they are laid out one after the other.
+fn same_array<'a>(ty: &Array<'a>, expected: &Array<'a>) -> bool {
+    let same_elements = match (ty.base_type(), expected.base_type()) {
+        (Type::Complex(Complex::Array(ty)), Type::Complex(Complex::Array(expected))) => same_array(ty, expected),
+        (ty, expected) => ty == expected,
+    };
+
+    same_elements && ty.is_unsized() == expected.is_unsized() && ty.length() == expected.length()
+}
+
+// Whether every value of `ty` is a value of `expected` as well. Integers only go to wider integers of the same sign, or
+// to wider signed ones, and to floats with enough digits to hold them exactly.
+fn widens(ty: &Simple, expected: &Simple) -> bool {
+    match (ty, expected) {
+        (&Simple::Integer(mut ty), &Simple::Integer(mut expected)) => {
+            match (ty.signed(), expected.signed()) {
+                (true, false) => false,
+                (false, true) => ty.size() < expected.size(),
+                _ => ty.size() <= expected.size(),
+            }
+        }
+        (&Simple::Integer(mut ty), &Simple::Float(mut expected)) => {
+            let digits = if expected.size() == 32 { 24 } else { 53 };
+            ty.size() < digits
+        }
+        (&Simple::Float(mut ty), &Simple::Float(mut expected)) => ty.size() <= expected.size(),
+
+        _ => ty == expected,
+    }
+}
+
+// Literals can be negated or put in parentheses and still count as literals, so `-(1)` becomes a `u8` as well.
+fn is_number_literal(expression: &Expression) -> bool {
+    match expression.kind() {
+        ExpressionKind::DecLiteral(_) | ExpressionKind::FloatLiteral(_) => true,
+        ExpressionKind::Grouping(inner) | ExpressionKind::Negate(_, inner) => {
+            is_number_literal(&inner.node)
+        }
+        _ => false,
+    }
+}
+
+fn function_definition<'a>(top_level: &TopLevel<'a>) -> Option<FunctionDefinition<'a>> {
+    let TopLevel::FunctionDeclaration {
+        name,
+        arguments,
+        return_type,
+        attributes,
+        is_public,
+        ..
+    } = top_level
+    else {
+        return None;
+    };
+
+    Some(FunctionDefinition {
+        name: name.node,
+        span: name.span,
+        return_type: return_type.clone(),
+        parameters: arguments
+            .parameters
+            .iter()
+            .map(|Parameter(_, ty)| ty.clone())
+            .collect(),
+        varargs: arguments.varargs,
+        must_use: attributes
+            .iter()
+            .any(|attribute| attribute.name.node == "must_use"),
+        is_public: *is_public,
+    })
+}
+
+// A method that takes `self` as its first parameter, as seen by a call like `self.method()`, which passes `self`
+// implicitly.
+fn method_definition<'a>(method: &TopLevel<'a>) -> Option<(&'a str, FunctionDefinition<'a>)> {
+    let TopLevel::FunctionDeclaration { arguments, .. } = method else {
+        return None;
+    };
+
+    match arguments.parameters.first() {
+        Some(Parameter(name, _)) if name.node == SELF => {}
+        _ => return None,
+    }
+
+    let mut definition = function_definition(method)?;
+    definition.parameters.remove(0);
+
+    Some((definition.name, definition))
+}
+
+// The widest integer type there is. Integer literals that do not fit into it are an error, whatever type is expected.
+fn widest_integer() -> Integer {
+    Integer::new_unsigned_int(64)
+}
+
+// The value of an integer literal, or `None` for any other expression. Literals too large for an `i128` are left to the
+// default type, which they do not fit into either.
+fn integer_literal(expression: &Expression) -> Option<i128> {
+    match expression.kind() {
+        ExpressionKind::DecLiteral(literal) => literal.parse().ok(),
+        ExpressionKind::Grouping(inner) => integer_literal(&inner.node),
+        ExpressionKind::Negate(_, inner) => integer_literal(&inner.node)?.checked_neg(),
+        _ => None,
+    }
+}
+
+fn set_literal_type<'a>(expression: &Expression<'a>, ty: &Type<'a>) {
+    expression.set_ty(ty.clone());
+
+    if let ExpressionKind::Grouping(inner) | ExpressionKind::Negate(_, inner) = expression.kind() {
+        set_literal_type(&inner.node, ty);
+    }
+}
+
+// The variant a `case` refers to, either `Red` or `Color.Red` for an enum named `Color`.
+fn variant_name<'a>(enum_name: &str, pattern: &Spanned<Expression<'a>>) -> Option<&'a str> {
+    match pattern.node.kind() {
+        ExpressionKind::Identifier(variant) => Some(variant),
+
+        ExpressionKind::Access { left, identifier } => match left.node.kind() {
+            ExpressionKind::Identifier(name) if *name == enum_name => Some(identifier.node),
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+// Every generic type within `ty`, however deeply it is nested, whose number of type arguments is wrong.
+fn type_argument_errors<'a>(
+    ty: &Type<'a>,
+    module_map: &ModuleMap<'a>,
+    errors: &mut Vec<TypeArgumentCountError<'a>>,
+) {
+    match ty {
+        Type::Simple(_) => {}
+
+        Type::Complex(Complex::Generic(generic)) => {
+            if let Type::Simple(Simple::UserDefinedType(identifier)) = generic.base_type() {
+                let mut identifier = identifier.clone();
+                let definition = module_map.get_user_type(identifier.file(), identifier.name());
+
+                if let Some(definition) = definition {
+                    if definition.generic_parameters.len() != generic.arguments().len() {
+                        errors.push(TypeArgumentCountError {
+                            name: definition.name,
+                            expected: definition.generic_parameters.len(),
+                            actual: generic.arguments().len(),
+                        });
+                    }
+                }
+            }
+
+            for argument in generic.arguments() {
+                type_argument_errors(argument, module_map, errors);
+            }
+        }
+
+        Type::Complex(Complex::Pointer(pointer)) => {
+            type_argument_errors(pointer.base_type(), module_map, errors)
+        }
+        Type::Complex(Complex::Ref(reference)) => {
+            type_argument_errors(reference.base_type(), module_map, errors)
+        }
+        Type::Complex(Complex::Array(array)) => {
+            type_argument_errors(array.base_type(), module_map, errors)
+        }
+        Type::Complex(Complex::Function(function)) => {
+            for ty in function.parameters().iter().chain(std::iter::once(function.return_type())) {
+                type_argument_errors(ty, module_map, errors);
+            }
+        }
+        Type::Complex(Complex::Tuple(tuple)) => {
+            for element in tuple.elements() {
+                type_argument_errors(element, module_map, errors);
+            }
+        }
+
+        Type::Nullable(nullable) => type_argument_errors(nullable.inner_type(), module_map, errors),
+    }
+}
+
+// Whether the array `ty` can be passed where `expected`, a pointer to its elements or an unsized array of them, is.
+fn decays_to<'a>(ty: &Type<'a>, expected: &Type<'a>) -> bool {
+    let Type::Complex(Complex::Array(array)) = ty else {
+        return false;
+    };
+
+    match expected {
+        Type::Complex(Complex::Pointer(pointer)) => {
+            pointer.size() == 1 && pointer.base_type() == array.base_type()
+        }
+        Type::Complex(Complex::Array(unsized_array)) => {
+            unsized_array.is_unsized() && unsized_array.base_type() == array.base_type()
+        }
+
+        _ => false,
+    }
+}
@@ -0,0 +1,282 @@
+/*
+ * Memory layout of Newton types: their size, their alignment and the offsets of struct fields.
+ *
+ * The layout depends on the target, so everything here is computed against a `DataLayout`. Structs are laid out in
+ * declaration order, C-style, with every field aligned to its natural alignment and the total size padded to a multiple
+ * of the struct's alignment. `@[packed]` structs align every field to a single byte instead, and `@[align(N)]` raises
+ * the alignment of a struct to at least `N`.
+ *
+ * Newton (C) 2023
+ */
+
+use super::types::*;
+use crate::semantic::modulemap::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DataLayout {
+    // Size (and alignment) of pointers, in bytes.
+    pub pointer_size: u64,
+}
+
+impl DataLayout {
+    pub fn new(pointer_size: u64) -> Self {
+        Self { pointer_size }
+    }
+}
+
+impl Default for DataLayout {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+}
+
+impl Layout {
+    pub fn new(size: u64, align: u64) -> Self {
+        Self { size, align }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout<'a> {
+    pub layout: Layout,
+    // The byte offset of each field, in declaration order.
+    pub offsets: Vec<(&'a str, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError<'a> {
+    // The struct contains itself by value, so it would be infinitely large.
+    RecursiveType(UserIdentifier<'a>),
+    UnknownType(UserIdentifier<'a>),
+    NonConstantArraySize(Type<'a>),
+    NoLayout(Type<'a>),
+}
+
+impl<'a> std::fmt::Display for LayoutError<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::RecursiveType(ty) => write!(f, "recursive type '{}' has infinite size", ty),
+            Self::UnknownType(ty) => {
+                write!(f, "cannot compute the layout of unknown type '{}'", ty)
+            }
+            Self::NonConstantArraySize(ty) => {
+                write!(f, "the size of array type '{}' is not a constant", ty)
+            }
+            Self::NoLayout(ty) => write!(f, "type '{}' has no layout", ty),
+        }
+    }
+}
+
+pub type LayoutResult<'a, T> = Result<T, LayoutError<'a>>;
+
+pub struct LayoutComputer<'a, 'b> {
+    data_layout: DataLayout,
+    module_map: &'b ModuleMap<'a>,
+
+    // Structs whose layout is being computed right now; seeing one of them again means the type is recursive.
+    in_progress: Vec<UserIdentifier<'a>>,
+}
+
+impl<'a, 'b> LayoutComputer<'a, 'b> {
+    pub fn new(data_layout: DataLayout, module_map: &'b ModuleMap<'a>) -> Self {
+        Self {
+            data_layout,
+            module_map,
+            in_progress: vec![],
+        }
+    }
+
+    pub fn layout_of(&mut self, ty: &Type<'a>) -> LayoutResult<'a, Layout> {
+        let pointer = Layout::new(self.data_layout.pointer_size, self.data_layout.pointer_size);
+
+        match ty {
+            Type::Simple(simple) => self.simple_layout_of(simple),
+
+            Type::Complex(Complex::Pointer(_))
+            | Type::Complex(Complex::Ref(_))
+            | Type::Complex(Complex::Function(_)) => Ok(pointer),
+
+            // Unsized arrays are only ever handled through a pointer to their first element.
+            Type::Complex(Complex::Array(array)) => {
+                if array.is_unsized() {
+                    return Ok(pointer);
+                }
+
+                let Some(length) = array.length() else {
+                    return Err(LayoutError::NonConstantArraySize(ty.clone()));
+                };
+
+                let element = self.layout_of(array.base_type())?;
+
+                Ok(Layout::new(element.size * length, element.align))
+            }
+
+            // Tuples are laid out like a struct with one field per element.
+            Type::Complex(Complex::Tuple(tuple)) => {
+                let mut offset = 0;
+                let mut align = 1;
+
+                for element in tuple.elements() {
+                    let element = self.layout_of(element)?;
+
+                    offset = align_to(offset, element.align) + element.size;
+                    align = align.max(element.align);
+                }
+
+                Ok(Layout::new(align_to(offset, align), align))
+            }
+
+            // Instances are laid out like their struct, with the type arguments in place of the type parameters.
+            Type::Complex(Complex::Generic(generic)) => match generic.base_type() {
+                Type::Simple(Simple::UserDefinedType(identifier)) => Ok(self
+                    .instance_layout_of(identifier, generic.arguments())?
+                    .layout),
+                _ => Err(LayoutError::NoLayout(ty.clone())),
+            },
+
+            // Nullable pointers use null as their empty value, everything else carries an additional flag.
+            Type::Nullable(nullable) => {
+                let inner = nullable.inner_type();
+                if let Type::Simple(Simple::String)
+                | Type::Complex(Complex::Pointer(_) | Complex::Ref(_) | Complex::Function(_)) =
+                    inner
+                {
+                    return Ok(pointer);
+                }
+
+                let inner = self.layout_of(inner)?;
+
+                Ok(Layout::new(
+                    align_to(inner.size + 1, inner.align),
+                    inner.align,
+                ))
+            }
+        }
+    }
+
+    fn simple_layout_of(&mut self, ty: &Simple<'a>) -> LayoutResult<'a, Layout> {
+        match ty {
+            Simple::Integer(integer) => {
+                let bytes = { *integer }.size() as u64 / 8;
+                Ok(Layout::new(bytes, bytes))
+            }
+
+            Simple::Float(float) => {
+                let bytes = { *float }.size() as u64 / 8;
+                Ok(Layout::new(bytes, bytes))
+            }
+
+            Simple::Character | Simple::Bool => Ok(Layout::new(1, 1)),
+            Simple::Void => Ok(Layout::new(0, 1)),
+
+            Simple::String => Ok(Layout::new(
+                self.data_layout.pointer_size,
+                self.data_layout.pointer_size,
+            )),
+
+            Simple::UserDefinedType(identifier) => Ok(self.struct_layout_of(identifier)?.layout),
+
+            // Type parameters have no layout until they are replaced by the arguments of an instance.
+            Simple::TypeParameter(_) | Simple::VarArgs => {
+                Err(LayoutError::NoLayout(Type::Simple(ty.clone())))
+            }
+        }
+    }
+
+    pub fn struct_layout_of(
+        &mut self,
+        identifier: &UserIdentifier<'a>,
+    ) -> LayoutResult<'a, StructLayout<'a>> {
+        self.instance_layout_of(identifier, &[])
+    }
+
+    // The layout of the struct `identifier` with `arguments` for its type parameters. Without arguments, the layout of
+    // a generic struct is only known if none of its fields depend on its type parameters.
+    pub fn instance_layout_of(
+        &mut self,
+        identifier: &UserIdentifier<'a>,
+        arguments: &[Type<'a>],
+    ) -> LayoutResult<'a, StructLayout<'a>> {
+        if self.in_progress.contains(identifier) {
+            return Err(LayoutError::RecursiveType(identifier.clone()));
+        }
+
+        let mut key = identifier.clone();
+        let Some(definition) = self.module_map.get_user_type(key.file(), key.name()) else {
+            return Err(LayoutError::UnknownType(identifier.clone()));
+        };
+
+        self.in_progress.push(identifier.clone());
+
+        let mut offset = 0;
+        let mut align = 1;
+        let mut offsets = Vec::with_capacity(definition.field_order.len());
+
+        for name in &definition.field_order {
+            let field = definition.fields[name]
+                .1
+                .node
+                .substitute(&definition.generic_parameters, arguments);
+
+            let field = match self.layout_of(&field) {
+                Ok(field) => field,
+                Err(error) => {
+                    self.in_progress.pop();
+                    return Err(error);
+                }
+            };
+
+            let field_align = if definition.packed { 1 } else { field.align };
+
+            offset = align_to(offset, field_align);
+            offsets.push((*name, offset));
+
+            offset += field.size;
+            align = align.max(field_align);
+        }
+
+        self.in_progress.pop();
+
+        if let Some(minimum) = definition.align {
+            align = align.max(minimum);
+        }
+
+        Ok(StructLayout {
+            layout: Layout::new(align_to(offset, align), align),
+            offsets,
+        })
+    }
+}
+
+// Round `offset` up to the next multiple of `align`.
+pub fn align_to(offset: u64, align: u64) -> u64 {
+    offset.div_ceil(align) * align
+}
+
+impl<'a> Type<'a> {
+    pub fn size_of(
+        &self,
+        data_layout: DataLayout,
+        module_map: &ModuleMap<'a>,
+    ) -> LayoutResult<'a, u64> {
+        Ok(LayoutComputer::new(data_layout, module_map)
+            .layout_of(self)?
+            .size)
+    }
+
+    pub fn align_of(
+        &self,
+        data_layout: DataLayout,
+        module_map: &ModuleMap<'a>,
+    ) -> LayoutResult<'a, u64> {
+        Ok(LayoutComputer::new(data_layout, module_map)
+            .layout_of(self)?
+            .align)
+    }
+}
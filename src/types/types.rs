@@ -1,4 +1,5 @@
 use crate::ast::ast::*;
+use crate::semantic::consteval::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct UserIdentifier<'a> {
@@ -26,7 +27,7 @@ impl<'a> std::fmt::Display for UserIdentifier<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type<'a> {
     Simple(Simple<'a>),
     Complex(Complex<'a>),
@@ -34,15 +35,19 @@ pub enum Type<'a> {
 }
 
 impl<'a> Type<'a> {
+    // Strings are lowered to pointers by the backends, but are not pointers to Newton, so there is no arithmetic on
+    // them. `is_string` tells them apart.
     pub fn is_pointer(&self) -> bool {
         match self {
-            Type::Complex(Complex::Array(_))
-            | Type::Complex(Complex::Pointer(_))
-            | Type::Simple(Simple::String) => true,
+            Type::Complex(Complex::Array(_)) | Type::Complex(Complex::Pointer(_)) => true,
             _ => false,
         }
     }
 
+    pub fn is_string(&self) -> bool {
+        matches!(self, Type::Simple(Simple::String))
+    }
+
     pub fn is_integer(&self) -> bool {
         match self {
             Type::Simple(Simple::Integer(_)) => true,
@@ -78,14 +83,87 @@ impl<'a> Type<'a> {
     pub fn simple(&self) -> &Simple {
         match self {
             Type::Simple(ty) => ty,
-            Type::Complex(Complex::Array(arr)) => &arr.base_type,
-            Type::Complex(Complex::Pointer(ptr)) => &ptr.base_type,
-            Type::Complex(Complex::Ref(_ref)) => &_ref.base_type,
-            Type::Nullable(nullable) => &nullable.inner_type,
+            Type::Complex(Complex::Array(arr)) => arr.base_type.simple(),
+            Type::Complex(Complex::Pointer(ptr)) => ptr.base_type.simple(),
+            Type::Complex(Complex::Ref(_ref)) => _ref.base_type.simple(),
+            Type::Complex(Complex::Function(function)) => function.return_type.simple(),
+            Type::Complex(Complex::Generic(generic)) => generic.base_type.simple(),
+            // Tuples have no single base type.
+            Type::Complex(Complex::Tuple(_)) => &Simple::Void,
+            Type::Nullable(nullable) => nullable.inner_type.simple(),
         }
     }
 
-    pub fn arithmetic(&mut self) -> bool {
+    // A copy of the type where every type parameter in `parameters` is replaced by the argument at the same position,
+    // which is how the types of a generic struct become the types of one of its instances.
+    pub fn substitute(&self, parameters: &[&str], arguments: &[Type<'a>]) -> Type<'a> {
+        self.replace_simple(&|simple| match simple {
+            Simple::TypeParameter(name) => parameters
+                .iter()
+                .position(|parameter| parameter == name)
+                .and_then(|index| arguments.get(index))
+                .cloned(),
+            _ => None,
+        })
+    }
+
+    // A copy of the type where every simple type `replace` returns a replacement for is replaced, however deeply it is
+    // nested. Replacements are not looked into again.
+    pub fn replace_simple<F>(&self, replace: &F) -> Type<'a>
+    where
+        F: Fn(&Simple<'a>) -> Option<Type<'a>>,
+    {
+        match self {
+            Type::Simple(simple) => replace(simple).unwrap_or_else(|| self.clone()),
+
+            Type::Complex(Complex::Pointer(pointer)) => Type::Complex(Complex::Pointer(Pointer::new(
+                pointer.base_type.replace_simple(replace),
+                pointer.size,
+            ))),
+            Type::Complex(Complex::Ref(reference)) => Type::Complex(Complex::Ref(Ref::new(
+                reference.base_type.replace_simple(replace),
+                reference.size,
+            ))),
+            Type::Complex(Complex::Array(array)) => Type::Complex(Complex::Array(Array::new(
+                array.base_type.replace_simple(replace),
+                array.size.clone(),
+            ))),
+            Type::Complex(Complex::Function(function)) => {
+                Type::Complex(Complex::Function(Function::new(
+                    function
+                        .parameters
+                        .iter()
+                        .map(|parameter| parameter.replace_simple(replace))
+                        .collect(),
+                    function.return_type.replace_simple(replace),
+                )))
+            }
+            Type::Complex(Complex::Tuple(tuple)) => Type::Complex(Complex::Tuple(Tuple::new(
+                tuple
+                    .elements
+                    .iter()
+                    .map(|element| element.replace_simple(replace))
+                    .collect(),
+            ))),
+            // The base of an instance always is the generic struct itself, only its arguments can change.
+            Type::Complex(Complex::Generic(generic)) => {
+                Type::Complex(Complex::Generic(Generic::new(
+                    generic.base_type.as_ref().clone(),
+                    generic
+                        .arguments
+                        .iter()
+                        .map(|argument| argument.replace_simple(replace))
+                        .collect(),
+                )))
+            }
+
+            Type::Nullable(nullable) => Type::Nullable(Nullable::new(
+                nullable.inner_type.replace_simple(replace),
+            )),
+        }
+    }
+
+    pub fn arithmetic(&self) -> bool {
         if self.is_pointer() {
             true
         } else if let Type::Simple(ty) = self {
@@ -106,7 +184,52 @@ impl<'a> std::fmt::Display for Type<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+// The type arithmetic on two numbers results in: a float beats an integer, and the wider of two floats or two integers
+// wins. An unsigned and a signed integer of the same width become unsigned, just like in C. Anything that is not a
+// pair of numbers has no common type.
+pub fn promote<'a>(left: &Type<'a>, right: &Type<'a>) -> Option<Type<'a>> {
+    let promoted = match (left, right) {
+        (Type::Simple(Simple::Float(l)), Type::Simple(Simple::Float(r))) => {
+            if l.size >= r.size {
+                left
+            } else {
+                right
+            }
+        }
+        (Type::Simple(Simple::Float(_)), Type::Simple(Simple::Integer(_))) => left,
+        (Type::Simple(Simple::Integer(_)), Type::Simple(Simple::Float(_))) => right,
+
+        (Type::Simple(Simple::Integer(l)), Type::Simple(Simple::Integer(r))) => {
+            if l.size > r.size || (l.size == r.size && !l.signed) {
+                left
+            } else {
+                right
+            }
+        }
+
+        _ => return None,
+    };
+
+    Some(promoted.clone())
+}
+
+// Whether an integer of one signedness is mixed with one of the other, and the promoted type cannot hold every value of
+// both. Unsigned integers narrower than the signed one always fit, like a `u8` in an `i32`.
+pub fn loses_sign(left: &Type, right: &Type) -> bool {
+    let (Type::Simple(Simple::Integer(l)), Type::Simple(Simple::Integer(r))) = (left, right) else {
+        return false;
+    };
+
+    let (signed, unsigned) = match (l.signed, r.signed) {
+        (true, false) => (l, r),
+        (false, true) => (r, l),
+        _ => return false,
+    };
+
+    unsigned.size >= signed.size
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Simple<'a> {
     String,
     Integer(Integer),
@@ -115,11 +238,14 @@ pub enum Simple<'a> {
     Void,
     Bool,
     UserDefinedType(UserIdentifier<'a>),
+    // A type parameter of a generic struct, like `K` in `Pair<K, V>`. It only becomes a concrete type in an instance
+    // of the struct.
+    TypeParameter(&'a str),
     VarArgs,
 }
 
 impl<'a> Simple<'a> {
-    pub fn arithmetic(&mut self) -> bool {
+    pub fn arithmetic(&self) -> bool {
         match self {
             Simple::Integer(_) | Simple::Float(_) | Simple::Character => true,
             _ => false,
@@ -139,6 +265,7 @@ impl<'a> std::fmt::Display for Simple<'a> {
             Self::Integer(ty) => write!(f, "{}", ty),
             Self::Float(ty) => write!(f, "{}", ty),
             Self::UserDefinedType(ty) => write!(f, "{}", ty),
+            Self::TypeParameter(name) => write!(f, "{}", name),
         }
     }
 }
@@ -182,7 +309,7 @@ impl std::fmt::Display for Integer {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Float {
     size: u8,
 }
@@ -213,21 +340,21 @@ impl std::fmt::Display for Float {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Nullable<'a> {
-    inner_type: Simple<'a>,
+    inner_type: Box<Type<'a>>,
 }
 
 impl<'a> Nullable<'a> {
-    pub fn new(inner_type: Simple<'a>) -> Self {
+    pub fn new(inner_type: Type<'a>) -> Self {
         Self {
-            inner_type
+            inner_type: Box::new(inner_type),
         }
     }
 
     #[inline]
-    pub fn inner_type(&mut self) -> Simple<'a> {
-        self.inner_type.clone()
+    pub fn inner_type(&self) -> &Type<'a> {
+        &self.inner_type
     }
 }
 
@@ -237,11 +364,14 @@ impl<'a> std::fmt::Display for Nullable<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Complex<'a> {
     Pointer(Pointer<'a>),
     Ref(Ref<'a>),
     Array(Array<'a>),
+    Function(Function<'a>),
+    Tuple(Tuple<'a>),
+    Generic(Generic<'a>),
 }
 
 impl<'a> std::fmt::Display for Complex<'a> {
@@ -250,23 +380,42 @@ impl<'a> std::fmt::Display for Complex<'a> {
             Self::Pointer(ptr) => write!(f, "{}", ptr),
             Self::Ref(_ref) => write!(f, "{}", _ref),
             Self::Array(arr) => write!(f, "{}", arr),
+            Self::Function(function) => write!(f, "{}", function),
+            Self::Tuple(tuple) => write!(f, "{}", tuple),
+            Self::Generic(generic) => write!(f, "{}", generic),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Pointer<'a> {
-    base_type: Simple<'a>,
+    // Never a `Pointer` itself, `size` counts the levels of indirection instead.
+    base_type: Box<Type<'a>>,
     size: u8,
 }
 
 impl<'a> Pointer<'a> {
-    pub fn new(base_type: Simple<'a>, size: u8) -> Self {
-        if size > 2 {
+    pub const MAX_SIZE: u8 = 2;
+
+    pub fn new(base_type: Type<'a>, size: u8) -> Self {
+        if size > Self::MAX_SIZE {
             panic!("ERROR : pointer cannot be more than `**` long.")
         }
 
-        Self { base_type, size }
+        Self {
+            base_type: Box::new(base_type),
+            size,
+        }
+    }
+
+    #[inline]
+    pub fn base_type(&self) -> &Type<'a> {
+        &self.base_type
+    }
+
+    #[inline]
+    pub fn size(&self) -> u8 {
+        self.size
     }
 }
 
@@ -276,19 +425,35 @@ impl<'a> std::fmt::Display for Pointer<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Ref<'a> {
-    base_type: Simple<'a>,
+    // Never a `Ref` itself, `size` counts the levels of indirection instead.
+    base_type: Box<Type<'a>>,
     size: u8,
 }
 
 impl<'a> Ref<'a> {
-    pub fn new(base_type: Simple<'a>, size: u8) -> Self {
-        if size > 2 {
+    pub const MAX_SIZE: u8 = 2;
+
+    pub fn new(base_type: Type<'a>, size: u8) -> Self {
+        if size > Self::MAX_SIZE {
             panic!("ERROR : ref cannot be more than `&&` long.");
         }
 
-        Self { base_type, size }
+        Self {
+            base_type: Box::new(base_type),
+            size,
+        }
+    }
+
+    #[inline]
+    pub fn base_type(&self) -> &Type<'a> {
+        &self.base_type
+    }
+
+    #[inline]
+    pub fn size(&self) -> u8 {
+        self.size
     }
 }
 
@@ -298,19 +463,23 @@ impl<'a> std::fmt::Display for Ref<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Array<'a> {
-    base_type: Simple<'a>,
+    // Arrays of arrays are multi-dimensional, `[3][4]i32` being three arrays of four integers each.
+    base_type: Box<Type<'a>>,
     size: Box<Option<Expression<'a>>>,
 }
 
 impl<'a> Array<'a> {
-    pub fn new(base_type: Simple<'a>, size: Box<Option<Expression<'a>>>) -> Self {
-        Self { base_type, size }
+    pub fn new(base_type: Type<'a>, size: Box<Option<Expression<'a>>>) -> Self {
+        Self {
+            base_type: Box::new(base_type),
+            size,
+        }
     }
 
     #[inline]
-    pub fn base_type(&mut self) -> &Simple {
+    pub fn base_type(&self) -> &Type<'a> {
         &self.base_type
     }
 
@@ -318,26 +487,146 @@ impl<'a> Array<'a> {
     pub fn size(&mut self) -> Expression<'a> {
         self.size.clone().unwrap()
     }
+
+    #[inline]
+    pub fn is_unsized(&self) -> bool {
+        self.size.is_none()
+    }
+
+    // Number of elements in the outermost dimension, if it is known at compile time.
+    pub fn length(&self) -> Option<u64> {
+        match evaluate(self.size.as_ref().as_ref()?) {
+            Ok(ConstValue::Integer(length)) => u64::try_from(length).ok(),
+            _ => None,
+        }
+    }
+
+    // Number of scalar elements across all dimensions, so 12 for `[3][4]i32`.
+    pub fn element_count(&self) -> Option<u64> {
+        let inner = match self.base_type.as_ref() {
+            Type::Complex(Complex::Array(array)) => array.element_count()?,
+            _ => 1,
+        };
+
+        self.length()?.checked_mul(inner)
+    }
 }
 
+// Sizes known at compile time are printed folded, since the type only depends on their value.
 impl<'a> std::fmt::Display for Array<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self.size.clone().unwrap().kind() {
-            ExpressionKind::DecLiteral(lit) => {
-                match lit {
-                    c if lit.parse::<u64>().is_ok() => {
-                        write!(f, "[{}]{}", c, self.base_type)
-                    }
-
-                    &"*" => {
-                        write!(f, "[*]{}", self.base_type)
-                    }
-
-                    _ => panic!("attempted to use non numerical value as array size")
-                }
-            }
+        match (self.length(), self.size.as_ref()) {
+            (Some(length), _) => write!(f, "[{}]{}", length, self.base_type),
+            (None, Some(size)) => write!(f, "[{}]{}", size, self.base_type),
+            (None, None) => write!(f, "[?]{}", self.base_type),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Function<'a> {
+    parameters: Vec<Type<'a>>,
+    return_type: Box<Type<'a>>,
+}
+
+impl<'a> Function<'a> {
+    pub fn new(parameters: Vec<Type<'a>>, return_type: Type<'a>) -> Self {
+        Self {
+            parameters,
+            return_type: Box::new(return_type),
+        }
+    }
+
+    #[inline]
+    pub fn parameters(&self) -> &[Type<'a>] {
+        &self.parameters
+    }
+
+    #[inline]
+    pub fn return_type(&self) -> &Type<'a> {
+        &self.return_type
+    }
+}
+
+impl<'a> std::fmt::Display for Function<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|parameter| parameter.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "fn({}) => {}", parameters, self.return_type)
+    }
+}
 
-            _ => panic!("invalid array size")
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tuple<'a> {
+    // Always at least two, a single type in parentheses is just that type.
+    elements: Vec<Type<'a>>,
+}
+
+impl<'a> Tuple<'a> {
+    pub fn new(elements: Vec<Type<'a>>) -> Self {
+        Self { elements }
+    }
+
+    #[inline]
+    pub fn elements(&self) -> &[Type<'a>] {
+        &self.elements
+    }
+}
+
+impl<'a> std::fmt::Display for Tuple<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let elements = self
+            .elements
+            .iter()
+            .map(|element| element.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "({})", elements)
+    }
+}
+
+// An instance of a generic struct, like `Pair<i32, string>`, with one type argument per type parameter of the struct.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Generic<'a> {
+    // Always the user defined type of the struct.
+    base_type: Box<Type<'a>>,
+    arguments: Vec<Type<'a>>,
+}
+
+impl<'a> Generic<'a> {
+    pub fn new(base_type: Type<'a>, arguments: Vec<Type<'a>>) -> Self {
+        Self {
+            base_type: Box::new(base_type),
+            arguments,
         }
     }
+
+    #[inline]
+    pub fn base_type(&self) -> &Type<'a> {
+        &self.base_type
+    }
+
+    #[inline]
+    pub fn arguments(&self) -> &[Type<'a>] {
+        &self.arguments
+    }
+}
+
+impl<'a> std::fmt::Display for Generic<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| argument.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        write!(f, "{}<{}>", self.base_type, arguments)
+    }
 }
@@ -0,0 +1,422 @@
+mod common;
+
+use common::*;
+use newton_rs::ast::ast::*;
+use newton_rs::codegen::api::*;
+use newton_rs::codegen::backends::c::*;
+use newton_rs::codegen::backends::llvm::*;
+use newton_rs::error::diagnostic::*;
+use newton_rs::semantic::typecheck::*;
+
+// Resolve `code` and lower it with `backend`, returning the output along with the messages of every diagnostic.
+fn generate(backend: &mut dyn Backend, code: &str) -> (String, Vec<String>) {
+    let source = source(code);
+    let program = parse(&source);
+    Resolver::new(&source).resolve(&program);
+
+    let mut diagnostics = DiagnosticSink::new();
+    let output = generate_program(backend, &source.name, &program, &mut diagnostics);
+    let messages = diagnostics.into_diagnostics().into_iter().map(|diagnostic| diagnostic.message).collect();
+
+    (output, messages)
+}
+
+#[test]
+fn finish_returns_the_generated_source() {
+    let source = source("fn answer() => i32 { return 42; }");
+    let program = parse(&source);
+
+    let mut backend = C::new();
+    let mut diagnostics = DiagnosticSink::new();
+    backend.generate_header("main");
+    backend.generate_function(&program.0[0], &mut diagnostics);
+
+    let generated = backend.source().clone();
+    let output = backend.finish();
+
+    assert!(output.contains("int32_t answer(void);"));
+    assert_eq!(output, generated);
+    assert!(backend.source().is_empty());
+}
+
+#[test]
+fn unknown_backends_are_not_found() {
+    let mut api = BackendAPI::new();
+    api.register("c", std::rc::Rc::new(std::cell::RefCell::new(C::new()))).unwrap();
+
+    assert!(api.get("c").is_some());
+    assert!(api.get("fortran").is_none());
+}
+
+#[test]
+fn backends_are_found_by_every_alias() {
+    let mut api = BackendAPI::new();
+    let backend: std::rc::Rc<std::cell::RefCell<dyn Backend>> = std::rc::Rc::new(std::cell::RefCell::new(C::new()));
+    api.register_aliases(&["c", "gcc"], backend.clone()).unwrap();
+
+    assert!(std::rc::Rc::ptr_eq(&api.get("c").unwrap(), &backend));
+    assert!(std::rc::Rc::ptr_eq(&api.get("gcc").unwrap(), &backend));
+}
+
+#[test]
+fn aliases_cannot_be_registered_twice() {
+    let mut api = BackendAPI::new();
+    api.register_aliases(&["c"], std::rc::Rc::new(std::cell::RefCell::new(C::new()))).unwrap();
+
+    let error = api
+        .register_aliases(&["clang", "c"], std::rc::Rc::new(std::cell::RefCell::new(C::new())))
+        .unwrap_err();
+
+    assert_eq!(error, BackendAPIError::DuplicateAlias("c".to_owned()));
+    assert!(api.get("clang").is_none());
+}
+
+#[test]
+fn names_taken_by_an_alias_cannot_be_registered() {
+    let mut api = BackendAPI::new();
+    let backend: std::rc::Rc<std::cell::RefCell<dyn Backend>> = std::rc::Rc::new(std::cell::RefCell::new(C::new()));
+    api.register_aliases(&["c", "gcc"], backend.clone()).unwrap();
+
+    let error = api.register("c", std::rc::Rc::new(std::cell::RefCell::new(C::new()))).unwrap_err();
+
+    assert_eq!(error, BackendAPIError::DuplicateAlias("c".to_owned()));
+    assert!(std::rc::Rc::ptr_eq(&api.get("c").unwrap(), &backend));
+}
+
+#[test]
+fn llvm_lowers_integer_arithmetic() {
+    let (output, diagnostics) = generate(&mut LLVM::new(), "fn plus_two(a: i32) => i32 { return a + 2; }");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("define i32 @plus_two(i32 %a) {"));
+    assert!(output.contains("  %t0 = add i32 %a, 2\n"));
+    assert!(output.contains("  ret i32 %t0\n"));
+}
+
+#[test]
+fn llvm_folds_constant_arithmetic() {
+    let code = "fn f(a: i32) => i32 { let b = (1 + 2) * 4; return a * (10 - 8) + b; }";
+    let (output, diagnostics) = generate(&mut LLVM::new(), code);
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("  %t0 = mul i32 %a, 2\n"));
+    assert!(output.contains("  %t1 = add i32 %t0, 12\n"));
+    assert!(output.contains("  ret i32 %t1\n"));
+    assert!(!output.contains("%t2"));
+}
+
+#[test]
+fn llvm_negates_floats_with_fneg() {
+    let (output, diagnostics) = generate(&mut LLVM::new(), "fn negate(x: f64) => f64 { return -x; }");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("  %t0 = fneg double %x\n"));
+    assert!(!output.contains("sub"));
+}
+
+#[test]
+fn llvm_lowers_every_statement_of_a_body() {
+    let code = "fn twice_plus_one(a: i32) => i32 { let b = a * 2; return b + 1; }";
+    let (output, diagnostics) = generate(&mut LLVM::new(), code);
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("  %t0 = mul i32 %a, 2\n"));
+    assert!(output.contains("  %t1 = add i32 %t0, 1\n"));
+    assert!(output.contains("  ret i32 %t1\n"));
+}
+
+#[test]
+fn llvm_reports_statements_it_cannot_lower() {
+    let code = "fn sign(a: i32) => i32 { if a > 0 { return 1; } return 0; }";
+    let (_, diagnostics) = generate(&mut LLVM::new(), code);
+
+    assert_eq!(diagnostics, vec!["'if' statements cannot be lowered to LLVM IR yet".to_owned()]);
+}
+
+#[test]
+fn llvm_reports_a_variable_that_cannot_be_lowered_once() {
+    let code = "fn positive(a: i32) => bool { let c = a > 0; return c; }";
+    let (output, diagnostics) = generate(&mut LLVM::new(), code);
+
+    assert_eq!(diagnostics, vec!["expression 'a > 0' cannot be lowered to LLVM IR yet".to_owned()]);
+    assert!(output.contains("  ret i1 undef\n"));
+}
+
+// The C for the value `f` returns, once `code` is resolved.
+fn returned_c(code: &str) -> Result<String, String> {
+    resolve(code, |_, program| match &body(program, "f").0[0] {
+        newton_rs::ast::ast::Statement::ReturnStatement(_, Some(value)) => C::expression(&value.node),
+        _ => panic!("expected 'f' to return a value right away"),
+    })
+}
+
+#[test]
+fn c_accesses_fields_through_pointers_with_an_arrow() {
+    let pair = "type Pair struct { @key: i32; }";
+
+    let code = format!("{} fn f(p: *Pair) => i32 {{ return p.key; }}", pair);
+    assert_eq!(returned_c(&code).as_deref(), Ok("p->key"));
+
+    let code = format!("{} fn f(p: Pair) => i32 {{ return p.key; }}", pair);
+    assert_eq!(returned_c(&code).as_deref(), Ok("p.key"));
+}
+
+#[test]
+fn c_reports_expressions_it_cannot_lower() {
+    let source = source("p.key");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&source).unwrap();
+
+    assert_eq!(C::expression(&expression.node), Err("the type of 'p' must be resolved first".to_owned()));
+}
+
+#[test]
+fn c_declares_every_dimension_of_an_array() {
+    let (output, diagnostics) = generate(&mut C::new(), "fn f(grid: [3][4]i32, flag: bool) {}");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("void f(int32_t grid[3][4], bool flag);"));
+    assert!(output.contains("#include <stdbool.h>\n"));
+    assert!(output.contains("#include <stdint.h>\n"));
+}
+
+#[test]
+fn c_lowers_bitwise_not() {
+    assert_eq!(returned_c("fn f(x: i32) => i32 { return ~x; }").as_deref(), Ok("(~x)"));
+}
+
+#[test]
+fn c_returns_structs_by_value() {
+    let code = "type Pair struct { @key: i32; } fn init(key: i32) => Pair { return Pair { key }; }";
+    let (output, diagnostics) = generate(&mut C::new(), code);
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("struct Pair init(int32_t key);"));
+    assert!(output.contains("    return ((struct Pair){.key = key});\n"));
+}
+
+#[test]
+fn c_defines_each_tuple_struct_once_before_its_first_use() {
+    let code = "
+        fn find() => (i32, bool) { return (1, true); }
+        fn check(result: (i32, bool)) {}
+    ";
+    let (output, diagnostics) = generate(&mut C::new(), code);
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert_eq!(output.matches("struct newton_tuple2_i32_bool {").count(), 1);
+
+    let definition = output.find("struct newton_tuple2_i32_bool {").unwrap();
+    let first_use = output.find("struct newton_tuple2_i32_bool find(void);").unwrap();
+    assert!(definition < first_use);
+}
+
+#[test]
+fn c_defines_nested_tuples_first() {
+    let (output, _) = generate(&mut C::new(), "fn f(nested: ((i8, i8), bool)) {}");
+
+    let inner = output.find("struct newton_tuple2_i8_i8 {").unwrap();
+    let outer = output.find("struct newton_tuple2_tuple2_i8_i8_bool {").unwrap();
+    assert!(inner < outer);
+}
+
+#[test]
+fn c_lowers_strings_to_character_pointers() {
+    let (output, diagnostics) = generate(&mut C::new(), "fn f(s: string) {}");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("void f(const char* s);"));
+}
+
+#[test]
+fn c_declares_extern_globals() {
+    let (output, diagnostics) = generate(&mut C::new(), "extern let errno: i32;");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("extern int32_t errno;"));
+}
+
+#[test]
+fn c_concatenates_strings_at_runtime() {
+    let code = r#"fn f(name: string) => string { return "hello " + name; }"#;
+    assert_eq!(returned_c(code).as_deref(), Ok(r#"newton_string_concat("hello ", name)"#));
+
+    let (output, diagnostics) = generate(&mut C::new(), code);
+    assert_eq!(diagnostics, Vec::<String>::new());
+
+    let helper = output.find("static const char* newton_string_concat(const char* left, const char* right) {").unwrap();
+    let definition = output.find("const char* f(const char* name) {\n").unwrap();
+    assert!(helper < definition);
+    assert!(output.contains("    return newton_string_concat(\"hello \", name);\n"));
+}
+
+#[test]
+fn c_leaves_out_the_runtime_unless_strings_are_concatenated() {
+    let (output, diagnostics) = generate(&mut C::new(), "fn f(name: string) => string { return name; }");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("#include <string.h>\n"));
+    assert!(!output.contains("newton_string_concat"));
+}
+
+#[test]
+fn c_defines_functions_after_every_prototype() {
+    let code = "
+        fn f(x: i32, _: bool) => i32 { let y = x * (2 + 3); if y > 10 { return g(y); } else { return y; } }
+        fn g(x: i32) => i32 { while x > 0 { x = x - 1; } return x; }";
+    let (output, diagnostics) = generate(&mut C::new(), code);
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    let prototype = output.find("int32_t g(int32_t x);").unwrap();
+    assert!(prototype < output.find("int32_t f(int32_t x, bool newton_discard1) {").unwrap());
+    assert!(output.contains("    int32_t y = (x * 5);\n    if ((y > 10)) {\n        return g(y);\n    } else {\n"));
+    assert!(output.contains("    while ((x > 0)) {\n        (x = (x - 1));\n    }\n    return x;\n}\n"));
+}
+
+#[test]
+fn c_only_declares_functions_with_statements_it_cannot_lower() {
+    let code = "fn f(a: i32) { match a { case 1: {} default: {} } }";
+    let (output, diagnostics) = generate(&mut C::new(), code);
+
+    assert_eq!(diagnostics, ["'match' statements cannot be lowered to C yet"]);
+    assert!(output.contains("void f(int32_t a);"));
+    assert!(!output.contains("void f(int32_t a) {"));
+}
+
+#[test]
+fn llvm_lowers_floats_as_the_bits_of_a_double() {
+    let (output, diagnostics) = generate(&mut LLVM::new(), "fn f() => f64 { return 0x1p4; }");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("  ret double 0x4030000000000000\n"));
+
+    // `0.1` is not exact as a `float`, so it is rounded to one first.
+    let (output, _) = generate(&mut LLVM::new(), "fn f() => f32 { return 0.1; }");
+    assert!(output.contains("  ret float 0x3FB99999A0000000\n"));
+}
+
+#[test]
+fn c_includes_the_headers_of_fixed_width_types_first() {
+    let (output, diagnostics) = generate(&mut C::new(), "fn f(flag: bool) => u64 { return 1; }");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    let prototype = output.find("uint64_t f(bool flag);").unwrap();
+    assert!(output.find("#include <stdbool.h>\n").unwrap() < prototype);
+    assert!(output.find("#include <stdint.h>\n").unwrap() < prototype);
+}
+
+#[test]
+fn c_reports_types_it_cannot_lower() {
+    let (output, diagnostics) = generate(&mut C::new(), "fn f(maybe: ?i32) {} fn g(x: i32) {}");
+
+    assert_eq!(diagnostics, ["type '?i32' cannot be lowered to C, nullable types are not supported yet"]);
+    assert!(!output.contains(" f("));
+    assert!(output.contains("void g(int32_t x);"));
+}
+
+// A backend that only writes down which of its hooks were called, in order.
+#[derive(Default)]
+struct Recorder {
+    info: String,
+    source: String,
+}
+
+impl Backend for Recorder {
+    fn backend_name(&self) -> &String {
+        &self.info
+    }
+
+    fn backend_description(&self) -> &String {
+        &self.info
+    }
+
+    fn backend_author(&self) -> &String {
+        &self.info
+    }
+
+    fn backend_target(&self) -> &String {
+        &self.info
+    }
+
+    fn source(&self) -> &String {
+        &self.source
+    }
+
+    fn emit(&mut self, code: &str) {
+        self.source.push_str(code);
+        self.source.push('\n');
+    }
+
+    fn generate_header(&mut self, module: &str) {
+        self.emit(&format!("header {}", module));
+    }
+
+    fn generate_footer(&mut self, module: &str) {
+        self.emit(&format!("footer {}", module));
+    }
+
+    fn generate(&mut self, program: &Program, _diagnostics: &mut DiagnosticSink) {
+        self.emit(&format!("program of {}", program.0.len()));
+    }
+
+    fn generate_function(&mut self, function: &TopLevel, _diagnostics: &mut DiagnosticSink) {
+        if let TopLevel::FunctionDeclaration { name, .. } = function {
+            self.emit(&format!("function {}", name.node));
+        }
+    }
+
+    fn begin_function(&mut self, _function: &TopLevel) {
+        self.emit("begin");
+    }
+
+    fn end_function(&mut self, _function: &TopLevel) {
+        self.emit("end");
+    }
+
+    fn finish(&mut self) -> String {
+        std::mem::take(&mut self.source)
+    }
+}
+
+#[test]
+fn hooks_are_called_in_order() {
+    let (output, _) = generate(&mut Recorder::default(), "fn first() {} extern let errno: i32; fn second() {}");
+
+    assert_eq!(
+        output.lines().collect::<Vec<&str>>(),
+        [
+            "header main",
+            "program of 3",
+            "begin",
+            "function first",
+            "end",
+            "begin",
+            "function second",
+            "end",
+            "footer main",
+        ]
+    );
+}
+
+#[test]
+fn c_guards_the_output_with_the_name_of_the_module() {
+    let source = source("fn f() {}");
+    let program = parse(&source);
+    let output = generate_program(&mut C::new(), "std.io", &program, &mut DiagnosticSink::new());
+
+    assert!(output.contains("#ifndef NEWTON_STD_IO_H\n#define NEWTON_STD_IO_H\n"));
+    assert!(output.ends_with("\n#endif // NEWTON_STD_IO_H\n"));
+    assert_eq!(include_guard("main"), "NEWTON_MAIN_H");
+}
+
+#[test]
+fn c_keeps_the_layout_attributes_of_structs() {
+    let (output, diagnostics) = generate(&mut C::new(), "@[packed] @[align(8)] type S struct { @a: i8; @b: i32; }");
+
+    assert_eq!(diagnostics, Vec::<String>::new());
+    assert!(output.contains("struct S {\n    int8_t a;\n    int32_t b;\n} __attribute__((packed, aligned(8)));"));
+}
+
+#[test]
+fn c_lowers_index_expressions() {
+    assert_eq!(returned_c("fn f(grid: [3][4]i32) => i32 { return grid[1][2]; }").as_deref(), Ok("grid[1][2]"));
+}
@@ -0,0 +1,84 @@
+/*
+ * Helpers shared by the integration tests, which all work on snippets of Newton code.
+ *
+ * Newton (C) 2023
+ */
+
+#![allow(dead_code)]
+
+use newton_rs::ast::ast::*;
+use newton_rs::error::diagnostic::*;
+use newton_rs::lexer::lexer::*;
+use newton_rs::parser::parser::*;
+use newton_rs::semantic::typecheck::*;
+use newton_rs::Source;
+
+pub fn source(code: &str) -> Source {
+    Source::new("main", code)
+}
+
+pub fn parse(source: &Source) -> Program<'_> {
+    Parser::new(Lexer::new(source)).parse()
+}
+
+// The messages of every error the parser reported, in the order it found them.
+pub fn parse_errors(code: &str) -> Vec<String> {
+    let source = source(code);
+    let diagnostics = newton_rs::try_parse(&source).err().unwrap_or_default();
+
+    diagnostics.into_iter().map(|diagnostic| diagnostic.message).collect()
+}
+
+pub fn parse_diagnostics(code: &str) -> Vec<Diagnostic> {
+    let source = source(code);
+    newton_rs::try_parse(&source).err().unwrap_or_default()
+}
+
+// Parse and resolve `code` as the module `main`, and hand the resolver over to `check`. Parse errors fail the test, as
+// the resolver is what is being tested.
+pub fn resolve<T>(code: &str, check: impl for<'a> FnOnce(&Resolver<'a>, &Program<'a>) -> T) -> T {
+    resolve_with_options(code, ResolverOptions::default(), check)
+}
+
+pub fn resolve_with_options<T>(
+    code: &str,
+    options: ResolverOptions,
+    check: impl for<'a> FnOnce(&Resolver<'a>, &Program<'a>) -> T,
+) -> T {
+    let source = source(code);
+    let program = parse(&source);
+    assert_eq!(parse_errors(code), Vec::<String>::new(), "the code must parse");
+
+    let mut resolver = Resolver::new_with_options(&source, options);
+    resolver.resolve(&program);
+
+    check(&resolver, &program)
+}
+
+// The message of every error the resolver reported, as the first line of the rendered error without `error: `.
+pub fn error_messages(code: &str) -> Vec<String> {
+    resolve(code, |resolver, _| resolver.errors().iter().map(|error| headline(&error.to_string())).collect())
+}
+
+pub fn warning_messages(code: &str) -> Vec<String> {
+    resolve(code, |resolver, _| resolver.warnings().iter().map(|warning| headline(&warning.to_string())).collect())
+}
+
+pub fn headline(rendered: &str) -> String {
+    let line = rendered.lines().next().unwrap_or_default();
+    let line = line.strip_prefix("error: ").or_else(|| line.strip_prefix("warning: ")).unwrap_or(line);
+
+    line.to_owned()
+}
+
+// The body of the function named `name`.
+pub fn body<'p, 'a>(program: &'p Program<'a>, name: &str) -> &'p Block<'a> {
+    program
+        .0
+        .iter()
+        .find_map(|top_level| match top_level {
+            TopLevel::FunctionDeclaration { name: function, body, .. } if function.node == name => Some(body),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no function named '{}'", name))
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::*;
+use newton_rs::ast::ast::*;
+use newton_rs::parser::parser::*;
+use newton_rs::semantic::consteval::*;
+
+#[test]
+fn nested_arithmetic_is_folded_into_one_constant() {
+    let source = source("(1 + 2) * (10 - 4) / 3");
+    let expression = Parser::parse_expression_str(&source).unwrap();
+    let folded = fold(&expression);
+
+    assert_eq!(folded.node.kind(), &ExpressionKind::Constant(ConstValue::Integer(6)));
+    assert_eq!(folded.span, expression.span);
+}
+
+#[test]
+fn expressions_with_variables_keep_their_variables() {
+    let source = source("x + 2 * 3");
+    let expression = Parser::parse_expression_str(&source).unwrap();
+    let folded = fold(&expression);
+
+    let ExpressionKind::Binary(left, _, right) = folded.node.kind() else {
+        panic!("expected a binary expression, got '{}'", folded.node);
+    };
+    assert_eq!(left.node.kind(), &ExpressionKind::Identifier("x"));
+    assert_eq!(right.node.kind(), &ExpressionKind::Constant(ConstValue::Integer(6)));
+}
+
+#[test]
+fn division_by_zero_is_not_folded() {
+    let source = source("1 / 0");
+    let expression = Parser::parse_expression_str(&source).unwrap();
+
+    assert_eq!(evaluate(&expression.node), Err(ConstEvalError::DivisionByZero));
+    assert_eq!(fold(&expression), expression);
+}
+
+#[test]
+fn bitwise_not_is_folded() {
+    let source = source("~5");
+    let expression = Parser::parse_expression_str(&source).unwrap();
+
+    assert_eq!(fold(&expression).node.kind(), &ExpressionKind::Constant(ConstValue::Integer(-6)));
+}
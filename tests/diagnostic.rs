@@ -0,0 +1,92 @@
+mod common;
+
+use common::*;
+use newton_rs::error::diagnostic::*;
+use newton_rs::parser::span::*;
+
+// A span in the JSON output, with half-open byte and column ranges on a single line.
+fn json_span(byte: usize, line: usize, column: usize, is_primary: bool, label: &str) -> String {
+    format!(
+        "{{\"file\":\"main\",\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{},\"is_primary\":{},\"label\":{}}}",
+        byte,
+        byte + 1,
+        line,
+        column,
+        line,
+        column + 1,
+        is_primary,
+        label
+    )
+}
+
+#[test]
+fn json_diagnostics_follow_the_schema() {
+    let source = source("let a = 1;\nlet a = 2;\n");
+    let diagnostic = Diagnostic::error(Span::new(15, 15), "'a' is already defined".to_owned())
+        .with_code("E0001")
+        .with_label(Span::new(4, 4), "'a' was defined here".to_owned())
+        .with_suggestion("rename it".to_owned(), Span::new(15, 15), "b".to_owned());
+
+    let json = diagnostic.to_json(&source);
+    let (fields, rendered) = json.split_once(",\"rendered\":").expect("the diagnostic must be rendered");
+
+    let expected = format!(
+        "{{\"version\":{},\"severity\":\"error\",\"code\":\"E0001\",\"message\":\"'a' is already defined\",\"spans\":[{},{}],\"suggestions\":[{{\"message\":\"rename it\",\"span\":{},\"replacement\":\"b\"}}]",
+        JSON_SCHEMA_VERSION,
+        json_span(15, 2, 5, true, "null"),
+        json_span(4, 1, 5, false, "\"'a' was defined here\""),
+        json_span(15, 2, 5, false, "null"),
+    );
+    assert_eq!(fields, expected);
+
+    assert!(rendered.starts_with("\"error: 'a' is already defined\\n--> main:2:5\\n"));
+    assert!(rendered.contains("\\nnote: 'a' was defined here\\n"));
+    assert!(rendered.ends_with("help: rename it: 'b'\"}"));
+    assert!(!json.contains('\n'));
+}
+
+// The carets marking `span` on the first line of `code`, and the number of columns before them.
+fn caret(code: &str, span: Span) -> (usize, usize) {
+    let source = source(code);
+    let rendered = newton_rs::error_to_string(&source, span, span, 1, false);
+    let marker = rendered.lines().last().unwrap().rsplit('|').next().unwrap();
+    let marker: String = marker.chars().filter(|c| *c == ' ' || *c == '^').collect();
+
+    (marker.len() - marker.trim_start().len(), marker.trim_start().len())
+}
+
+#[test]
+fn carets_are_as_wide_as_the_token() {
+    // The span of a token ends at the first byte of its last character.
+    assert_eq!(caret("let name = 1;", Span::new(4, 7)), (4, 4));
+    assert_eq!(caret("let 名前 = 1;", Span::new(4, 7)), (4, 4));
+    assert_eq!(caret("let 名前 = 1;", Span::new(11, 11)), (9, 1));
+    assert_eq!(caret("let e\u{301} = 1;", Span::new(4, 5)), (4, 1));
+}
+
+#[test]
+fn sinks_hand_out_diagnostics_in_source_order() {
+    let mut sink = DiagnosticSink::new();
+    sink.warning(Span::new(20, 22), "late warning".to_owned());
+    sink.error(Span::new(3, 4), "early error".to_owned());
+    sink.extend([
+        Diagnostic::warning(Span::new(10, 12), "warning in the middle".to_owned()),
+        Diagnostic::error(Span::new(10, 11), "error in the middle".to_owned()),
+        Diagnostic::error(Span::new(10, 10), "second error in the middle".to_owned()),
+    ]);
+
+    let expected = [
+        "early error",
+        "error in the middle",
+        "second error in the middle",
+        "warning in the middle",
+        "late warning",
+    ];
+
+    let messages = sink.sorted().into_iter().map(|diagnostic| diagnostic.message.as_str()).collect::<Vec<_>>();
+    assert_eq!(messages, expected);
+
+    assert_eq!(sink.len(), 5);
+    let messages = sink.into_sorted().into_iter().map(|diagnostic| diagnostic.message).collect::<Vec<_>>();
+    assert_eq!(messages, expected);
+}
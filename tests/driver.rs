@@ -0,0 +1,287 @@
+mod common;
+
+use common::*;
+use newton_rs::driver::compiler::*;
+use newton_rs::driver::format::*;
+use newton_rs::driver::highlight::*;
+use newton_rs::Source;
+
+// Compile every module in `modules`, given by name and code, and return the messages of the resolver errors.
+fn compile_errors(modules: &[(&str, &str)]) -> Vec<String> {
+    let mut compiler = Compiler::new();
+    for (name, code) in modules {
+        compiler.add_source(Source::new(name, code));
+    }
+
+    let compilation = compiler.compile();
+    compilation.errors.iter().map(|error| headline(&error.to_string())).collect()
+}
+
+const HELPERS: &str = "pub fn helper() {} fn secret() {}";
+
+#[test]
+fn exported_functions_can_be_imported() {
+    let main = "from \"helpers\" import helper; fn f() { helper(); }";
+
+    assert_eq!(compile_errors(&[("main", main), ("helpers", HELPERS)]), Vec::<String>::new());
+}
+
+#[test]
+fn private_functions_cannot_be_imported() {
+    let main = "from \"helpers\" import secret; fn f() { secret(); }";
+
+    assert_eq!(
+        compile_errors(&[("main", main), ("helpers", HELPERS)]),
+        ["'secret' is not marked as 'pub' in module 'helpers', so it cannot be imported"]
+    );
+}
+
+#[test]
+fn the_compiler_owns_its_sources() {
+    let mut compiler = Compiler::new();
+    compiler.add_source(Source::new("main", "from \"math\" import twice; fn f() => i32 { return twice(2); }"));
+    compiler.add_source(Source::new("math", "pub fn twice(x: i32) => i32 { return x * 2; }"));
+
+    let compilation = compiler.compile();
+
+    assert!(!compilation.has_errors());
+    assert_eq!(compilation.programs.len(), 2);
+    assert_eq!(compilation.program("main").map(|program| program.0.len()), Some(2));
+    assert_eq!(compilation.program("math").map(|program| program.0.len()), Some(1));
+    assert!(compilation.program("missing").is_none());
+}
+
+#[test]
+fn tokens_are_classified_for_highlighting() {
+    let code = "type P struct { @x: i32; } // note\nfn f(p: P) => i32 { return 1; }";
+    let source = source(code);
+
+    let classes: Vec<(&str, TokenClass)> = highlight(&source)
+        .into_iter()
+        .map(|(span, class)| (&code[span.start..=span.end], class))
+        .filter(|(_, class)| *class != TokenClass::Operator)
+        .collect();
+
+    // `P` is a type wherever it is used, since the resolver knows it is declared as one.
+    assert_eq!(classes, [
+        ("type", TokenClass::Keyword),
+        ("P", TokenClass::Type),
+        ("struct", TokenClass::Keyword),
+        ("x", TokenClass::Identifier),
+        ("i32", TokenClass::Type),
+        ("// note", TokenClass::Comment),
+        ("fn", TokenClass::Keyword),
+        ("f", TokenClass::Identifier),
+        ("p", TokenClass::Identifier),
+        ("P", TokenClass::Type),
+        ("i32", TokenClass::Type),
+        ("return", TokenClass::Keyword),
+        ("1", TokenClass::Literal),
+    ]);
+}
+
+// A fresh, empty directory for the test `name`, holding `files` given by name and contents.
+fn directory(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let directory = std::env::temp_dir().join(format!("newton-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+
+    for (file, code) in files {
+        let path = directory.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, code).unwrap();
+    }
+
+    directory
+}
+
+#[test]
+fn modules_are_found_on_any_search_path() {
+    let root = directory("search-paths", &[("second/utils.nt", "pub fn helper() {}")]);
+
+    let mut imports = newton_rs::driver::import::ImportResolver::new();
+    imports.add_search_path(root.join("first"));
+    imports.add_search_path(root.join("second"));
+
+    assert_eq!(imports.resolve("utils"), Some(root.join("second").join("utils.nt")));
+}
+
+#[test]
+fn missing_modules_list_every_searched_path() {
+    let root = directory("missing-module", &[]);
+
+    let mut imports = newton_rs::driver::import::ImportResolver::new();
+    imports.add_search_path(root.join("first"));
+    imports.add_search_path(root.join("second"));
+
+    let error = imports.find("utils", None).unwrap_err();
+    assert_eq!(error.searched, [root.join("first").join("utils.nt"), root.join("second").join("utils.nt")]);
+    assert!(error.to_string().starts_with("cannot find module 'utils', searched in:\n"));
+}
+
+#[test]
+fn shared_imports_are_loaded_once() {
+    let root = directory("diamond", &[
+        ("main.nt", "import \"left\"; import \"right\"; fn main() {}"),
+        ("left.nt", "import \"shared\"; pub fn left() {}"),
+        ("right.nt", "import \"shared\"; pub fn right() {}"),
+        ("shared.nt", "pub fn shared() {}"),
+    ]);
+
+    let mut compiler = Compiler::new();
+    compiler.add_search_path(&root);
+    compiler.add_file(root.join("main.nt")).unwrap();
+
+    let mut names: Vec<&str> = compiler.sources().iter().map(|source| source.name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["left", "main", "right", "shared"]);
+
+    assert!(!compiler.compile().has_errors());
+}
+
+#[test]
+fn modules_imported_by_different_paths_are_one_module() {
+    let root = directory("relative-diamond", &[
+        ("main.nt", "import \"./sub/b\"; from \"./c\" import h; fn main() { h(); }"),
+        ("sub/b.nt", "from \"../c\" import h; pub fn b() { h(); }"),
+        ("c.nt", "pub fn h() {}"),
+    ]);
+
+    let mut compiler = Compiler::new();
+    compiler.add_file(root.join("main.nt")).unwrap();
+    assert_eq!(compiler.sources().len(), 3);
+
+    let compilation = compiler.compile();
+    let errors: Vec<String> = compilation.errors.iter().map(|error| headline(&error.to_string())).collect();
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[test]
+fn modules_of_the_same_name_in_different_directories_are_kept_apart() {
+    let root = directory("same-name", &[
+        ("main.nt", "import \"./sub/b\"; from \"./c\" import h; fn main() { h(); }"),
+        ("sub/b.nt", "from \"./c\" import g; pub fn b() { g(); }"),
+        ("sub/c.nt", "pub fn g() {}"),
+        ("c.nt", "pub fn h() {}"),
+    ]);
+
+    let mut compiler = Compiler::new();
+    compiler.add_file(root.join("main.nt")).unwrap();
+    assert_eq!(compiler.sources().len(), 4);
+
+    let compilation = compiler.compile();
+    let errors: Vec<String> = compilation.errors.iter().map(|error| headline(&error.to_string())).collect();
+    assert_eq!(errors, Vec::<String>::new());
+}
+
+#[test]
+fn only_changed_files_are_reloaded() {
+    let root = directory("refresh", &[
+        ("main.nt", "import \"shared\"; fn main() {}"),
+        ("shared.nt", "pub fn shared() {}"),
+    ]);
+
+    let mut compiler = Compiler::new();
+    compiler.add_search_path(&root);
+    compiler.add_file(root.join("main.nt")).unwrap();
+    assert_eq!(compiler.refresh().unwrap(), 0);
+
+    // The modification time is moved along explicitly, as it may not change otherwise on coarse file systems.
+    let shared = root.join("shared.nt");
+    let modified = std::fs::metadata(&shared).unwrap().modified().unwrap();
+    std::fs::write(&shared, "pub fn shared() {} pub fn other() {}").unwrap();
+    let file = std::fs::File::options().write(true).open(&shared).unwrap();
+    file.set_modified(modified + std::time::Duration::from_secs(1)).unwrap();
+
+    assert_eq!(compiler.refresh().unwrap(), 1);
+    assert_eq!(compiler.sources().len(), 2);
+}
+
+#[test]
+fn the_sample_dumps_like_its_golden_file() {
+    // `sample.nt` is the code `src/main.rs` compiles and dumps.
+    let golden = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let code = std::fs::read_to_string(golden.join("sample.nt")).unwrap();
+    let expected = std::fs::read_to_string(golden.join("sample.dump")).unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.add_source(Source::new("main", &code));
+    let compilation = compiler.compile();
+
+    assert_eq!(compilation.errors.len(), 0);
+    assert_eq!(newton_rs::ast::dump::debug_dump(compilation.program("main").unwrap()), expected);
+}
+
+#[test]
+fn blank_lines_between_declarations_collapse_to_one() {
+    let source = Source::new("main", "\n\nfn a() {}\n\n\nfn b() {\n\n\n    let x = 1;\n}\n\nfn c() {}\n\n");
+    let formatted = format_blank_lines(&source);
+
+    // Blank lines inside of `b` are not between declarations, so they are left alone.
+    assert_eq!(formatted, "fn a() {}\n\nfn b() {\n\n\n    let x = 1;\n}\n\nfn c() {}\n");
+}
+
+#[test]
+fn modules_can_define_the_same_name() {
+    let a = "pub fn foo() => i32 { return 1; }";
+    let b = "pub fn foo() => bool { return true; }";
+    let main = "fn f() { let x: i32 = a.foo(); let y: bool = b.foo(); }";
+
+    assert_eq!(compile_errors(&[("main", main), ("a", a), ("b", b)]), Vec::<String>::new());
+
+    let swapped = "fn f() { let x: bool = a.foo(); }";
+    assert_eq!(
+        compile_errors(&[("main", swapped), ("a", a), ("b", b)]),
+        ["initial value must be of type 'bool', but the actual type was 'i32'"]
+    );
+}
+
+#[test]
+fn declarations_for_inactive_names_are_left_out() {
+    let code = "
+        @[cfg(c)]
+        fn target() => i32 { return 1; }
+        @[cfg(llvm)]
+        fn target() => i32 { return 2; }
+        fn f() => i32 { return target(); }
+    ";
+
+    let options = newton_rs::semantic::typecheck::ResolverOptions {
+        cfg: ["c".to_owned()].into_iter().collect(),
+        ..Default::default()
+    };
+    let mut compiler = Compiler::new_with_options(options);
+    compiler.add_source(Source::new("main", code));
+    let compilation = compiler.compile();
+
+    assert!(!compilation.has_errors());
+    let program = compilation.program("main").unwrap();
+    assert_eq!(program.0.len(), 2);
+    assert!(program.0[0].attributes().iter().any(|attribute| attribute.arguments[0].node == "c"));
+
+    // Without any active names, `f` calls a function that is not there at all.
+    assert_eq!(compile_errors(&[("main", code)]), ["'target' is not defined in the current scope"]);
+}
+
+#[test]
+fn reporting_a_compilation_counts_errors_but_not_warnings() {
+    let mut compiler = Compiler::new();
+    compiler.add_source(Source::new("main", "fn f() { let a = ; } fn g() { let b = c; if true {} }"));
+
+    let mut output = vec![];
+    assert_eq!(compiler.compile().report(&mut output).unwrap(), 2);
+    assert!(String::from_utf8(output).unwrap().contains("warning: "));
+}
+
+#[test]
+fn compilations_are_reported_in_source_order() {
+    let mut compiler = Compiler::new();
+    compiler.add_source(Source::new("main", "fn f() { let a = b; }\nfn g() { let c = ; }\nfn h() { let d = e; }"));
+
+    let mut output = vec![];
+    assert_eq!(compiler.compile().report(&mut output).unwrap(), 3);
+
+    let output = String::from_utf8(output).unwrap();
+    let lines = output.lines().filter(|line| line.starts_with("-->")).collect::<Vec<_>>();
+    assert_eq!(lines, ["--> main:1:18", "--> main:2:18", "--> main:3:18"]);
+}
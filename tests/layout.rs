@@ -0,0 +1,102 @@
+mod common;
+
+use common::*;
+use newton_rs::ast::ast::*;
+use newton_rs::types::layout::*;
+
+// The size and alignment of every parameter of the function `f` in `code`, for a target with `pointer_size`.
+fn layouts(code: &str, pointer_size: u64) -> Vec<Result<Layout, String>> {
+    resolve(code, |resolver, program| {
+        let arguments = program
+            .0
+            .iter()
+            .find_map(|top_level| match top_level {
+                TopLevel::FunctionDeclaration { name, arguments, .. } if name.node == "f" => Some(arguments),
+                _ => None,
+            })
+            .expect("no function named 'f'");
+
+        let mut computer = LayoutComputer::new(DataLayout::new(pointer_size), resolver.module_map());
+        arguments
+            .parameters
+            .iter()
+            .map(|Parameter(_, ty)| computer.layout_of(&ty.node).map_err(|error| error.to_string()))
+            .collect()
+    })
+}
+
+#[test]
+fn integers_are_as_large_as_their_width() {
+    assert_eq!(layouts("fn f(a: i32, b: u8, c: i64) {}", 8), [
+        Ok(Layout::new(4, 4)),
+        Ok(Layout::new(1, 1)),
+        Ok(Layout::new(8, 8)),
+    ]);
+}
+
+#[test]
+fn struct_fields_are_padded_to_their_alignment() {
+    let code = "type Mixed struct { @a: i8; @b: i32; @c: i8; } fn f(m: Mixed) {}";
+
+    assert_eq!(layouts(code, 8), [Ok(Layout::new(12, 4))]);
+}
+
+#[test]
+fn arrays_are_as_large_as_all_their_elements() {
+    assert_eq!(layouts("fn f(a: [4]i16) {}", 8), [Ok(Layout::new(8, 2))]);
+}
+
+#[test]
+fn pointers_are_as_large_as_the_target_says() {
+    assert_eq!(layouts("fn f(p: *i64) {}", 8), [Ok(Layout::new(8, 8))]);
+    assert_eq!(layouts("fn f(p: *i64) {}", 4), [Ok(Layout::new(4, 4))]);
+}
+
+#[test]
+fn structs_containing_themselves_have_no_layout() {
+    let code = "type Node struct { @next: Node; } fn f(n: Node) {}";
+
+    assert_eq!(layouts(code, 8), [Err("recursive type 'main.Node' has infinite size".to_owned())]);
+}
+
+#[test]
+fn field_offsets_account_for_padding() {
+    let offsets = resolve("type Padded struct { @a: i8; @b: i32; }", |resolver, _| {
+        let definition = resolver.module_map().get_user_type("main", "Padded").unwrap();
+        definition
+            .field_order
+            .iter()
+            .map(|field| (field.to_string(), definition.fields[field].0))
+            .collect::<Vec<_>>()
+    });
+
+    assert_eq!(offsets, [("a".to_owned(), 0), ("b".to_owned(), 4)]);
+}
+
+#[test]
+fn multi_dimensional_arrays_hold_every_element() {
+    assert_eq!(layouts("fn f(grid: [3][4]i32) {}", 8), [Ok(Layout::new(48, 4))]);
+}
+
+#[test]
+fn packed_structs_have_no_padding() {
+    let fields = "{ @a: i8; @b: i32; @c: i8; }";
+    let code = format!(
+        "type Mixed struct {} @[packed] type Packed struct {} fn f(m: Mixed, p: Packed) {{}}",
+        fields, fields
+    );
+
+    assert_eq!(layouts(&code, 8), [Ok(Layout::new(12, 4)), Ok(Layout::new(6, 1))]);
+}
+
+#[test]
+fn aligned_structs_are_at_least_as_aligned_as_requested() {
+    let code = "
+        @[align(16)] type Wide struct { @a: i8; @b: i32; }
+        @[align(2)] type Narrow struct { @a: i8; @b: i32; }
+        @[packed] @[align(4)] type Both struct { @a: i8; @b: i32; }
+        fn f(w: Wide, n: Narrow, b: Both) {}
+    ";
+
+    assert_eq!(layouts(code, 8), [Ok(Layout::new(16, 16)), Ok(Layout::new(8, 4)), Ok(Layout::new(8, 4))]);
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use common::*;
+use newton_rs::lexer::lexer::*;
+use newton_rs::lexer::token::*;
+use newton_rs::parser::error::*;
+use newton_rs::parser::span::*;
+
+// Assert that `code` lexes to exactly `expected`, followed by the end of the input.
+fn assert_lexes_to(code: &str, expected: &[TokenType]) {
+    let source = source(code);
+    let tokens: Vec<_> = Lexer::new(&source).map(|scanned| scanned.expect("the code must lex").node).collect();
+
+    assert_eq!(&tokens[..tokens.len() - 1], expected, "lexing {:?}", code);
+    assert_eq!(tokens.last(), Some(&TokenType::Eof));
+}
+
+#[test]
+fn operators_at_the_end_of_the_input_are_single_tokens() {
+    assert_lexes_to("!", &[TokenType::Bang]);
+    assert_lexes_to("+", &[TokenType::Plus]);
+    assert_lexes_to("-", &[TokenType::Minus]);
+    assert_lexes_to("<", &[TokenType::Smaller]);
+    assert_lexes_to(">", &[TokenType::Greater]);
+    assert_lexes_to("&", &[TokenType::Ampersand]);
+    assert_lexes_to("|", &[TokenType::Pipe]);
+    assert_lexes_to("=", &[TokenType::Equals]);
+    assert_lexes_to("/", &[TokenType::Slash]);
+    assert_lexes_to(".", &[TokenType::Dot]);
+}
+
+#[test]
+fn operators_at_the_end_of_the_input_can_still_be_doubled() {
+    assert_lexes_to("!=", &[TokenType::BangEquals]);
+    assert_lexes_to("&&", &[TokenType::AmpersandAmpersand]);
+    assert_lexes_to("a <", &[TokenType::Identifier("a"), TokenType::Smaller]);
+}
+
+#[test]
+fn the_last_token_is_an_empty_eof() {
+    let source = source("let x");
+    let tokens: Vec<_> = Lexer::new(&source).collect();
+
+    let last = tokens.last().unwrap().as_ref().unwrap();
+    assert_eq!(last.node, TokenType::Eof);
+    assert_eq!((last.span.start, last.span.end), (5, 5));
+    assert_eq!(tokens.len(), 3);
+}
+
+// The first lexing error in `code`.
+fn lexing_error(code: &str) -> LexingError {
+    let source = source(code);
+    Lexer::new(&source)
+        .find_map(|scanned| match scanned {
+            Err(Spanned {
+                node: ParseError::LexingError(error),
+                ..
+            }) => Some(error),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("expected {:?} to fail lexing", code))
+}
+
+#[test]
+fn lexing_errors_tell_their_cause() {
+    assert_eq!(lexing_error(r#"let s = "open"#).cause(), Some("unterminated string literal"));
+    assert_eq!(lexing_error("let a = $;").cause(), None);
+}
+
+#[test]
+fn lexing_errors_have_a_kind_for_every_failure() {
+    let failures = [
+        ("let a = $;", LexingErrorKind::UnexpectedCharacter),
+        (r#"let s = "open"#, LexingErrorKind::UnterminatedString),
+        ("fn f(a: i32, ....) {}", LexingErrorKind::TooManyDots),
+        ("let ä = 1;", LexingErrorKind::NonAsciiIdentifier),
+        ("let c = 'ab';", LexingErrorKind::InvalidCharLiteral),
+        (r"let c = '\q';", LexingErrorKind::InvalidEscape),
+    ];
+
+    for (code, kind) in failures {
+        assert_eq!(lexing_error(code).kind(), kind, "lexing {:?}", code);
+    }
+
+    assert_eq!(lexing_error("let a = $;").to_string(), "failed to lex token");
+    assert_eq!(lexing_error("a..b").to_string(), "failed to lex token; because too many dots");
+}
+
+#[test]
+fn any_number_of_comments_can_come_before_a_token() {
+    let code = format!("{}/* block */ let", "// line\n".repeat(100_000));
+    assert_lexes_to(&code, &[TokenType::Let]);
+}
+
+#[test]
+fn hex_floats_are_decoded() {
+    assert_lexes_to("0x1p4", &[TokenType::FloatLiteral("0x1p4")]);
+
+    assert_eq!(float_value("0x1p4"), Some(16.0));
+    assert_eq!(float_value("0x1.8p3"), Some(12.0));
+    assert_eq!(float_value("0XA.8P-1"), Some(5.25));
+    assert_eq!(float_value("0x.1p+4"), Some(1.0));
+    assert_eq!(float_value("2.5"), Some(2.5));
+}
+
+#[test]
+fn hex_floats_need_an_exponent() {
+    assert_eq!(lexing_error("0x1.8").kind(), LexingErrorKind::MissingHexExponent);
+    assert_eq!(lexing_error("0x1p").kind(), LexingErrorKind::MissingHexExponent);
+    assert_eq!(lexing_error("0xFF").kind(), LexingErrorKind::MissingHexExponent);
+    assert_eq!(lexing_error("0x").kind(), LexingErrorKind::EmptyHexLiteral);
+}
+
+#[test]
+fn block_comments_nest() {
+    assert_lexes_to("let /* a /* b */ c */ x", &[TokenType::Let, TokenType::Identifier("x")]);
+    assert_lexes_to("/**/1/* * / */", &[TokenType::DecLiteral("1")]);
+}
+
+#[test]
+fn unterminated_block_comments_point_at_the_opener() {
+    let source = source("let x; /* a /* b */ c");
+    let error = Lexer::new(&source).find_map(Result::err).unwrap();
+
+    assert_eq!(error.span, Span::new(7, 8));
+    let ParseError::LexingError(error) = error.node else {
+        panic!("expected a lexing error");
+    };
+    assert_eq!(error.kind(), LexingErrorKind::UnterminatedBlockComment);
+    assert_eq!(error.cause(), Some("unterminated block comment"));
+}
+
+#[test]
+fn string_escapes_are_decoded() {
+    let code = r#""line\n\t\r\\\"\0\x41""#;
+    assert_lexes_to(code, &[TokenType::StringLiteral("line\n\t\r\\\"\0A".into())]);
+
+    let source = source(r#"let s = "plain";"#);
+    let string = Lexer::new(&source).nth(3).unwrap().unwrap();
+    assert!(matches!(string.node, TokenType::StringLiteral(std::borrow::Cow::Borrowed("plain"))));
+
+    // The span covers the quotes, and ends at the first byte of the closing one.
+    assert_eq!(string.span, Span::new(8, 14));
+}
+
+#[test]
+fn invalid_string_escapes_are_reported() {
+    for code in [r#""\q""#, r#""\x4""#, r#""\xFF""#] {
+        assert_eq!(lexing_error(code).kind(), LexingErrorKind::InvalidStringEscape, "lexing {:?}", code);
+    }
+
+    // Lexing goes on after the string.
+    let source = source(r#""\q" 1"#);
+    let tokens: Vec<_> = Lexer::new(&source).collect();
+    assert!(tokens[0].is_err());
+    assert_eq!(tokens[1].as_ref().unwrap().node, TokenType::DecLiteral("1"));
+}
@@ -0,0 +1,605 @@
+mod common;
+
+use common::*;
+use newton_rs::ast::ast::*;
+use newton_rs::lexer::lexer::*;
+use newton_rs::lexer::token::*;
+use newton_rs::parser::parser::*;
+use newton_rs::parser::span::*;
+
+#[test]
+fn labels_are_attached_to_loops_and_breaks() {
+    let source = source("fn f() { outer: while true { while true { break outer; } } }");
+    let program = parse(&source);
+
+    let Statement::WhileStatement(outer) = &body(&program, "f").0[0] else {
+        panic!("expected the outer loop");
+    };
+    assert_eq!(outer.label.map(|label| label.node), Some("outer"));
+
+    let Statement::WhileStatement(inner) = &outer.body.0[0] else {
+        panic!("expected the inner loop");
+    };
+    assert_eq!(inner.label, None);
+
+    let Statement::BreakStatement(control) = &inner.body.0[0] else {
+        panic!("expected a break");
+    };
+    assert_eq!(control.label.map(|label| label.node), Some("outer"));
+}
+
+#[test]
+fn array_literals_hold_their_elements() {
+    let source = source("[1, 2, 3]");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&source).unwrap();
+
+    let ExpressionKind::ArrayLiteral(elements) = expression.node.kind() else {
+        panic!("expected an array literal, got '{}'", expression.node);
+    };
+    let elements: Vec<String> = elements.iter().map(|element| element.node.to_string()).collect();
+    assert_eq!(elements, ["1", "2", "3"]);
+}
+
+#[test]
+fn array_sizes_must_be_positive() {
+    assert_eq!(parse_errors("fn f(a: [-1]i32) {}"), ["array size must be positive, but is -1"]);
+    assert_eq!(parse_errors("fn f(a: [0]i32) {}"), ["array size must be positive, but is 0"]);
+    assert_eq!(parse_errors("fn f(a: [?]i32) {}"), Vec::<String>::new());
+}
+
+#[test]
+fn nested_array_types_are_multi_dimensional() {
+    let source = source("fn f(grid: [3][4]i32) {}");
+    let program = parse(&source);
+
+    let TopLevel::FunctionDeclaration { arguments, .. } = &program.0[0] else {
+        panic!("expected a function");
+    };
+    let Parameter(_, ty) = &arguments.parameters[0];
+    let newton_rs::types::types::Type::Complex(newton_rs::types::types::Complex::Array(rows)) = &ty.node else {
+        panic!("expected an array, got '{}'", ty.node);
+    };
+
+    assert_eq!(rows.length(), Some(3));
+    assert_eq!(rows.element_count(), Some(12));
+    assert_eq!(rows.base_type().to_string(), "[4]i32");
+}
+
+#[test]
+fn prefix_operators_bind_tighter_than_binary_ones() {
+    let source = source("-a * b");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&source).unwrap();
+
+    let ExpressionKind::Binary(left, operator, right) = expression.node.kind() else {
+        panic!("expected a multiplication, got '{}'", expression.node);
+    };
+    assert_eq!(operator.node, TokenType::Star);
+    assert!(matches!(left.node.kind(), ExpressionKind::Negate(_, operand) if operand.node.to_string() == "a"));
+    assert_eq!(right.node.to_string(), "b");
+}
+
+#[test]
+fn tilde_is_a_bitwise_not() {
+    let source = source("~x + 1");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&source).unwrap();
+
+    let ExpressionKind::Binary(left, _, _) = expression.node.kind() else {
+        panic!("expected an addition, got '{}'", expression.node);
+    };
+    let ExpressionKind::BitNot(operator, operand) = left.node.kind() else {
+        panic!("expected a bitwise not, got '{}'", left.node);
+    };
+    assert_eq!(operator.node, TokenType::Tilde);
+    assert_eq!(operand.node.kind(), &ExpressionKind::Identifier("x"));
+}
+
+#[test]
+fn single_expressions_are_parsed_with_precedence() {
+    let source = source("1 + 2 * 3");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&source).unwrap();
+
+    let ExpressionKind::Binary(left, operator, right) = expression.node.kind() else {
+        panic!("expected an addition, got '{}'", expression.node);
+    };
+    assert_eq!(operator.node, TokenType::Plus);
+    assert_eq!(left.node.kind(), &ExpressionKind::DecLiteral("1"));
+    assert_eq!(right.node.to_string(), "2 * 3");
+}
+
+#[test]
+fn single_expressions_reject_trailing_input() {
+    let source = source("1 + 2; 3");
+    let error = newton_rs::parser::parser::Parser::parse_expression_str(&source).unwrap_err();
+
+    assert_eq!(error.node.to_string(), "expected 'end of input', but got ';' instead");
+    assert_eq!((error.span.start, error.span.end), (5, 5));
+}
+
+#[test]
+fn keywords_in_type_position_explain_the_mistake() {
+    assert_eq!(
+        parse_errors("fn f(a: struct) {}"),
+        ["expected a type, but got 'struct' instead; type declarations only appear at top level, refer to the declared type by its name instead"]
+    );
+    assert_eq!(
+        parse_errors("fn f(a: null) {}"),
+        ["expected a type, but got 'null' instead; 'null' is a value, use a nullable type like '?i32' to allow it"]
+    );
+    assert_eq!(parse_errors("fn f(a: 5) {}"), ["expected 'type', but got '5' instead"]);
+}
+
+#[test]
+fn missing_tokens_at_the_end_report_the_end_of_file() {
+    assert_eq!(parse_errors("fn f("), ["expected 'identifier', but got end of file"]);
+}
+
+#[test]
+fn missing_semicolons_suggest_inserting_one() {
+    let diagnostics = parse_diagnostics("fn f() {\n    let a = 1\n    let b = 2;\n}");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "expected ';', but got 'let' instead");
+
+    let suggestion = &diagnostics[0].suggestions[0];
+    assert!(suggestion.insertion);
+    assert_eq!(suggestion.replacement, ";");
+    assert_eq!(suggestion.span.start, "fn f() {\n    let a = 1".len());
+}
+
+#[test]
+fn statements_after_a_missing_semicolon_are_still_parsed() {
+    let source = source("fn f() { let a = 1 let b = 2; let c = 3; }");
+    let program = parse(&source);
+
+    // The missing semicolon is reported right where it is missing, after the first declaration.
+    let statements: Vec<String> = body(&program, "f")
+        .0
+        .iter()
+        .map(|statement| match statement {
+            Statement::VariableDeclaration(declaration) => declaration.name.node.to_owned(),
+            Statement::ExpressionStatement(expression) if expression.node.is_error() => "error".to_owned(),
+            other => panic!("expected only variable declarations, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(statements, ["a", "error", "b", "c"]);
+}
+
+// The declared type of the only parameter of the function `f` in `program`.
+fn parameter_type<'p, 'a>(program: &'p Program<'a>) -> &'p newton_rs::types::types::Type<'a> {
+    let TopLevel::FunctionDeclaration { arguments, .. } = &program.0[0] else {
+        panic!("expected a function");
+    };
+    let Parameter(_, ty) = &arguments.parameters[0];
+
+    &ty.node
+}
+
+#[test]
+fn displayed_types_parse_back_to_themselves() {
+    for ty in ["?*i32", "*?i32", "fn(i32) => void", "?fn(i32, bool) => *u8", "[4]?*f64"] {
+        let original_source = source(&format!("fn f(a: {}) {{}}", ty));
+        let displayed = parameter_type(&parse(&original_source)).to_string();
+
+        let code = format!("fn f(a: {}) {{}}", displayed);
+        assert_eq!(parse_errors(&code), Vec::<String>::new(), "'{}' must parse", displayed);
+
+        let reparsed_source = source(&code);
+        let original = parse(&original_source);
+        let reparsed = parse(&reparsed_source);
+        assert_eq!(parameter_type(&reparsed), parameter_type(&original), "displaying '{}' as '{}'", ty, displayed);
+    }
+}
+
+#[test]
+fn sizeof_tells_types_and_expressions_apart() {
+    let of_type = source("sizeof(i32)");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&of_type).unwrap();
+    assert!(matches!(expression.node.kind(), ExpressionKind::SizeOf(_)), "got '{}'", expression.node);
+
+    let of_value = source("sizeof(x)");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&of_value).unwrap();
+    assert!(matches!(expression.node.kind(), ExpressionKind::SizeOfValue(_)), "got '{}'", expression.node);
+}
+
+#[test]
+fn lexer_errors_are_reported_once_and_skipped() {
+    let code = "fn f() { let a = 1 $ 2; let b = 3; }";
+    assert_eq!(parse_errors(code), ["failed to lex token"]);
+
+    let source = source(code);
+    let program = parse(&source);
+    let declared: Vec<&str> = body(&program, "f")
+        .0
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::VariableDeclaration(declaration) => Some(declaration.name.node),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(declared, ["b"]);
+}
+
+#[test]
+fn truncated_files_report_the_end_of_file() {
+    assert_eq!(parse_errors("fn f() => void { return"), ["expected '}', but got end of file"]);
+    assert_eq!(parse_errors("fn f() =>"), ["expected 'type', but got end of file"]);
+}
+
+#[test]
+fn assignments_in_conditions_suggest_comparing() {
+    let diagnostics = parse_diagnostics("fn f(x: i32) { if x = 5 {} }");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "expected a comparison, but got an assignment in this condition");
+
+    let suggestion = &diagnostics[0].suggestions[0];
+    assert_eq!(suggestion.replacement, "==");
+    assert_eq!(suggestion.span, newton_rs::parser::span::Span::new(20, 20));
+    assert!(!suggestion.insertion);
+}
+
+#[test]
+fn tuples_are_told_apart_from_groupings_by_the_comma() {
+    let tuple = source("(a, 1)");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&tuple).unwrap();
+    let ExpressionKind::Tuple(elements) = expression.node.kind() else {
+        panic!("expected a tuple, got '{}'", expression.node);
+    };
+    assert_eq!(elements.len(), 2);
+
+    let grouping = source("(a)");
+    let expression = newton_rs::parser::parser::Parser::parse_expression_str(&grouping).unwrap();
+    assert!(matches!(expression.node.kind(), ExpressionKind::Grouping(_)), "got '{}'", expression.node);
+
+    let function = source("fn f(pair: (i32, bool)) {}");
+    let program = parse(&function);
+    assert_eq!(parameter_type(&program).to_string(), "(i32, bool)");
+}
+
+#[test]
+fn nesting_too_deep_is_an_error_instead_of_a_crash() {
+    let n = 20000;
+    let pathological = [
+        format!("fn f() => i32 {{ return {}1{}; }}", "(".repeat(n), ")".repeat(n)),
+        format!("fn f() => i32 {{ return {}1; }}", "-".repeat(n)),
+        format!("fn f() => i32 {{ return 1{}; }}", " + 1".repeat(n)),
+        format!("fn f(a: i32) {{ let x = a{}; }}", ".b".repeat(n)),
+        format!("fn f() {{ g{}; }}", "()".repeat(n)),
+        format!("fn f(a: i32) {{ let x = a{}; }}", " as i32".repeat(n)),
+        format!("fn f() {{ {}{} }}", "{ ".repeat(n), "}".repeat(n)),
+        format!("fn f(a: bool) {{ {}{} }}", "if a { ".repeat(n), "}".repeat(n)),
+        format!("fn f(a: bool) {{ if a {{}}{} }}", " else if a {}".repeat(n)),
+        format!("fn f(a: {}i32) {{}}", "[1]".repeat(n)),
+        format!("fn f(a: {}i32) {{}}", "?".repeat(n)),
+    ];
+
+    for code in &pathological {
+        assert_eq!(
+            parse_errors(code),
+            ["nesting too deep, at most 512 levels are allowed"],
+            "in '{}...'",
+            &code[..40]
+        );
+    }
+}
+
+#[test]
+fn deep_but_reasonable_nesting_still_parses() {
+    let code = format!("fn f() => i32 {{ return {}1{}; }}", "(".repeat(500), ")".repeat(500));
+    assert_eq!(parse_errors(&code), Vec::<String>::new());
+}
+
+#[test]
+fn the_nesting_limit_can_be_configured() {
+    let source = source("fn f() => i32 { return ((((1)))); }");
+    let options = ParserOptions {
+        max_depth: 4,
+        ..ParserOptions::default()
+    };
+    let program = Parser::new_with_options(Lexer::new(&source), options).parse();
+
+    let mut output = vec![];
+    assert_eq!(newton_rs::report_errors(&source, &program, &mut output).unwrap(), 1);
+    assert!(String::from_utf8(output).unwrap().contains("nesting too deep, at most 4 levels are allowed"));
+}
+
+#[test]
+fn extern_globals_have_a_type_but_no_value() {
+    let source = source("extern let errno: i32;");
+    let program = parse(&source);
+
+    let TopLevel::ExternVariable { name, ty, is_public, .. } = &program.0[0] else {
+        panic!("expected an extern variable");
+    };
+    assert_eq!(name.node, "errno");
+    assert_eq!(ty.node.to_string(), "i32");
+    assert!(!is_public);
+
+    assert_eq!(parse_errors("extern let errno: i32 = 0;"), ["expected ';', but got '=' instead"]);
+}
+
+#[test]
+fn groupings_keep_their_parentheses() {
+    let source = source("(a + b) * c");
+    let expression = Parser::parse_expression_str(&source).unwrap();
+
+    assert_eq!(expression.node.to_string(), "(a + b) * c");
+}
+
+#[test]
+fn structural_hashes_ignore_formatting() {
+    let compact = source("fn f(a: i32) => i32 { return a + 1; }");
+    let spread = source("fn f( a : i32 )\n    => i32\n{\n    return a\n        + 1;\n}\n");
+
+    assert_eq!(parse(&compact).structural_hash(), parse(&spread).structural_hash());
+    assert_eq!(parse(&compact).0[0].structural_hash(), parse(&spread).0[0].structural_hash());
+}
+
+#[test]
+fn structural_hashes_change_with_the_code() {
+    let one = source("fn f(a: i32) => i32 { return a + 1; }");
+    let two = source("fn f(a: i32) => i32 { return a + 2; }");
+
+    assert_ne!(parse(&one).structural_hash(), parse(&two).structural_hash());
+}
+
+#[test]
+fn nested_generic_arguments_close_every_level() {
+    for ty in ["Pair<i32, string>", "Pair<i32, Pair<i32, bool>>", "Pair<Pair<i8, i8>, Pair<i32, Pair<i32, bool>>>"] {
+        let code = source(&format!("fn f(a: {}) {{}}", ty));
+        let program = parse(&code);
+
+        assert_eq!(parse_errors(&code.code), Vec::<String>::new());
+        assert_eq!(parameter_type(&program).to_string(), ty.replace("Pair", "main.Pair"));
+    }
+}
+
+#[test]
+fn greater_equals_closes_generic_arguments() {
+    let code = "fn f(y: Pair<i32, Pair<i32, bool>>) { let x: Pair<i32, Pair<i32, bool>>= y; }";
+    assert_eq!(parse_errors(code), Vec::<String>::new());
+}
+
+#[test]
+fn the_fuzz_corpus_parses_without_panicking() {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/parse");
+
+    for entry in std::fs::read_dir(corpus).unwrap() {
+        let code = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        parse_errors(&code);
+    }
+}
+
+#[test]
+fn long_chains_within_the_limit_are_dropped_without_recursing() {
+    let code = format!("fn f() => i32 {{ return 1{}; }}", " + 1".repeat(500));
+    let source = source(&code);
+
+    // Dropping each level from within the one above it would overflow this stack.
+    let program = newton_rs::try_parse(&source).unwrap();
+    std::thread::scope(|scope| {
+        std::thread::Builder::new().stack_size(32 * 1024).spawn_scoped(scope, move || drop(program)).unwrap();
+    });
+}
+
+#[test]
+fn finally_is_reserved() {
+    let reserved = "'finally' is a reserved keyword, but it is not supported yet";
+
+    assert_eq!(parse_errors("fn f() { finally {} }"), [reserved]);
+    assert_eq!(
+        parse_errors("fn f() { let a = finally; let b = 1 +; }"),
+        [reserved, "expected an expression, but got ';' instead"]
+    );
+}
+
+#[test]
+fn returns_without_a_value_or_semicolon_are_targeted() {
+    let expected = ["expected an expression or ';' after 'return'"];
+
+    assert_eq!(parse_errors("fn f() => i32 {\n    return\n    let a = 1;\n}"), expected);
+    assert_eq!(parse_errors("fn f() { return }"), expected);
+}
+
+#[test]
+fn disabled_features_are_reported() {
+    let code = "fn f() => (i32, bool) { return (1, true); }";
+    let source = source(code);
+    assert_eq!(parse_errors(code), Vec::<String>::new());
+
+    let options = ParserOptions::default().disable(Feature::Tuples);
+    assert!(!options.is_enabled(Feature::Tuples));
+    assert!(options.is_enabled(Feature::Labels));
+
+    let program = Parser::new_with_options(Lexer::new(&source), options).parse();
+    let mut output = vec![];
+    assert_eq!(newton_rs::report_errors(&source, &program, &mut output).unwrap(), 2);
+    assert!(String::from_utf8(output).unwrap().contains("tuples are not enabled"));
+}
+
+#[test]
+fn only_assignable_expressions_are_incremented() {
+    let error = |operator| {
+        format!(
+            "cannot apply '{}' to this expression, only to variables, fields, array elements and dereferenced pointers",
+            operator
+        )
+    };
+
+    assert_eq!(parse_errors("fn f() { let i = 1; i++; ++i; i--; --i; }"), Vec::<String>::new());
+    assert_eq!(parse_errors("fn f() { let i = 1; i++++; }"), [error("++")]);
+    assert_eq!(parse_errors("fn f() { --(1 + 2); }"), [error("--")]);
+}
+
+#[test]
+fn struct_initializers_in_conditions_need_parentheses() {
+    let code = "fn f(k: i32) { while Pair { key: k } { } }";
+    let diagnostics = parse_diagnostics(code);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "struct initializers in conditions need parentheses, like '(Pair { ... })', the braces are taken for the body \
+         otherwise"
+    );
+
+    // Both suggestions are applied from the back, so the first position stays the same.
+    let mut fixed = code.to_owned();
+    for suggestion in diagnostics[0].suggestions.iter().rev() {
+        fixed.insert_str(suggestion.span.start, &suggestion.replacement);
+    }
+    assert_eq!(fixed, "fn f(k: i32) { while (Pair { key: k }) { } }");
+    assert_eq!(parse_errors(&fixed), Vec::<String>::new());
+
+    assert_eq!(parse_errors("fn f(k: i32) { while k { key: while true {} } }"), Vec::<String>::new());
+}
+
+#[test]
+fn prefix_and_infix_errors_point_at_the_token() {
+    let code = "fn f() { let a = ); }";
+    let diagnostics = parse_diagnostics(code);
+    assert_eq!(diagnostics[0].message, "expected an expression, but got ')' instead");
+
+    // Column 18 is the `)`, which is underlined on its own.
+    let rendered = diagnostics[0].render(&source(code));
+    assert!(rendered.contains("--> main:1:18\n"), "{}", rendered);
+    assert_eq!(rendered.matches('^').count(), 1);
+
+    assert_eq!(parse_errors("fn f() { let a = 1 + ; }"), ["expected an expression, but got ';' instead"]);
+    assert_eq!(parse_errors("fn f() => i32 { return 1 + }"), ["expected an expression, but got '}' instead"]);
+}
+
+#[test]
+fn cfg_checks_for_exactly_one_name() {
+    assert_eq!(parse_errors("@[cfg(c)] fn f() {}"), Vec::<String>::new());
+    assert_eq!(
+        parse_errors("@[cfg(c, llvm)] fn f() {}"),
+        ["'cfg' checks for exactly one name, like '@[cfg(c)]', but got 2"]
+    );
+    assert_eq!(parse_errors("@[cfg] fn f() {}"), ["'cfg' checks for exactly one name, like '@[cfg(c)]', but got 0"]);
+}
+
+#[test]
+fn alignments_are_powers_of_two() {
+    let error = "'align' takes an alignment in bytes that is a power of two, like '@[align(8)]'";
+
+    assert_eq!(parse_errors("@[align(8)] type S struct { @a: i8; }"), Vec::<String>::new());
+    assert_eq!(parse_errors("@[align(12)] type S struct { @a: i8; }"), [error]);
+    assert_eq!(parse_errors("@[align] type S struct { @a: i8; }"), [error]);
+}
+
+#[test]
+fn reporting_errors_counts_them() {
+    let count = |code: &str| {
+        let source = source(code);
+        let program = parse(&source);
+        let mut output = vec![];
+
+        let count = newton_rs::report_errors(&source, &program, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap().matches("error: ").count(), count);
+        count
+    };
+
+    assert_eq!(count("fn f() {}"), 0);
+    assert_eq!(count("fn f() { let a = ; let b = ); } fn g() { let c = ; }"), 3);
+}
+
+#[test]
+fn functions_without_a_return_type_return_void() {
+    let source = source("fn f() {} fn g() => i32 { return 1; }");
+    let program = parse(&source);
+
+    let return_types = program
+        .0
+        .iter()
+        .map(|top_level| match top_level {
+            TopLevel::FunctionDeclaration { return_type, .. } => (return_type.node.to_string(), return_type.span),
+            _ => panic!("expected a function"),
+        })
+        .collect::<Vec<_>>();
+
+    // `f` has no return type to point at, so its name stands in for it.
+    assert_eq!(return_types, [("void".to_owned(), Span::new(3, 3)), ("i32".to_owned(), Span::new(20, 22))]);
+    assert_eq!(parse_errors("fn f() { return; } fn g() => void {}"), Vec::<String>::new());
+}
+
+#[test]
+fn match_statements_have_arms_and_a_default() {
+    let source = source("fn f(x: i32) { match x { case 1: { } case 2 + 3: { return; } default: { } } }");
+    let program = parse(&source);
+
+    let TopLevel::FunctionDeclaration { body, .. } = &program.0[0] else {
+        panic!("expected a function");
+    };
+    let Statement::MatchStatement(statement) = &body.0[0] else {
+        panic!("expected a match");
+    };
+
+    assert_eq!(statement.scrutinee.node.to_string(), "x");
+    let patterns = statement.arms.iter().map(|(pattern, _)| pattern.node.to_string()).collect::<Vec<_>>();
+    assert_eq!(patterns, ["1", "2 + 3"]);
+    let (_, returning) = &statement.arms[1];
+    assert_eq!(returning.0.len(), 1);
+    assert!(statement.default.is_some());
+}
+
+#[test]
+fn match_statements_need_a_case() {
+    assert_eq!(parse_errors("fn f(x: i32) { match x { default: { } } }"), ["'match' needs at least one 'case'"]);
+    assert_eq!(
+        parse_errors("fn f(x: i32) { match x { case 1: { let a = ; } } }"),
+        ["expected an expression, but got ';' instead"]
+    );
+}
+
+#[test]
+fn index_expressions_chain() {
+    let source = source("grid[y + 1][x].field");
+    let expression = Parser::parse_expression_str(&source).unwrap();
+    assert_eq!(expression.node.to_string(), "grid[y + 1][x].field");
+
+    let ExpressionKind::Access { left, .. } = expression.node.kind() else {
+        panic!("expected the field to be accessed last");
+    };
+    let ExpressionKind::Index { array, index } = left.node.kind() else {
+        panic!("expected an index");
+    };
+
+    assert_eq!(index.node.to_string(), "x");
+    assert!(matches!(array.node.kind(), ExpressionKind::Index { .. }));
+    assert_eq!(array.node.sub_expressions().len(), 2);
+}
+
+#[test]
+fn type_declarations_are_displayed_as_written() {
+    let declarations = [
+        "type Point struct { @x: i32; @y: i32; }",
+        "type Pair struct<K, V> { @key: K; @value: V; }",
+        "type Color enum { Red, Green, Blue }",
+        "type Flag enum: u8 { On, Off }",
+        "type Int = i32;",
+        "type Maybe<T> = ?T;",
+    ];
+
+    for code in declarations {
+        let source = source(code);
+        let program = parse(&source);
+        assert_eq!(parse_errors(code), Vec::<String>::new(), "parsing {:?}", code);
+
+        let TopLevel::TypeDeclaration { ty, .. } = &program.0[0] else {
+            panic!("expected a type declaration");
+        };
+        assert_eq!(ty.to_string(), code);
+    }
+}
+
+#[test]
+fn methods_are_left_out_of_displayed_structs() {
+    let source = source("type Counter struct { @count: i32; fn get(self: &Counter) => i32 { return self.count; }; }");
+    let program = parse(&source);
+
+    let TopLevel::TypeDeclaration { ty, .. } = &program.0[0] else {
+        panic!("expected a type declaration");
+    };
+    assert_eq!(ty.to_string(), "type Counter struct { @count: i32; ... }");
+}
@@ -0,0 +1,872 @@
+mod common;
+
+use common::*;
+use newton_rs::ast::ast::*;
+use newton_rs::semantic::consteval::*;
+use newton_rs::semantic::typecheck::*;
+
+#[test]
+fn break_with_a_label_leaves_the_labeled_loop() {
+    // Leaving only the inner loop keeps the outer one running forever, so nothing needs to be returned after it.
+    let inner = "fn f() => i32 { outer: while true { while true { break; } } }";
+    assert_eq!(error_messages(inner), Vec::<String>::new());
+
+    let outer = "fn f() => i32 { outer: while true { while true { break outer; } } }";
+    assert_eq!(
+        error_messages(outer),
+        ["'f' returns 'i32', but the end of its body can be reached without returning a value"]
+    );
+}
+
+#[test]
+fn labels_have_to_exist() {
+    let errors = error_messages("fn f() { outer: while true { break inner; } }");
+
+    assert_eq!(errors, ["use of undeclared label 'inner'"]);
+}
+
+#[test]
+fn discarded_results_of_must_use_functions_warn() {
+    let code = "
+        @[must_use]
+        fn compute() => i32 { return 1; }
+        fn nothing() {}
+
+        fn main() {
+            compute();
+            nothing();
+        }
+    ";
+
+    assert_eq!(warning_messages(code), ["unused result of 'compute', which returns 'i32'"]);
+}
+
+#[test]
+fn discarded_results_warn_for_every_function_if_enabled() {
+    let code = "fn compute() => i32 { return 1; } fn nothing() {} fn main() { compute(); nothing(); }";
+    let options = ResolverOptions {
+        warn_unused_results: true,
+        ..ResolverOptions::default()
+    };
+
+    let warnings = resolve_with_options(code, options, |resolver, _| resolver.warnings().len());
+    assert_eq!(warnings, 1);
+    assert_eq!(warning_messages(code), Vec::<String>::new());
+}
+
+// The type the resolver gave to the variable declared by the first statement of `f`.
+fn first_variable_type(code: &str) -> Option<String> {
+    resolve(code, |_, program| match &body(program, "f").0[0] {
+        Statement::VariableDeclaration(declaration) => {
+            declaration.ty.borrow().as_ref().map(|ty| ty.node.to_string())
+        }
+        _ => panic!("expected a variable declaration"),
+    })
+}
+
+#[test]
+fn array_literals_are_sized_arrays_of_their_elements() {
+    assert_eq!(first_variable_type("fn f() { let a = [1, 2, 3]; }").as_deref(), Some("[3]i32"));
+}
+
+#[test]
+fn array_literals_need_uniform_elements() {
+    let errors = error_messages(r#"fn f() { let a = [1, "x"]; }"#);
+
+    assert_eq!(errors, ["array element must be of type 'i32', but the actual type was 'string'"]);
+}
+
+#[test]
+fn fields_are_accessed_through_a_single_pointer() {
+    let code = "
+        type Pair struct { @key: i32; }
+        fn f(p: *Pair) => i32 { return p.key; }
+    ";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+}
+
+#[test]
+fn fields_are_not_accessed_through_several_pointers() {
+    let code = "
+        type Pair struct { @key: i32; }
+        fn f(pp: **Pair) => i32 { return pp.key; }
+    ";
+
+    assert_eq!(
+        error_messages(code),
+        ["fields cannot be accessed through '**main.Pair' implicitly, dereference it first"]
+    );
+}
+
+#[test]
+fn fields_of_undefined_names_are_reported() {
+    assert_eq!(
+        error_messages("fn f() { let x = undefined.field; }"),
+        ["'undefined' is not defined in the current scope"]
+    );
+}
+
+#[test]
+fn fields_of_globals_have_their_type() {
+    let code = "
+        type Pair struct { @key: i32; }
+        extern let pair: Pair;
+        fn f() { let key = pair.key; }
+    ";
+
+    assert_eq!(first_variable_type(code).as_deref(), Some("i32"));
+}
+
+#[test]
+fn enum_variants_have_the_type_of_their_enum() {
+    let code = "type Color enum { Red, Green } fn f() { let c = Color.Red; }";
+    assert_eq!(first_variable_type(code).as_deref(), Some("main.Color"));
+
+    let code = "type Color enum { Red, Green } fn f() { let c = Color.Blue; }";
+    assert_eq!(error_messages(code), ["'Color' has no variant named 'Blue'"]);
+}
+
+#[test]
+fn bitwise_not_only_applies_to_integers() {
+    assert_eq!(first_variable_type("fn f() { let x = ~5; }").as_deref(), Some("i32"));
+    assert_eq!(
+        error_messages("fn f() { let x = ~1.0; }"),
+        ["cannot use operator '~' on an expression of type 'f64'"]
+    );
+}
+
+#[test]
+fn locals_of_a_function_outlive_its_scopes() {
+    let code = "fn f(count: i32) { let total = count * 2; if true { let done = false; } }";
+
+    let locals = resolve(code, |resolver, _| {
+        let locals = resolver.locals_of(None, "f").expect("'f' must have locals");
+        locals
+            .iter()
+            .filter(|local| local.node.kind == newton_rs::semantic::symtable::SymbolType::Local)
+            .map(|local| (local.node.name.to_owned(), local.node.ty.to_string()))
+            .collect::<Vec<_>>()
+    });
+
+    assert_eq!(locals, [("total".to_owned(), "i32".to_owned()), ("done".to_owned(), "bool".to_owned())]);
+}
+
+// The warnings about assignments used as conditions, leaving out the ones about empty bodies.
+fn assignment_warnings(code: &str) -> Vec<String> {
+    warning_messages(code).into_iter().filter(|warning| warning.starts_with("assignment")).collect()
+}
+
+#[test]
+fn assignments_as_conditions_warn() {
+    assert_eq!(
+        assignment_warnings("fn f() { let done = false; if (done = true) {} }"),
+        ["assignment used as a condition, did you mean '=='? Wrap it in another pair of parentheses if the assignment is intended"]
+    );
+}
+
+#[test]
+fn nested_assignments_in_conditions_do_not_warn() {
+    let code = "
+        fn next() => *i32 { return null; }
+        fn f() { let p: *i32 = null; while ((p = next()) != null) {} }
+    ";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(assignment_warnings(code), Vec::<String>::new());
+}
+
+#[test]
+fn structs_are_returned_by_value() {
+    let pair = "type Pair struct { @key: i32; }";
+
+    let code = format!("{} fn init(key: i32) => Pair {{ return Pair {{ key }}; }}", pair);
+    assert_eq!(error_messages(&code), Vec::<String>::new());
+
+    let code = format!("{} fn init(key: i32) => Pair {{ return new Pair {{ key }}; }}", pair);
+    assert_eq!(
+        error_messages(&code),
+        ["returned value must be of type 'main.Pair', but the actual type was '*main.Pair'"]
+    );
+}
+
+#[test]
+fn bare_returns_need_a_void_function() {
+    assert_eq!(error_messages("fn f() { return; }"), Vec::<String>::new());
+    assert_eq!(
+        error_messages("fn f() => i32 { if true { return; } return 1; }"),
+        ["returned value must be of type 'i32', but the actual type was 'void'"]
+    );
+}
+
+#[test]
+fn undefined_names_are_reported_once() {
+    let code = "fn f() { let a = x; let b = x + 1; g(x); } fn g(a: i32) {}";
+
+    assert_eq!(error_messages(code), ["'x' is not defined in the current scope"]);
+
+    let rendered = resolve(code, |resolver, _| resolver.errors()[0].to_string());
+    assert!(rendered.contains("note: 'x' is used 2 more times, on lines 1, 1"), "{}", rendered);
+}
+
+#[test]
+fn names_are_undefined_per_function() {
+    let code = "fn f() { let x = y; x = 1; } fn g() => i32 { return x; }";
+
+    assert_eq!(
+        error_messages(code),
+        ["'y' is not defined in the current scope", "'x' is not defined in the current scope"]
+    );
+}
+
+#[test]
+fn variables_whose_type_is_unknown_shadow_like_any_other() {
+    let code = "fn f() => bool { let x = true; { let x = y; x = 1; } return x; }";
+
+    assert_eq!(error_messages(code), ["'y' is not defined in the current scope"]);
+}
+
+#[test]
+fn infinite_loops_at_the_end_need_no_return() {
+    let code = "fn f() => i32 { let i = 0; while true { i = i + 1; } }";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+}
+
+#[test]
+fn loops_that_break_fall_through_to_the_end() {
+    let code = "fn f() => i32 { while true { break; } }";
+
+    assert_eq!(
+        error_messages(code),
+        ["'f' returns 'i32', but the end of its body can be reached without returning a value"]
+    );
+}
+
+#[test]
+fn sizeof_takes_types_and_expressions() {
+    assert_eq!(first_variable_type("fn f() { let size = sizeof(i32); }").as_deref(), Some("u64"));
+    assert_eq!(first_variable_type("fn f(x: i64) { let size = sizeof(x); }").as_deref(), Some("u64"));
+}
+
+#[test]
+fn empty_control_flow_bodies_warn() {
+    assert_eq!(warning_messages("fn f(a: bool) { if a {} }"), ["empty body of 'if' statement"]);
+    assert_eq!(warning_messages("fn f(a: bool) { while a {} }"), ["empty body of 'while' statement"]);
+}
+
+#[test]
+fn empty_function_bodies_do_not_warn() {
+    assert_eq!(warning_messages("fn f() {}"), Vec::<String>::new());
+}
+
+#[test]
+fn functions_return_tuples() {
+    let code = "fn find() => (i32, bool) { return (1, true); } fn f() { let found = find(); }";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(first_variable_type(code).as_deref(), Some("(i32, bool)"));
+
+    assert_eq!(
+        error_messages("fn find() => (i32, bool) { return (1, 2); }"),
+        ["tuple element must be of type 'bool', but the actual type was 'i32'"]
+    );
+}
+
+#[test]
+fn exhaustive_enum_matches_do_not_warn() {
+    let code = "
+        type Color enum { Red, Green, Blue }
+        fn f(c: Color) { match c { case Red: { } case Green: { } case Blue: { } } }
+    ";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(warning_messages(code), Vec::<String>::new());
+}
+
+#[test]
+fn enum_matches_missing_a_variant_warn() {
+    let code = "
+        type Color enum { Red, Green, Blue }
+        fn f(c: Color) { match c { case Red: { } case Green: { } } }
+    ";
+    assert_eq!(warning_messages(code), ["match on 'Color' does not handle 'Blue'; add the missing cases or a 'default'"]);
+
+    let code = "
+        type Color enum { Red, Green, Blue }
+        fn f(c: Color) { match c { case Red: { } default: { } } }
+    ";
+    assert_eq!(warning_messages(code), Vec::<String>::new());
+}
+
+#[test]
+fn sized_arrays_decay_to_pointers_in_calls() {
+    let code = "
+        fn takes(p: *i32) {}
+        fn takes_unsized(a: [?]i32) {}
+        fn f(a: [4]i32) { takes(a); takes_unsized(a); }
+    ";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    let code = "fn takes(x: i32) {} fn f(a: [4]i32) { takes(a); }";
+    assert_eq!(error_messages(code), ["argument must be of type 'i32', but the actual type was '[4]i32'"]);
+}
+
+#[test]
+fn casts_to_the_same_type_warn() {
+    assert_eq!(
+        warning_messages("fn f(x: i32) => i32 { return x as i32; }"),
+        ["redundant cast, the expression already is of type 'i32'"]
+    );
+    assert_eq!(warning_messages("fn f(x: i32) => i64 { return x as i64; }"), Vec::<String>::new());
+}
+
+#[test]
+fn strings_are_not_arithmetic() {
+    let errors = error_messages(r#"fn f() { let x = "a" + 1; }"#);
+    assert_eq!(errors, ["binary operation '+' cannot be applied to 'string' and 'i32'"]);
+}
+
+#[test]
+fn pointers_move_by_integers() {
+    assert_eq!(first_variable_type("fn f(p: *u8) { let q = p + 1; }").as_deref(), Some("*u8"));
+    assert_eq!(error_messages("fn f(p: *u8) { let q = p + 1; }"), Vec::<String>::new());
+}
+
+#[test]
+fn extern_globals_can_be_referenced() {
+    let code = "extern let errno: i32; fn f() { let error = errno; }";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(first_variable_type(code).as_deref(), Some("i32"));
+}
+
+const PAIR: &str = "
+    type Pair struct<K, V> {
+        @key: K;
+        @value: V;
+
+        fn get_key(self: &Pair) => K {
+            return self.key;
+        };
+    }
+";
+
+#[test]
+fn self_is_the_generic_instance_in_methods_of_generic_structs() {
+    let key_type = resolve(PAIR, |_, program| {
+        let TopLevel::TypeDeclaration {
+            ty: TypeDeclaration::StructDefinition { methods, .. },
+            ..
+        } = &program.0[0]
+        else {
+            panic!("expected a struct");
+        };
+        let TopLevel::FunctionDeclaration { body, .. } = &methods[0] else {
+            panic!("expected a method");
+        };
+        let Statement::ReturnStatement(_, Some(value)) = &body.0[0] else {
+            panic!("expected 'get_key' to return a value");
+        };
+
+        value.node.clone_ty().map(|ty| ty.to_string())
+    });
+
+    assert_eq!(key_type.as_deref(), Some("K"));
+    assert_eq!(error_messages(PAIR), Vec::<String>::new());
+}
+
+#[test]
+fn fields_of_generic_instances_have_the_type_arguments() {
+    let code = format!("{} fn f(pair: Pair<i32, string>) {{ let key = pair.key; }}", PAIR);
+
+    assert_eq!(first_variable_type(&code).as_deref(), Some("i32"));
+}
+
+#[test]
+fn groupings_have_the_type_of_their_expression() {
+    assert_eq!(first_variable_type("fn f(a: i64) { let x = (a + 1) * 2; }").as_deref(), Some("i64"));
+}
+
+#[test]
+fn fields_named_like_methods_warn() {
+    let code = "type Box struct {\n    @get: i32;\n    fn get(self: &Box) => i32 { return 1; };\n}";
+
+    assert_eq!(
+        warning_messages(code),
+        ["field 'get' of 'Box' has the same name as a method, so '.get' is ambiguous"]
+    );
+
+    let rendered = resolve(code, |resolver, _| resolver.warnings()[0].to_string());
+    assert!(rendered.contains("--> main:2:6"));
+    assert!(rendered.contains("the method 'get' is declared on line 3"));
+}
+
+#[test]
+fn every_independent_error_is_reported() {
+    // `a` has no type once its initializer fails, which must not cause another error where it is used.
+    let code = r#"fn f() { let a = "x" + 1; let b = ~1.0; let d = a * 2; } fn g() { let c = "y" - 2; }"#;
+    assert_eq!(
+        error_messages(code),
+        [
+            "binary operation '+' cannot be applied to 'string' and 'i32'",
+            "cannot use operator '~' on an expression of type 'f64'",
+            "binary operation '-' cannot be applied to 'string' and 'i32'",
+        ]
+    );
+
+    let options = ResolverOptions {
+        max_errors: Some(2),
+        ..ResolverOptions::default()
+    };
+    let errors = resolve_with_options(code, options, |resolver, _| resolver.errors().len());
+    assert_eq!(errors, 2);
+}
+
+const CALLS: &str = "
+    fn main() { helper(); }
+    fn helper() { leaf(); }
+    fn leaf() {}
+    fn unused() { leaf(); }
+";
+
+#[test]
+fn functions_main_reaches_are_kept() {
+    let source = source(CALLS);
+    let program = parse(&source);
+    let graph = newton_rs::semantic::callgraph::CallGraph::new("main", &program);
+
+    assert!(graph.is_reachable(None, "main"));
+    assert!(graph.is_reachable(None, "helper"));
+    assert!(graph.is_reachable(None, "leaf"));
+    assert!(!graph.is_reachable(None, "unused"));
+
+    let unused: Vec<_> = graph.unused_functions().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(unused, [(None, "unused")]);
+}
+
+#[test]
+fn uncalled_private_functions_warn_if_enabled() {
+    let options = ResolverOptions {
+        warn_unused_functions: true,
+        ..ResolverOptions::default()
+    };
+    let warnings = resolve_with_options(CALLS, options, |resolver, _| {
+        resolver.warnings().iter().map(|warning| headline(&warning.to_string())).collect::<Vec<_>>()
+    });
+
+    assert_eq!(warnings, ["function 'unused' is never used"]);
+    assert_eq!(warning_messages(CALLS), Vec::<String>::new());
+}
+
+#[test]
+fn strings_are_concatenated_with_plus() {
+    assert_eq!(first_variable_type(r#"fn f() { let s = "a" + "b"; }"#).as_deref(), Some("string"));
+    assert_eq!(
+        error_messages(r#"fn f() { let s = "a" - "b"; }"#),
+        ["binary operation '-' cannot be applied to 'string' and 'string'"]
+    );
+}
+
+#[test]
+fn values_are_assigned_through_pointers_and_to_fields() {
+    let code = "
+        type Pair struct { @key: i32; }
+        fn f(p: *i32, pair: Pair, q: *Pair) { *p = 5; pair.key = 6; q.key = *p; }
+    ";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    assert_eq!(
+        error_messages("fn f(p: *i32) { *p = true; }"),
+        ["assigned value must be of type 'i32', but the actual type was 'bool'"]
+    );
+}
+
+#[test]
+fn numbers_only_widen_implicitly() {
+    let code = "fn f(x: i32, u: u8, d: f32) { let a: i64 = x; let b: i16 = u; let c: f64 = d; let e: f64 = x; }";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    assert_eq!(
+        error_messages("fn f(x: i32, d: f64) { let a: i16 = x; let b: u32 = x; let c: f32 = d; let e: i64 = d; }"),
+        [
+            "initial value must be of type 'i16', but the actual type was 'i32'",
+            "initial value must be of type 'u32', but the actual type was 'i32'",
+            "initial value must be of type 'f32', but the actual type was 'f64'",
+            "initial value must be of type 'i64', but the actual type was 'f64'",
+        ]
+    );
+}
+
+#[test]
+fn literals_take_on_any_type_they_fit() {
+    let code = "fn f() { let a: u8 = 200; let b: f32 = 1; let c: [2]u8 = [1, 2]; }";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(error_messages("fn f() { let a: u8 = 300; }"), ["'300' is out of the range of 'u8'"]);
+}
+
+#[test]
+fn declared_types_are_checked() {
+    assert_eq!(
+        error_messages("fn f() { let z: bool = 5; }"),
+        ["initial value must be of type 'bool', but the actual type was 'i32'"]
+    );
+}
+
+#[test]
+fn conditions_have_to_be_bool() {
+    assert_eq!(error_messages("fn f(x: i32) { if (x > 0) {} }"), Vec::<String>::new());
+    assert_eq!(
+        error_messages("fn f() { if (5) {} }"),
+        ["condition must be of type 'bool', but the actual type was 'i32'"]
+    );
+
+    let options = ResolverOptions {
+        truthy_conditions: true,
+        ..ResolverOptions::default()
+    };
+    let code = "fn f(p: *i32) { if (5) {} while (p) {} }";
+    assert_eq!(resolve_with_options(code, options, |resolver, _| resolver.errors().len()), 0);
+}
+
+#[test]
+fn constant_conditions_warn() {
+    assert_eq!(
+        warning_messages("fn f() { if (false) { let a = 1; } else { let b = 2; } }"),
+        ["the body of this 'if' is never run, since the condition is always 'false'"]
+    );
+    assert_eq!(
+        warning_messages("fn f() { while (false) { let a = 1; } }"),
+        ["the body of this 'while' is never run, since the condition is always 'false'"]
+    );
+    assert_eq!(
+        warning_messages("fn f() { if (true) { let a = 1; } else { let b = 2; } }"),
+        ["this 'else' branch is never run, since the condition is always 'true'"]
+    );
+}
+
+#[test]
+fn illegal_operations_point_at_the_operator() {
+    let rendered = resolve(r#"fn f() { let x = "a" - 1; }"#, |resolver, _| resolver.errors()[0].to_string());
+
+    // Column 22 is the `-`, which is underlined on its own.
+    assert!(rendered.contains("--> main:1:22\n"));
+    assert_eq!(rendered.matches('^').count(), 1);
+}
+
+#[test]
+fn chained_comparisons_are_rejected() {
+    assert_eq!(
+        error_messages("fn f(a: i32, b: i32, c: i32) { let x = a < b < c; }"),
+        ["comparisons cannot be chained, write 'a < b && b < c' instead of 'a < b < c'"]
+    );
+    assert_eq!(
+        error_messages("fn f(x: i32, y: i32, z: i32) { let w = x <= y > z; }"),
+        ["comparisons cannot be chained, write 'a <= b && b > c' instead of 'a <= b > c'"]
+    );
+    assert_eq!(error_messages("fn f(a: i32, b: i32, c: i32) { let x = a < b && b < c; }"), Vec::<String>::new());
+}
+
+#[test]
+fn literals_take_on_the_type_of_their_context() {
+    assert_eq!(first_variable_type("fn f() { let x: u8 = 255; }").as_deref(), Some("u8"));
+    assert_eq!(error_messages("fn f() { let x: u8 = 255; }"), Vec::<String>::new());
+
+    let literal_type = resolve("fn f() { let x: f32 = 1; }", |_, program| match &body(program, "f").0[0] {
+        Statement::VariableDeclaration(declaration) => declaration.value.node.clone_ty().map(|ty| ty.to_string()),
+        _ => panic!("expected a variable declaration"),
+    });
+    assert_eq!(literal_type.as_deref(), Some("f32"));
+
+    assert_eq!(first_variable_type("fn f() { let y = 1; }").as_deref(), Some("i32"));
+    assert_eq!(first_variable_type("fn f() { let y = 1.5; }").as_deref(), Some("f64"));
+}
+
+#[test]
+fn new_struct_initializations_work_in_conditions() {
+    let pair = "type Pair struct { @key: i32; @value: bool; }";
+
+    let code = format!("{} fn f(k: i32, v: bool) {{ while new Pair {{ key: k, value: v }} != null {{}} }}", pair);
+    assert_eq!(error_messages(&code), Vec::<String>::new());
+
+    let code = format!("{} fn f(k: i32) {{ let p = new Pair {{ key: k, value: k }}; }}", pair);
+    assert_eq!(error_messages(&code), ["field value must be of type 'bool', but the actual type was 'i32'"]);
+
+    let code = format!("{} fn f(k: i32, v: bool) {{ let p = new Pair {{ key: k, value: v }}; }}", pair);
+    assert_eq!(first_variable_type(&code).as_deref(), Some("*main.Pair"));
+}
+
+#[test]
+fn struct_initializations_need_a_struct() {
+    assert_eq!(error_messages("fn f() { let q = new Q { a: 1 }; }"), ["there is no struct named 'Q'"]);
+
+    let code = "type Color enum { Red, Green } fn f() { let c = new Color { a: 1 }; }";
+    assert_eq!(error_messages(code), ["there is no struct named 'Color'"]);
+}
+
+#[test]
+fn struct_initializations_give_every_field_a_value() {
+    let code = "type P struct { @a: i32; @b: i32; @c: bool; } fn f() { let p = new P { a: 1 }; }";
+    assert_eq!(error_messages(code), ["initialization of 'P' is missing the fields 'b', 'c'"]);
+
+    let code = "type P struct { @a: i32; @b: i32; } fn f() { let p = new P { b: 2 }; }";
+    assert_eq!(error_messages(code), ["initialization of 'P' is missing the field 'a'"]);
+}
+
+#[test]
+fn functions_without_any_return_warn_as_stubs() {
+    // Looping forever is the only way not to reach the end of a body without returning.
+    let stub = "fn f() => i32 { while true { let a = 1; } }";
+    let stub_warning = "'f' returns 'i32', but there is no 'return' in it at all, it may still be a stub".to_owned();
+    assert!(warning_messages(stub).contains(&stub_warning));
+
+    let returns = "fn f() => i32 { while true { return 1; } }";
+    assert!(!warning_messages(returns).contains(&stub_warning));
+}
+
+#[test]
+fn missing_returns_are_not_reported_as_stubs_as_well() {
+    let code = "fn f() => i32 { let a = 1; }";
+
+    assert_eq!(
+        error_messages(code),
+        ["'f' returns 'i32', but the end of its body can be reached without returning a value"]
+    );
+    assert_eq!(warning_messages(code), Vec::<String>::new());
+}
+
+#[test]
+fn underscores_discard_values() {
+    let code = "
+        @[must_use]
+        fn compute() => i32 { return 1; }
+
+        fn main(_: i32, _: bool) {
+            let _ = compute();
+            let _ = compute();
+        }
+    ";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(warning_messages(code), Vec::<String>::new());
+
+    let errors = error_messages("fn f() { let x = 1; let _ = 2; x = _; }");
+    assert_eq!(errors, ["'_' only discards values, it cannot be read"]);
+}
+
+#[test]
+fn arithmetic_promotes_to_the_wider_type() {
+    assert_eq!(first_variable_type("fn f(a: i16, b: i32) { let c = a + b; }").as_deref(), Some("i32"));
+    assert_eq!(first_variable_type("fn f(a: i32, b: f32) { let c = a * b; }").as_deref(), Some("f32"));
+    assert_eq!(first_variable_type("fn f(a: f64, b: f32) { let c = b - a; }").as_deref(), Some("f64"));
+    assert_eq!(first_variable_type("fn f(a: u8, b: i32) { let c = a + b; }").as_deref(), Some("i32"));
+    assert_eq!(warning_messages("fn f(a: u8, b: i32) { let c = a + b; }"), Vec::<String>::new());
+
+    let code = "fn f(a: u32, b: i32) { let c = a + b; }";
+    assert_eq!(first_variable_type(code).as_deref(), Some("u32"));
+    assert_eq!(
+        warning_messages(code),
+        ["'u32' and 'i32' differ in signedness, the result is 'u32', which cannot hold every value of both"]
+    );
+}
+
+#[test]
+fn constants_can_refer_to_later_ones() {
+    let code = "let A = B + 1; let B = 2; fn f() => i32 { return A; }";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    resolve(code, |resolver, _| {
+        let value = |name| resolver.module_map().get_global("main", name).unwrap().value();
+
+        assert_eq!(value("A"), Some(ConstValue::Integer(3)));
+        assert_eq!(value("B"), Some(ConstValue::Integer(2)));
+    });
+}
+
+#[test]
+fn constants_cannot_depend_on_themselves() {
+    assert_eq!(error_messages("let A = B; let B = A;"), ["the value of constant 'A' depends on itself: A -> B -> A"]);
+    assert_eq!(error_messages("let A = A + 1;"), ["the value of constant 'A' depends on itself: A -> A"]);
+}
+
+#[test]
+fn increments_are_statements_of_their_own() {
+    let code = "fn f(p: *i32) { let i = 1; let j: u8 = 2; i++; --j; p++; }";
+    let options = ResolverOptions {
+        warn_unused_results: true,
+        ..ResolverOptions::default()
+    };
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+    assert_eq!(resolve_with_options(code, options, |resolver, _| resolver.warnings().len()), 0);
+    assert_eq!(first_variable_type("fn f(a: u16) { let b = a++; }").as_deref(), Some("u16"));
+
+    let errors = error_messages("fn f() { let b = true; b++; }");
+    assert_eq!(errors, ["cannot use operator '++' on an expression of type 'bool'"]);
+}
+
+#[test]
+fn blocks_yield_their_final_expression() {
+    let code = "fn f() { let x = { let a = 1; a + 1 }; }";
+    assert_eq!(first_variable_type(code).as_deref(), Some("i32"));
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    let code = "fn g() {} fn f() { let x = { g(); }; }";
+    assert_eq!(first_variable_type(code).as_deref(), Some("void"));
+
+    // Variables declared in the block are gone after it.
+    let errors = error_messages("fn f() { let x = { let a = 1; a }; let y = a; }");
+    assert_eq!(errors, ["'a' is not defined in the current scope"]);
+}
+
+#[test]
+fn generic_structs_take_every_type_argument_or_none() {
+    let with = |signature: &str| error_messages(&format!("{} fn f({}) {{}}", PAIR, signature));
+
+    assert_eq!(with("p: Pair<i32>"), ["expected 2 type arguments for 'Pair', found 1"]);
+    assert_eq!(with("p: *Pair<i32, bool, u8>"), ["expected 2 type arguments for 'Pair', found 3"]);
+    assert_eq!(with("p: Pair<i32, bool>"), Vec::<String>::new());
+    assert_eq!(with("p: *Pair"), Vec::<String>::new());
+}
+
+#[test]
+fn errors_point_at_the_right_line_with_crlf_line_endings() {
+    let code = "fn f() {\r\n    // a comment\r\n    let x = \"a\" - 1;\r\n}\r\n";
+    let rendered = resolve(code, |resolver, _| resolver.errors()[0].to_string());
+
+    // Line 3, column 17 is the `-`, which is underlined on its own without counting the `\r` of the lines before.
+    assert!(rendered.contains("--> main:3:17\n"), "{}", rendered);
+    assert_eq!(rendered.matches('^').count(), 1);
+    assert!(!rendered.contains('\r'));
+
+    let code = "fn f() {\r    let x = \"a\" - 1;\r}";
+    let rendered = resolve(code, |resolver, _| resolver.errors()[0].to_string());
+    assert!(rendered.contains("--> main:2:17\n"), "{}", rendered);
+}
+
+#[test]
+fn scopes_are_closed_after_returning_from_nested_blocks() {
+    let code = "
+        fn f(a: i32) => i32 {
+            while a > 0 {
+                if a > 1 { let b = a; return b; } else { while true { return 0; } }
+            }
+            return a;
+        }
+        fn g() => i32 { let b = true; return f(1); }
+    ";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    // The error statement the parser recovers with is resolved in its scope like any other.
+    let source = source("fn f(a: i32) { if a > 0 { while true { let b = ; return; } } } fn g() { let b = a; }");
+    let program = parse(&source);
+    let mut resolver = Resolver::new(&source);
+    resolver.resolve(&program);
+
+    let errors = resolver.errors().iter().map(|error| headline(&error.to_string())).collect::<Vec<String>>();
+    assert_eq!(errors, ["'a' is not defined in the current scope"]);
+}
+
+#[test]
+fn literals_without_a_type_to_take_on_are_as_wide_as_they_need() {
+    assert_eq!(first_variable_type("fn f() { let x = 2147483647; }").as_deref(), Some("i32"));
+    assert_eq!(first_variable_type("fn f() { let x = 3000000000; }").as_deref(), Some("i64"));
+    assert_eq!(first_variable_type("fn f() { let x = -3000000000; }").as_deref(), Some("i64"));
+    assert_eq!(first_variable_type("fn f() { let x = 10000000000000000000; }").as_deref(), Some("u64"));
+
+    let errors = error_messages("fn f() { let x = 99999999999999999999; }");
+    assert_eq!(errors, ["'99999999999999999999' is out of the range of 'u64'"]);
+    assert_eq!(error_messages("fn f() { let x: i32 = 3000000000; }"), ["'3000000000' is out of the range of 'i32'"]);
+    assert_eq!(error_messages("fn f() { let x: i64 = 3000000000; }"), Vec::<String>::new());
+}
+
+#[test]
+fn methods_call_sibling_methods_through_self() {
+    let code = "
+        type Entry struct {
+            @key: i32;
+            @value: i32;
+            fn get_value(self: &Entry, offset: i32) => i32 { return self.value + offset; };
+            fn get_key(self: &Entry) => i32 { let value = self.get_value(1); return self.key + value; };
+        }
+    ";
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    let errors = error_messages(&code.replace("self.get_value(1)", "self.get_value()"));
+    assert_eq!(errors, ["'get_value' takes 1 argument, but 0 were given"]);
+
+    let errors = error_messages(&code.replace("self.get_value(1)", "self.get_other(1)"));
+    assert_eq!(errors, ["'Entry' has no method named 'get_other'"]);
+}
+
+const COLOR: &str = "type Color enum: u8 { Red, Green, Blue }";
+
+#[test]
+fn enums_are_cast_to_and_from_integers() {
+    let code = format!("{} fn f(c: Color) {{ let x = c as u8; let y = c as i32; let z = 2 as Color; }}", COLOR);
+    assert_eq!(first_variable_type(&code).as_deref(), Some("u8"));
+    assert_eq!(error_messages(&code), Vec::<String>::new());
+    assert_eq!(warning_messages(&code), Vec::<String>::new());
+
+    let errors = error_messages(&format!("{} fn f(c: Color) {{ let x = c as bool; }}", COLOR));
+    assert_eq!(errors, ["cannot cast 'main.Color' to 'bool', enums can only be cast to and from integers"]);
+}
+
+#[test]
+fn unlisted_values_cast_to_enums_warn_if_checked() {
+    let code = format!("{} fn f() {{ let c = 7 as Color; }}", COLOR);
+    let options = ResolverOptions {
+        checked_enum_casts: true,
+        ..ResolverOptions::default()
+    };
+
+    assert_eq!(warning_messages(&code), Vec::<String>::new());
+    let warnings = resolve_with_options(&code, options, |resolver, _| {
+        resolver.warnings().iter().map(|warning| headline(&warning.to_string())).collect::<Vec<String>>()
+    });
+    assert_eq!(warnings, ["no variant of 'Color' has the value 7, only 0 to 2 are valid"]);
+
+    let errors = error_messages(&format!("{} fn f() {{ let c = 300 as Color; }}", COLOR));
+    assert_eq!(errors, ["'300' is out of the range of 'u8'"]);
+}
+
+#[test]
+fn definitions_know_where_they_were_declared() {
+    let code = "fn helper() {}\ntype Point struct { @x: i32; }";
+
+    let (function, user_type) = resolve(code, |resolver, _| {
+        let module_map = resolver.module_map();
+        (
+            module_map.get_function("main", "helper").unwrap().span(),
+            module_map.get_user_type("main", "Point").unwrap().span,
+        )
+    });
+
+    assert_eq!(&code[function.start..=function.end], "helper");
+    assert_eq!(&code[user_type.start..=user_type.end], "Point");
+}
+
+#[test]
+fn arrays_and_pointers_are_indexed_by_integers() {
+    assert_eq!(first_variable_type("fn f(grid: [3][4]i32) { let a = grid[1][2]; }").as_deref(), Some("i32"));
+    assert_eq!(first_variable_type("fn f(grid: [3][4]i32) { let a = grid[1]; }").as_deref(), Some("[4]i32"));
+    assert_eq!(first_variable_type("fn f(p: **u8, i: u64) { let a = p[i]; }").as_deref(), Some("*u8"));
+
+    let errors = error_messages("fn f(a: i32) { let b = a[0]; }");
+    assert_eq!(errors, ["'i32' cannot be indexed, only arrays and pointers can"]);
+
+    let errors = error_messages("fn f(a: [2]i32) { let b = a[true]; }");
+    assert_eq!(errors, ["indices must be integers, but this one is of type 'bool'"]);
+}
+
+#[test]
+fn nested_functions_are_called_like_any_other() {
+    let code = "fn f() => i32 { fn twice(x: i32) => i32 { return x * 2; } let a = twice(2); return twice(a); }";
+
+    assert_eq!(error_messages(code), Vec::<String>::new());
+
+    let errors = error_messages("fn f() { let a = 1; fn g() => i32 { return a; } }");
+    assert_eq!(errors, ["nested function 'g' cannot use 'a', which belongs to the function it is declared in"]);
+
+    // Nested functions are only known inside the function they are declared in.
+    let errors = error_messages("fn f() { fn g() {} } fn h() { g(); }");
+    assert_eq!(errors, ["'g' is not defined in the current scope"]);
+}
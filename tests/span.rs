@@ -0,0 +1,58 @@
+use newton_rs::parser::span::*;
+
+#[test]
+fn map_keeps_the_span() {
+    let spanned = Spanned::new(3, 7, "42");
+    let mapped = spanned.map(|text| text.parse::<i32>().unwrap());
+
+    assert_eq!(mapped.node, 42);
+    assert_eq!(mapped.span, Span::new(3, 7));
+}
+
+#[test]
+fn as_ref_and_as_mut_borrow_the_node() {
+    let mut spanned = Spanned::new(1, 2, String::from("name"));
+
+    let borrowed = spanned.as_ref();
+    assert_eq!(borrowed.node, "name");
+    assert_eq!(borrowed.span, Span::new(1, 2));
+
+    spanned.as_mut().node.push('s');
+    assert_eq!(spanned.node, "names");
+    assert_eq!(spanned.span, Span::new(1, 2));
+}
+
+#[test]
+fn snippets_include_the_lines_around_the_span() {
+    let source = newton_rs::Source::new("main", "fn f() {\n    let x = 1;\n}\n\nfn g() {}\n");
+    let start = source.code.find("x").unwrap();
+
+    let snippet = source.snippet(Span::new(start, start + 4), 1);
+
+    assert_eq!(snippet.text, "x = 1");
+    assert_eq!(snippet.first_line, 1);
+    assert_eq!(snippet.lines, ["fn f() {", "    let x = 1;", "}"]);
+}
+
+#[test]
+fn snippets_stay_on_character_boundaries() {
+    let source = newton_rs::Source::new("main", "let ä = 1;");
+
+    let snippet = source.snippet(Span::new(5, 100), 0);
+
+    assert_eq!(snippet.text, "ä = 1;");
+    assert_eq!(snippet.lines, ["let ä = 1;"]);
+}
+
+#[test]
+fn snippets_count_every_kind_of_line_ending() {
+    for code in ["fn f() {\r\n    let x = 1;\r\n}\r\n", "fn f() {\r    let x = 1;\r}\r"] {
+        let source = newton_rs::Source::new("main", code);
+        let start = source.code.find("x").unwrap();
+
+        let snippet = source.snippet(Span::new(start, start + 4), 1);
+
+        assert_eq!(snippet.first_line, 1);
+        assert_eq!(snippet.lines, ["fn f() {", "    let x = 1;", "}"]);
+    }
+}